@@ -17,7 +17,12 @@ use crate::{
     circuits::{self, CircuitPreview, CircuitPreviewImpl},
     error::{ErrorList, ResultReport},
     evenly_spaced_out,
-    ui::{editor::CircuitBoardEditor, side_panel::PanelSide},
+    ui::{
+        editor::CircuitBoardEditor,
+        keybinds::Keybinds,
+        side_panel::PanelSide,
+        theme::{CanvasColors, ThemePreset},
+    },
     wires::WireColors,
     DynStaticStr, RwLock,
 };
@@ -28,6 +33,66 @@ pub struct SimulationContext {
 }
 
 impl SimulationContext {
+    pub fn builtin_previews() -> Vec<Box<dyn CircuitPreviewImpl>> {
+        vec![
+            Box::new(circuits::button::ButtonPreview {}) as Box<dyn CircuitPreviewImpl>,
+            Box::new(circuits::led::LedPreview {}) as Box<dyn CircuitPreviewImpl>,
+            Box::new(circuits::gates::gate::GatePreview::<circuits::gates::or::Or>::new()),
+            Box::new(circuits::gates::gate::GatePreview::<
+                circuits::gates::xor::Xor,
+            >::new()),
+            Box::new(circuits::gates::gate::GatePreview::<
+                circuits::gates::nor::Nor,
+            >::new()),
+            Box::new(circuits::gates::gate::GatePreview::<
+                circuits::gates::xnor::Xnor,
+            >::new()),
+            Box::new(circuits::gates::gate::GatePreview::<
+                circuits::gates::and::And,
+            >::new()),
+            Box::new(circuits::gates::gate::GatePreview::<
+                circuits::gates::nand::Nand,
+            >::new()),
+            Box::new(circuits::gates::gate::Gate2497Preview),
+            Box::new(circuits::gates::not::NotPreview {}),
+            Box::new(circuits::pullup::PullupPreview {}),
+            Box::new(circuits::tunnel::TunnelPreview {}),
+            Box::new(circuits::transistor::TransistorPreview {}),
+            Box::new(circuits::relay::RelayPreview {}),
+            Box::new(circuits::freq_meter::FreqMeterPreview {}),
+            Box::new(circuits::probe::ProbePreview {}),
+            Box::new(circuits::pin::Preview {}),
+            Box::new(circuits::bundler::Preview {}),
+            Box::new(circuits::splitter::SplitterPreview {}),
+            Box::new(circuits::lookup_table::LookupTablePreview {}),
+            Box::new(circuits::expression::ExpressionPreview {}),
+            Box::new(circuits::script::ScriptPreview {}),
+            Box::new(circuits::clock::Preview {}),
+            Box::new(circuits::random::RandomPreview {}),
+            Box::new(circuits::random::LfsrPreview {}),
+            Box::new(circuits::pulse::ClockDividerPreview {}),
+            Box::new(circuits::pulse::OneShotPreview {}),
+            Box::new(circuits::pulse::EdgeDetectorPreview {}),
+            Box::<circuits::board::BoardPreview>::default(),
+        ]
+    }
+
+    /// Context with only built-in circuits and no boards
+    #[cfg(test)]
+    pub fn with_builtin_previews() -> Arc<Self> {
+        let previews = Self::builtin_previews()
+            .into_iter()
+            .map(|p| {
+                let p = CircuitPreview::from_impl(p);
+                (p.imp.type_name(), Arc::new(p))
+            })
+            .collect();
+        Arc::new(Self {
+            previews,
+            boards: Default::default(),
+        })
+    }
+
     pub fn reset(&self) {
         for board in self.boards.write().drain() {
             board.1.board.destroy();
@@ -35,14 +100,74 @@ impl SimulationContext {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Style {
     pub egui_style: Arc<egui::Style>,
     #[serde(default)]
     pub wire_colors: WireColors,
+    #[serde(default)]
+    pub canvas_colors: CanvasColors,
 }
 
 impl Style {
+    pub fn canvas_background_color(&self) -> Color32 {
+        self.canvas_colors
+            .background
+            .unwrap_or(self.egui_style.visuals.panel_fill)
+    }
+
+    pub fn grid_color(&self) -> Color32 {
+        self.canvas_colors
+            .grid
+            .unwrap_or(match self.egui_style.visuals.dark_mode {
+                true => Color32::from_gray(64),
+                false => Color32::from_gray(192),
+            })
+    }
+
+    pub fn grid_highlight_color(&self) -> Color32 {
+        self.canvas_colors
+            .grid_highlight
+            .unwrap_or(match self.egui_style.visuals.dark_mode {
+                true => Color32::from_gray(96),
+                false => Color32::from_gray(160),
+            })
+    }
+
+    pub fn origin_cross_color(&self) -> Color32 {
+        self.canvas_colors
+            .origin_cross
+            .unwrap_or(match self.egui_style.visuals.dark_mode {
+                true => Color32::WHITE,
+                false => Color32::from_gray(32),
+            })
+    }
+
+    pub fn component_fill_color(&self) -> Color32 {
+        self.canvas_colors
+            .component_fill
+            .unwrap_or(Color32::from_gray(200))
+    }
+
+    pub fn component_border_color(&self) -> Color32 {
+        self.canvas_colors
+            .component_border
+            .unwrap_or(Color32::BLACK)
+    }
+
+    /// Color of pin markers on circuit previews and in the designer
+    pub fn pin_color(&self) -> Color32 {
+        self.canvas_colors
+            .pin
+            .unwrap_or_else(|| self.wire_colors.false_color())
+    }
+
+    pub fn pin_label_color(&self) -> Color32 {
+        self.canvas_colors
+            .pin_label
+            .unwrap_or_else(|| self.egui_style.visuals.text_color())
+    }
+
     pub fn selection_fill_color(&self) -> Color32 {
         self.egui_style
             .visuals
@@ -54,6 +179,15 @@ impl Style {
     pub fn selection_border_color(&self) -> Color32 {
         self.egui_style.visuals.selection.stroke.color
     }
+
+    pub fn net_highlight_color(&self) -> Color32 {
+        self.canvas_colors
+            .net_highlight
+            .unwrap_or(match self.egui_style.visuals.dark_mode {
+                true => Color32::from_rgb(0, 200, 255),
+                false => Color32::from_rgb(0, 110, 220),
+            })
+    }
 }
 pub struct App {
     editor: crate::ui::editor::CircuitBoardEditor,
@@ -103,7 +237,8 @@ impl eframe::App for App {
         #[cfg(feature = "wasm")]
         {
             if let Some((name, string)) = crate::web::take_state_input() {
-                let state = ron::from_str::<crate::io::SaveStateData>(&string).report_error(
+                let state = crate::io::migration::load_save(
+                    &string,
                     &mut self
                         .state_loading_errors
                         .enter_context(|| "deserializing savestate"),
@@ -143,7 +278,8 @@ impl eframe::App for App {
             })
         });
         if let Some((name, string)) = dropped_file {
-            let state = ron::from_str::<crate::io::SaveStateData>(&string).report_error(
+            let state = crate::io::migration::load_save(
+                &string,
                 &mut self
                     .state_loading_errors
                     .enter_context(|| "deserializing savestate"),
@@ -155,11 +291,15 @@ impl eframe::App for App {
         }
 
         egui::CentralPanel::default()
-            .frame(egui::Frame::central_panel(ctx.style().as_ref()).inner_margin(Margin::same(0.0)))
+            .frame(
+                egui::Frame::central_panel(ctx.style().as_ref())
+                    .inner_margin(Margin::same(0.0))
+                    .fill(self.style.canvas_background_color()),
+            )
             .show(ctx, |ui| {
                 match &mut self.designer {
                     Some(designer) => {
-                        designer.background_update(&self.style, ui);
+                        designer.background_update(&self.style, &self.editor.keybinds, ui);
                     }
                     None => {
                         self.editor.background_update(&self.style, ui);
@@ -295,9 +435,12 @@ impl eframe::App for App {
                                             board.board.activate();
                                         }
 
-                                        let board = boards.values().next().expect("Boards must exist!");
-                                        self.editor.board =
-                                            EditableCircuitBoard::new_main(board.board.clone());
+                                        let board = state.1.boards
+                                            .first()
+                                            .and_then(|data| boards.get(&data.uid))
+                                            .or_else(|| boards.values().next())
+                                            .expect("Boards must exist!");
+                                        self.editor.reset_tabs(board.board.clone());
 
                                         discard_state = true;
                                     }
@@ -327,6 +470,15 @@ impl eframe::App for App {
             storage.set_string("style", style);
         }
 
+        let keybinds = ron::to_string(&self.editor.keybinds).report_error(
+            &mut self
+                .state_saving_errors
+                .enter_context(|| "serializing keybinds"),
+        );
+        if let Some(keybinds) = keybinds {
+            storage.set_string("keybinds", keybinds);
+        }
+
         if self.state_loading_errors.is_empty() {
             let boards = self.save_boards();
             if let Some(string) = ron::to_string(&boards).report_error(
@@ -335,6 +487,10 @@ impl eframe::App for App {
                     .enter_context(|| "serializing savestate"),
             ) {
                 storage.set_string("boards", string);
+                storage.set_string(
+                    "boards_version",
+                    crate::io::migration::SAVE_VERSION.to_string(),
+                );
             }
 
             let previews = crate::io::CircuitPreviewCollectionData(HashMap::from_iter(
@@ -350,37 +506,23 @@ impl eframe::App for App {
 
 impl App {
     pub fn create(cc: &CreationContext) -> Self {
-        let previews = [
-            Box::new(circuits::button::ButtonPreview {}) as Box<dyn CircuitPreviewImpl>,
-            Box::new(circuits::led::LedPreview {}) as Box<dyn CircuitPreviewImpl>,
-            Box::new(circuits::gates::gate::GatePreview::<circuits::gates::or::Or>::new()),
-            Box::new(circuits::gates::gate::GatePreview::<
-                circuits::gates::xor::Xor,
-            >::new()),
-            Box::new(circuits::gates::gate::GatePreview::<
-                circuits::gates::nor::Nor,
-            >::new()),
-            Box::new(circuits::gates::gate::GatePreview::<
-                circuits::gates::xnor::Xnor,
-            >::new()),
-            Box::new(circuits::gates::gate::GatePreview::<
-                circuits::gates::and::And,
-            >::new()),
-            Box::new(circuits::gates::gate::GatePreview::<
-                circuits::gates::nand::Nand,
-            >::new()),
-            Box::new(circuits::gates::gate::Gate2497Preview),
-            Box::new(circuits::gates::not::NotPreview {}),
-            Box::new(circuits::pullup::PullupPreview {}),
-            Box::new(circuits::transistor::TransistorPreview {}),
-            Box::new(circuits::relay::RelayPreview {}),
-            Box::new(circuits::freq_meter::FreqMeterPreview {}),
-            Box::new(circuits::pin::Preview {}),
-            Box::new(circuits::bundler::Preview {}),
-            Box::new(circuits::clock::Preview {}),
-            Box::<circuits::board::BoardPreview>::default(),
-        ];
         let mut errors = ErrorList::new();
+
+        #[allow(unused_mut)]
+        let mut previews = SimulationContext::builtin_previews();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        for plugin in circuits::plugin::load_plugins(&mut errors) {
+            let ty = plugin.type_name();
+            if previews.iter().any(|p| p.type_name() == ty) {
+                errors
+                    .enter_context(|| "loading plugins")
+                    .push_error(format!("circuit type {} is already registered", ty.deref()));
+                continue;
+            }
+            previews.push(plugin);
+        }
+
         let preview_data = cc
             .storage
             .and_then(|s| s.get_string("previews"))
@@ -406,15 +548,31 @@ impl App {
 
         if let Some(storage) = cc.storage {
             if let Some(boards) = storage.get_string("boards") {
+                let version = storage
+                    .get_string("boards_version")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0);
                 match ron::from_str::<Vec<crate::io::CircuitBoardData>>(&boards) {
-                    Ok(data) => Self::load_boards(&data, &ctx, &mut errors),
+                    Ok(mut data) => {
+                        crate::io::migration::migrate_boards(
+                            version,
+                            &mut data,
+                            &mut errors.enter_context(|| "loading board data"),
+                        );
+                        Self::load_boards(&data, &ctx, &mut errors)
+                    }
                     Err(e) => errors.enter_context(|| "loading board data").push_error(e),
                 }
             } else if let Some(main_board) = storage.get_string("board") {
                 let data = ron::from_str::<crate::io::CircuitBoardData>(&main_board)
                     .report_error(&mut errors.enter_context(|| "loading board data"));
 
-                if let Some(data) = data {
+                if let Some(mut data) = data {
+                    crate::io::migration::migrate_boards(
+                        0,
+                        std::slice::from_mut(&mut data),
+                        &mut errors.enter_context(|| "loading board data"),
+                    );
                     let board = CircuitBoard::load(&data, &ctx, &mut errors);
                     let mut name = board.name.write();
                     if name.get_str().is_empty() {
@@ -446,12 +604,28 @@ impl App {
         let style = style_override.unwrap_or_else(|| Style {
             egui_style: cc.egui_ctx.style(),
             wire_colors: Default::default(),
+            canvas_colors: Default::default(),
         });
 
-        Self::new(ctx, style, errors)
+        let keybinds = cc
+            .storage
+            .and_then(|s| s.get_string("keybinds"))
+            .and_then(|s| {
+                ron::from_str::<Keybinds>(&s)
+                    .report_error(&mut errors.enter_context(|| "loading keybinds"))
+            })
+            .map(Keybinds::with_defaults)
+            .unwrap_or_default();
+
+        Self::new(ctx, style, keybinds, errors)
     }
 
-    pub fn new(ctx: Arc<SimulationContext>, style: Style, errors: ErrorList) -> Self {
+    pub fn new(
+        ctx: Arc<SimulationContext>,
+        style: Style,
+        keybinds: Keybinds,
+        errors: ErrorList,
+    ) -> Self {
         if ctx.boards.read().is_empty() {
             let board = CircuitBoard::new(ctx.clone(), "main");
             ctx.boards
@@ -473,7 +647,8 @@ impl App {
                 .clone(),
         );
 
-        let editor = CircuitBoardEditor::new(board, &ctx);
+        let mut editor = CircuitBoardEditor::new(board, &ctx);
+        editor.keybinds = keybinds;
 
         Self {
             editor,
@@ -554,12 +729,22 @@ impl App {
                     ui.label("Scroll to zoom");
                     ui.label("0-9 to select items in inventory");
                     ui.label("Esc to deselect");
+                    ui.label("F1 to show keyboard shortcuts, keys listed here are the defaults");
+                    ui.label("Ctrl+F to search circuits and boards");
+                    ui.label("Home to zoom to fit the board, Shift+Home to zoom to selection");
+                    ui.label("M to toggle the minimap, click or drag on it to move the view");
+                    ui.label("Board circuits can open their definition or instance state in a new tab");
                     ui.label("While selecting, holding Shift will add to existing selection and Ctrl will remove from it");
                     ui.add_space(5.0);
                     ui.label("In editor:");
                     ui.add_space(2.0);
                     ui.label("  R to rotate selected component (if applicable)");
                     ui.label("  F to flip selected component (if applicable)");
+                    ui.label("  With Selection tool, R / Shift+R rotates selection clockwise / counterclockwise");
+                    ui.label("  With Selection tool, F / Shift+F mirrors selection horizontally / vertically");
+                    ui.label("  With Selection tool, drag selected objects or use arrow keys to move them");
+                    ui.label("  With Wire router, click two pins to connect them with an automatically routed wire");
+                    ui.label("  Hover a wire or pin to highlight its net, Alt+click to select it");
                     let paused = if self.editor.board.state.is_frozen() {
                         "  P to resume current circuit simulation"
                     } else {
@@ -656,6 +841,13 @@ impl App {
                         {
                             self.load_state();
                         }
+                        #[cfg(not(feature = "wasm"))]
+                        if ui
+                            .button(RichText::new(" Import Logisim ").size(13.0))
+                            .clicked()
+                        {
+                            self.import_logisim();
+                        }
                     });
                 });
             });
@@ -704,6 +896,39 @@ impl App {
                 CollapsingHeader::new("Default wire colors").show(ui, |ui| {
                     self.style.wire_colors.ui(None, ui);
                 });
+                CollapsingHeader::new("Theme").show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Presets:");
+                        for preset in ThemePreset::ALL {
+                            if ui.button(preset.name()).clicked() {
+                                self.apply_style(ui.ctx(), preset.style(&ui.ctx().style()));
+                            }
+                        }
+                    });
+                    if self.style.theme_ui(ui) {
+                        ui.ctx().set_style(self.style.egui_style.clone());
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.button("Export theme").clicked() {
+                            self.export_theme();
+                        }
+                        #[cfg(not(feature = "wasm"))]
+                        if ui.button("Import theme").clicked() {
+                            self.import_theme(ui.ctx());
+                        }
+                    });
+                });
+                CollapsingHeader::new("Keyboard shortcuts").show(ui, |ui| {
+                    if self.editor.keybinds.has_conflicts() {
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            "Some actions share the same shortcut",
+                        );
+                    }
+                    if ui.button("Edit shortcuts").clicked() {
+                        self.editor.show_keybinds(true);
+                    }
+                });
             });
     }
 
@@ -724,7 +949,7 @@ impl App {
             if let Some(path) = path {
                 let boards = self.save_boards();
 
-                let save_state = crate::io::SaveStateData { boards };
+                let save_state = crate::io::SaveStateData::new(boards);
                 let string = ron::to_string(&save_state).report_error(
                     &mut self
                         .state_saving_errors
@@ -747,7 +972,7 @@ impl App {
         {
             let boards = self.save_boards();
 
-            let save_state = crate::io::SaveStateData { boards };
+            let save_state = crate::io::SaveStateData::new(boards);
             let string = ron::to_string(&save_state).report_error(
                 &mut self
                     .state_saving_errors
@@ -778,7 +1003,8 @@ impl App {
                         .enter_context(|| "loading savestate"),
                 );
                 if let Some(string) = string {
-                    let state = ron::from_str::<crate::io::SaveStateData>(&string).report_error(
+                    let state = crate::io::migration::load_save(
+                        &string,
                         &mut self
                             .state_loading_errors
                             .enter_context(|| "deserializing savestate"),
@@ -799,6 +1025,78 @@ impl App {
         }
     }
 
+    fn apply_style(&mut self, ctx: &egui::Context, style: Style) {
+        ctx.set_style(style.egui_style.clone());
+        self.style = style;
+    }
+
+    fn export_theme(&mut self) {
+        let string = ron::ser::to_string_pretty(&self.style, Default::default()).report_error(
+            &mut self
+                .state_saving_errors
+                .enter_context(|| "serializing theme"),
+        );
+        let string = crate::unwrap_option_or_return!(string);
+
+        #[cfg(not(feature = "wasm"))]
+        {
+            let fd = rfd::FileDialog::new()
+                .set_title("Export theme")
+                .set_file_name("theme.ron")
+                .add_filter("RON file", &["ron"]);
+            if let Some(path) = fd.save_file() {
+                std::fs::write(path, string).report_error(
+                    &mut self
+                        .state_saving_errors
+                        .enter_context(|| "saving theme"),
+                );
+            }
+        }
+        #[cfg(feature = "wasm")]
+        {
+            crate::web::save_state("theme.ron".into(), string);
+        }
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    fn import_theme(&mut self, ctx: &egui::Context) {
+        let fd = rfd::FileDialog::new()
+            .set_title("Import theme")
+            .add_filter("RON file", &["ron"]);
+        let path = crate::unwrap_option_or_return!(fd.pick_file());
+        let mut errors = self
+            .state_loading_errors
+            .enter_context(|| "importing theme");
+        let string = std::fs::read_to_string(path).report_error(&mut errors);
+        let style = string.and_then(|s| ron::from_str::<Style>(&s).report_error(&mut errors));
+        drop(errors);
+        if let Some(style) = style {
+            self.apply_style(ctx, style);
+        }
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    fn import_logisim(&mut self) {
+        let fd = rfd::FileDialog::new()
+            .set_title("Import Logisim circuit")
+            .add_filter("Logisim circuit", &["circ"]);
+        let path = crate::unwrap_option_or_return!(fd.pick_file());
+        let mut errors = self
+            .state_loading_errors
+            .enter_context(|| "importing Logisim circuit");
+        let string = std::fs::read_to_string(&path).report_error(&mut errors);
+        let string = crate::unwrap_option_or_return!(string);
+
+        let boards = crate::io::logisim::import(&string, &self.sim, &mut errors);
+        drop(errors);
+        if !boards.is_empty() {
+            let name = path
+                .file_stem()
+                .map(|n| format!("{}.ron", n.to_string_lossy()));
+            self.state_to_load = Some((name, crate::io::SaveStateData::new(boards)));
+        }
+    }
+
     fn save_boards(&mut self) -> Vec<crate::io::CircuitBoardData> {
         let boards = self.sim.boards.read();
         let locks: Vec<_> = boards.values().map(|b| b.board.sim_lock.write()).collect();
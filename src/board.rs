@@ -4,7 +4,7 @@ use std::{
     num::NonZeroU32,
     ops::Deref,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     },
 };
@@ -36,7 +36,7 @@ use crate::{
         RectVisuals,
     },
     unwrap_option_or_continue, unwrap_option_or_return,
-    vector::{Vec2f, Vec2i, Vec2u},
+    vector::{Vec2f, Vec2i, Vec2isize, Vec2u},
     wires::{FoundWireNode, TileWires, Wire, WireNode, WirePart, WirePoint, WireColors},
     ArcString, Direction2, Direction4, DynStaticStr, PaintContext, PastePreview, RwLock,
 };
@@ -66,12 +66,16 @@ pub struct CircuitBoard {
     pub designs: Arc<RwLock<CircuitDesignStorage>>,
     pub pins: RwLock<BiMap<Arc<str>, usize>>,
     pub controls: RwLock<HashSet<usize>>,
+    /// Tunnel circuit ids grouped by their names
+    pub tunnels: RwLock<HashMap<Arc<str>, HashSet<usize>>>,
     pub ctx: Arc<SimulationContext>,
 
     // RwLock for blocking simulation while modifying board
     pub sim_lock: Arc<RwLock<()>>,
     ordered_queue: AtomicBool,
     pub single_outer_control: AtomicBool,
+    /// Incremented by every `EditableCircuitBoard` edit, so editors can tell their node caches are stale
+    edits: AtomicU64,
 }
 
 impl CircuitBoard {
@@ -89,7 +93,9 @@ impl CircuitBoard {
             ))),
             pins: Default::default(),
             controls: Default::default(),
+            tunnels: Default::default(),
             single_outer_control: AtomicBool::new(false),
+            edits: AtomicU64::new(0),
             ctx,
         }
     }
@@ -281,7 +287,9 @@ impl CircuitBoard {
             designs,
             pins: Default::default(),
             controls: Default::default(),
+            tunnels: Default::default(),
             single_outer_control: AtomicBool::new(data.single_outer_control),
+            edits: AtomicU64::new(0),
             ctx: ctx.clone(),
         });
 
@@ -299,9 +307,16 @@ impl CircuitBoard {
                 )
             });
 
-            let preview = ctx.previews.get(&c.ty);
-            let preview = errors.report_none(preview, || "unknown circuit id");
-            let preview = unwrap_option_or_continue!(preview);
+            let missing;
+            let preview = match ctx.previews.get(&c.ty) {
+                Some(preview) => preview.deref(),
+                None => {
+                    errors.push_error("unknown circuit type, replaced with a placeholder");
+                    let imp = crate::circuits::missing::MissingPreview::new(i, c, data);
+                    missing = CircuitPreview::from_impl(Box::new(imp));
+                    &missing
+                }
+            };
             let props = preview.imp.default_props();
             props.load(&c.props);
 
@@ -383,6 +398,7 @@ impl CircuitBoard {
 pub enum SelectedItem {
     Selection,
     Wire,
+    Router,
     Circuit(Arc<CircuitPreview>),
     Paste(Arc<PastePreview>),
 }
@@ -393,6 +409,9 @@ pub struct EditableCircuitBoard {
 
     pub wire_nodes: Chunks2D<16, WireNode>,
     pub circuit_nodes: Chunks2D<16, CircuitNode>,
+
+    /// Board edit count this editor's nodes are up to date with
+    seen_edits: u64,
 }
 
 impl EditableCircuitBoard {
@@ -402,6 +421,7 @@ impl EditableCircuitBoard {
 
     pub fn new(state: Arc<State>) -> Self {
         let board = state.board.clone();
+        let seen_edits = board.edits.load(Ordering::Relaxed);
         let (wires, circuits) = {
             let mut wires = Chunks2D::<16, WireNode>::default();
             let mut circuits = Chunks2D::<16, CircuitNode>::default();
@@ -465,6 +485,19 @@ impl EditableCircuitBoard {
             wire_nodes: wires,
             circuit_nodes: circuits,
             state,
+            seen_edits,
+        }
+    }
+
+    /// Whether the board was edited through another editor since this one was created
+    pub fn is_outdated(&self) -> bool {
+        self.board.edits.load(Ordering::Relaxed) != self.seen_edits
+    }
+
+    fn mark_edited(&mut self) {
+        let edits = self.board.edits.fetch_add(1, Ordering::Relaxed);
+        if edits == self.seen_edits {
+            self.seen_edits = edits + 1;
         }
     }
 
@@ -472,6 +505,7 @@ impl EditableCircuitBoard {
 
     /// Returns placed wire id
     pub fn place_wire_part(&mut self, part: WirePart, lock_sim: bool, colors: WireColors) -> Option<usize> {
+        self.mark_edited();
         let part = unwrap_option_or_return!(self.optimize_wire_part(part), None);
 
         let sim_lock = { self.board.sim_lock.clone() };
@@ -737,12 +771,14 @@ impl EditableCircuitBoard {
         prev_wire
     }
 
-    pub fn try_toggle_node_intersection(&mut self, pos: Vec2i) {
+    /// Returns whether intersection was toggled
+    pub fn try_toggle_node_intersection(&mut self, pos: Vec2i) -> bool {
+        self.mark_edited();
         let node = self.wire_nodes.get(pos.convert(|v| v as isize));
 
         let node = match node {
-            None => return,
-            Some(n) if n.is_empty() => return,
+            None => return false,
+            Some(n) if n.is_empty() => return false,
             Some(v) => v,
         };
 
@@ -753,7 +789,7 @@ impl EditableCircuitBoard {
         let down = node.down.is_some();
 
         if up != down || left != right {
-            return;
+            return false;
         }
 
         if center {
@@ -761,6 +797,10 @@ impl EditableCircuitBoard {
         } else {
             self.create_wire_intersection_at_node(pos, *node);
         }
+
+        self.wire_nodes
+            .get(pos.convert(|v| v as isize))
+            .is_some_and(|n| n.wire.is_some() != center)
     }
 
     pub fn create_wire_intersection(&mut self, pos: Vec2i) -> Option<usize> {
@@ -778,6 +818,7 @@ impl EditableCircuitBoard {
         pos: Vec2i,
         node: WireNode,
     ) -> Option<usize> {
+        self.mark_edited();
         let wires = self.wires_at_node(pos, &node);
 
         match wires {
@@ -932,6 +973,7 @@ impl EditableCircuitBoard {
         split: bool,
         update_states: bool,
     ) -> Option<usize> {
+        self.mark_edited();
         let node = *self.wire_nodes.get(pos.convert(|v| v as isize))?;
         let wire = node.wire.get()?;
 
@@ -1070,6 +1112,24 @@ impl EditableCircuitBoard {
 
     /* #endregion */
 
+    /// Area of all circuits and wires in tiles
+    pub fn bounds(&self) -> Option<Rect> {
+        let wires = self.wire_nodes.occupied_bounds(|n| !n.is_empty());
+        let circuits = self.circuit_nodes.occupied_bounds(|n| n.circuit.is_some());
+        let (tl, br): (Vec2isize, Vec2isize) = match (wires, circuits) {
+            (Some(a), Some(b)) => (
+                [a.0.x.min(b.0.x), a.0.y.min(b.0.y)].into(),
+                [a.1.x.max(b.1.x), a.1.y.max(b.1.y)].into(),
+            ),
+            (Some(a), None) | (None, Some(a)) => a,
+            (None, None) => return None,
+        };
+        Some(Rect::from_min_max(
+            pos2(tl.x as f32, tl.y as f32),
+            pos2(br.x as f32 + 1.0, br.y as f32 + 1.0),
+        ))
+    }
+
     /* #region Wire node querying */
 
     pub fn wires_at(&self, pos: Vec2i) -> TileWires {
@@ -1246,6 +1306,7 @@ impl EditableCircuitBoard {
         imp_data: Option<&Intermediate>,
         handler: &mut dyn FnMut(&mut EditableCircuitBoard, usize),
     ) -> Option<usize> {
+        self.mark_edited();
         let size = preview.describe().size;
         if !self.can_place_circuit_at(size, place_pos, None) {
             return None;
@@ -1355,7 +1416,16 @@ impl EditableCircuitBoard {
         true
     }
 
-    fn pin_at(&self, pos: Vec2i) -> Option<Arc<RwLock<CircuitPin>>> {
+    /// Id of the circuit with its top-left corner at `pos`
+    pub fn circuit_at(&self, pos: Vec2i) -> Option<usize> {
+        let node = self.circuit_nodes.get(pos.convert(|v| v as isize))?;
+        if node.origin_dist != Vec2u::single_value(0) {
+            return None;
+        }
+        node.circuit.get()
+    }
+
+    pub fn pin_at(&self, pos: Vec2i) -> Option<Arc<RwLock<CircuitPin>>> {
         let circ = self.circuit_nodes.get(pos.convert(|v| v as isize))?;
         let pos = circ.origin_dist;
         let circ = circ.circuit.get()?;
@@ -1419,6 +1489,7 @@ impl EditableCircuitBoard {
     }
 
     pub fn remove_circuit(&mut self, id: usize, affected_wires: &mut HashSet<usize>) {
+        self.mark_edited();
         let circuit = self.board.circuits.read().get(id).cloned();
         let circuit = unwrap_option_or_return!(circuit);
 
@@ -1537,6 +1608,7 @@ impl EditableCircuitBoard {
         property: &str,
         old_value: &dyn CircuitPropertyImpl,
     ) -> bool {
+        self.mark_edited();
         if !self.try_updating_circuit_property(circuit, property) {
             let circuits = self.board.circuits.read();
             if let Some(circuit) = circuits.get(circuit) {
@@ -1675,7 +1747,7 @@ impl CircuitDesignStorage {
     }
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
 pub enum Decoration {
     Rect { rect: Rect, visuals: RectVisuals }, // TODO: MOAR!
 }
@@ -1723,7 +1795,7 @@ impl Decoration {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CircuitDesignPin {
     pub id: DynStaticStr,
     pub pos: Vec2u,
@@ -1732,7 +1804,7 @@ pub struct CircuitDesignPin {
     pub display_name: DynStaticStr,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CircuitDesignControl {
     pub rect: Rect,
     pub display_name: ArcString,
@@ -1757,6 +1829,22 @@ impl CircuitDesign {
         Self { id, ..self }
     }
 
+    /// Compares everything except design ids
+    pub fn same_contents(&self, other: &Self) -> bool {
+        self.size == other.size
+            && self.pins == other.pins
+            && self.decorations == other.decorations
+            && self.controls == other.controls
+    }
+
+    /// Approximate memory used by this design, in bytes
+    pub fn memory_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.pins.len() * std::mem::size_of::<CircuitDesignPin>()
+            + self.decorations.len() * std::mem::size_of::<Decoration>()
+            + self.controls.len() * std::mem::size_of::<((usize, usize), CircuitDesignControl)>()
+    }
+
     pub fn default_board_design() -> Self {
         Self {
             id: 0,
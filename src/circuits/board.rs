@@ -39,6 +39,13 @@ pub struct BoardState {
     state: Option<Arc<State>>,
 }
 
+impl BoardState {
+    /// State of the inner board used by this instance
+    pub fn inner_state(&self) -> Option<&Arc<State>> {
+        self.state.as_ref()
+    }
+}
+
 impl InternalCircuitState for BoardState {
     fn serialize(&self, _: bool) -> serde_intermediate::Intermediate {
         let state_id = self.state.as_ref().map(|s| s.id).or(self.state_id);
@@ -89,7 +96,7 @@ impl Board {
                 ctx.rect,
                 Rounding::ZERO,
                 Color32::RED.gamma_multiply(0.8 * trans),
-                Stroke::new(2.0, Color32::BLACK.gamma_multiply(trans)),
+                Stroke::new(2.0, ctx.style.component_border_color().gamma_multiply(trans)),
             );
 
             let rect = ctx.rect.shrink(2.0);
@@ -169,6 +176,10 @@ impl Board {
         }
     }
 
+    pub fn inner_board(&self) -> Option<&Arc<CircuitBoard>> {
+        self.board.as_ref()
+    }
+
     pub fn resolve_inner_to_outer(&self, inner: usize) -> Option<usize> {
         if let Some(outer) = self.pinmap.read().get_by_left(&inner) {
             return Some(*outer);
@@ -313,7 +324,7 @@ impl CircuitImpl for Board {
                 ctx.rect,
                 Rounding::ZERO,
                 Color32::RED.gamma_multiply(0.8),
-                Stroke::new(2.0, Color32::BLACK),
+                Stroke::new(2.0, ctx.style.component_border_color()),
             );
 
             let rect = ctx.rect.shrink(2.0);
@@ -15,7 +15,7 @@ use crate::{circuits::*, create_safe_prop_enums};
 create_safe_prop_enums! {
     #[default(Input)]
     #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-    enum BundlerDirecion {
+    pub enum BundlerDirecion {
         Input('i'),
         Output('o'),
     }
@@ -0,0 +1,835 @@
+use std::{collections::HashMap, iter::Peekable, str::CharIndices};
+
+use eframe::epaint::{FontId, Rounding, Stroke};
+use emath::Align2;
+
+use crate::board::EditableCircuitBoard;
+use crate::circuits::props::{CircuitProperty, RangedValue};
+use crate::pool::PooledStateVec;
+use crate::wires::{WireColors, WirePart};
+use crate::{circuits::*, ArcString};
+
+/// Parsed boolean expression
+pub enum Expr {
+    Const(bool),
+    /// Index into `ParsedExpression::vars`
+    Var(usize),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Xor(Vec<Expr>),
+}
+
+impl Expr {
+    /// Evaluates expression, `None` being an unknown value
+    pub fn eval(&self, vars: &[Option<bool>]) -> Option<bool> {
+        match self {
+            Expr::Const(v) => Some(*v),
+            Expr::Var(i) => vars.get(*i).copied().flatten(),
+            Expr::Not(e) => e.eval(vars).map(|v| !v),
+            Expr::And(es) => {
+                let mut out = Some(true);
+                for e in es {
+                    match e.eval(vars) {
+                        Some(false) => return Some(false),
+                        Some(true) => {}
+                        None => out = None,
+                    }
+                }
+                out
+            }
+            Expr::Or(es) => {
+                let mut out = Some(false);
+                for e in es {
+                    match e.eval(vars) {
+                        Some(true) => return Some(true),
+                        Some(false) => {}
+                        None => out = None,
+                    }
+                }
+                out
+            }
+            Expr::Xor(es) => {
+                let mut out = false;
+                for e in es {
+                    out ^= e.eval(vars)?;
+                }
+                Some(out)
+            }
+        }
+    }
+}
+
+pub struct ParsedExpression {
+    /// Variable names in order of first appearance
+    pub vars: Vec<Arc<str>>,
+    pub root: Expr,
+}
+
+impl ParsedExpression {
+    pub const MAX_VARS: usize = 32;
+    /// Limits `!` and parentheses nesting, so long inputs can't overflow the stack
+    pub const MAX_DEPTH: usize = 64;
+
+    /// Parses expressions like `(a & !b) | c`<br>
+    /// Operators, from highest priority: `!` or `~` (not), `&` or `*` (and), `^` (xor), `|` or `+` (or).
+    /// `0` and `1` are constants
+    pub fn parse(str: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: str.char_indices().peekable(),
+            vars: vec![],
+            depth: 0,
+        };
+        let root = parser.parse_or()?;
+        parser.skip_whitespace();
+        if let Some((i, c)) = parser.chars.next() {
+            return Err(format!("Unexpected `{c}` at {i}"));
+        }
+        Ok(Self {
+            vars: parser.vars,
+            root,
+        })
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<CharIndices<'a>>,
+    vars: Vec<Arc<str>>,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn next_is(&mut self, ops: &[char]) -> bool {
+        self.skip_whitespace();
+        self.chars.next_if(|(_, c)| ops.contains(c)).is_some()
+    }
+
+    fn parse_list(
+        &mut self,
+        ops: &[char],
+        inner: fn(&mut Self) -> Result<Expr, String>,
+        combine: fn(Vec<Expr>) -> Expr,
+    ) -> Result<Expr, String> {
+        let mut list = vec![inner(self)?];
+        while self.next_is(ops) {
+            list.push(inner(self)?);
+        }
+        Ok(match list.len() {
+            1 => list.remove(0),
+            _ => combine(list),
+        })
+    }
+
+    fn nested(&mut self, inner: fn(&mut Self) -> Result<Expr, String>) -> Result<Expr, String> {
+        if self.depth >= ParsedExpression::MAX_DEPTH {
+            return Err(format!(
+                "Expression is nested too deeply, maximum is {}",
+                ParsedExpression::MAX_DEPTH
+            ));
+        }
+        self.depth += 1;
+        let expr = inner(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        self.parse_list(&['|', '+'], Self::parse_xor, Expr::Or)
+    }
+
+    fn parse_xor(&mut self) -> Result<Expr, String> {
+        self.parse_list(&['^'], Self::parse_and, Expr::Xor)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        self.parse_list(&['&', '*'], Self::parse_unary, Expr::And)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        if self.next_is(&['!', '~']) {
+            return Ok(Expr::Not(Box::new(self.nested(Self::parse_unary)?)));
+        }
+        self.skip_whitespace();
+        match self.chars.next() {
+            None => Err("Unexpected end of expression".into()),
+            Some((_, '(')) => {
+                let expr = self.nested(Self::parse_or)?;
+                if !self.next_is(&[')']) {
+                    return Err("Expected `)`".into());
+                }
+                Ok(expr)
+            }
+            Some((_, '0')) => Ok(Expr::Const(false)),
+            Some((_, '1')) => Ok(Expr::Const(true)),
+            Some((_, c)) if c.is_alphabetic() || c == '_' => {
+                let mut name = String::from(c);
+                while let Some((_, c)) = self
+                    .chars
+                    .next_if(|(_, c)| c.is_alphanumeric() || *c == '_')
+                {
+                    name.push(c);
+                }
+                let index = match self.vars.iter().position(|v| **v == *name) {
+                    Some(i) => i,
+                    None => {
+                        if self.vars.len() >= ParsedExpression::MAX_VARS {
+                            return Err(format!(
+                                "Too many variables, maximum is {}",
+                                ParsedExpression::MAX_VARS
+                            ));
+                        }
+                        self.vars.push(name.into());
+                        self.vars.len() - 1
+                    }
+                };
+                Ok(Expr::Var(index))
+            }
+            Some((i, c)) => Err(format!("Unexpected `{c}` at {i}")),
+        }
+    }
+}
+
+struct Expression {
+    dir: Direction4,
+    text: Arc<str>,
+    expr: Option<ParsedExpression>,
+
+    inputs: Box<[CircuitPinInfo]>,
+    output: CircuitPinInfo,
+}
+
+impl Expression {
+    const DEFAULT_EXPR: &'static str = "(a & !b) | c";
+
+    fn new() -> Self {
+        let expr = ParsedExpression::parse(Self::DEFAULT_EXPR).ok();
+        let description = Self::describe(Direction4::Right, Self::DEFAULT_EXPR, expr.as_ref());
+        let (output, inputs) = description.pins.split_last().expect("pins should exist!");
+        Self {
+            dir: Direction4::Right,
+            text: Self::DEFAULT_EXPR.into(),
+            expr,
+            inputs: inputs.iter().map(|p| p.to_info()).collect(),
+            output: output.to_info(),
+        }
+    }
+
+    fn read_text(props: &CircuitPropertyStore) -> Arc<str> {
+        props
+            .read("expr", |s: &ArcString| s.get_arc())
+            .unwrap_or_else(|| Self::DEFAULT_EXPR.into())
+    }
+
+    fn draw(text: &str, valid: bool, ctx: &PaintContext, semi_transparent: bool) {
+        let opacity = if semi_transparent { 0.6 } else { 1.0 };
+
+        let border_color = ctx.style.component_border_color().linear_multiply(opacity);
+        let fill_color = ctx.style.component_fill_color().linear_multiply(opacity);
+        let text_color = match valid {
+            true => border_color,
+            false => Color32::RED.linear_multiply(opacity),
+        };
+
+        let rect = ctx.rect.shrink(ctx.screen.scale * 0.25);
+        ctx.paint.rect(
+            rect,
+            Rounding::same(ctx.screen.scale * 0.15),
+            fill_color,
+            Stroke::new(0.15 * ctx.screen.scale, border_color),
+        );
+
+        // Monospace glyphs are roughly 0.6 of font size wide, shrink long expressions to fit
+        let chars = text.chars().count().max(1) as f32;
+        let size = (ctx.screen.scale * 0.6).min(rect.width() * 0.9 / (chars * 0.6));
+        ctx.paint.text(
+            rect.center(),
+            Align2::CENTER_CENTER,
+            text,
+            FontId::monospace(size),
+            text_color,
+        );
+    }
+
+    fn size(dir: Direction4, text: &str, vars: usize) -> Vec2u {
+        let width = (text.chars().count() as u32 * 2 / 5 + 2).clamp(3, 16);
+        let height = (vars as u32).max(1);
+        if dir.is_horizontal() {
+            [width, height].into()
+        } else {
+            [height, width].into()
+        }
+    }
+
+    fn describe_props(props: &CircuitPropertyStore) -> DynCircuitDescription {
+        let dir = props.read_clone("dir").unwrap_or(Direction4::Right);
+        let text = Self::read_text(props);
+        let expr = ParsedExpression::parse(&text).ok();
+        Self::describe(dir, &text, expr.as_ref())
+    }
+
+    // [inputs..., out]
+    fn describe(
+        dir: Direction4,
+        text: &str,
+        expr: Option<&ParsedExpression>,
+    ) -> DynCircuitDescription {
+        let vars = expr.map(|e| e.vars.as_slice()).unwrap_or_default();
+
+        let dir_normalized = dir.rotate_counterclockwise_by(Direction4::Right);
+        let size_rotated = Self::size(dir, text, vars.len());
+        let [width, height]: [u32; 2] = Self::size(Direction4::Right, text, vars.len()).into();
+
+        let mut pins = Vec::with_capacity(vars.len() + 1);
+        let in_display_dir = Direction4::Left.rotate_clockwise_by(dir_normalized);
+        for (i, var) in vars.iter().enumerate() {
+            let pos =
+                crate::circuits::rotate_pos([0, i as u32], size_rotated.into(), dir_normalized);
+            pins.push(CircuitPinDescription {
+                active: true,
+                display_name: var.clone().into(),
+                display_dir: Some(in_display_dir),
+                dir: InternalPinDirection::Inside,
+                name: format!("in_{var}").into(),
+                pos: pos.into(),
+            });
+        }
+
+        let out_pos = crate::circuits::rotate_pos(
+            [width - 1, height / 2],
+            size_rotated.into(),
+            dir_normalized,
+        );
+        pins.push(CircuitPinDescription {
+            active: true,
+            display_name: "Out".into(),
+            display_dir: Some(Direction4::Right.rotate_clockwise_by(dir_normalized)),
+            dir: InternalPinDirection::Outside,
+            name: "out".into(),
+            pos: out_pos.into(),
+        });
+
+        DynCircuitDescription {
+            size: size_rotated,
+            pins: pins.into(),
+        }
+    }
+}
+
+impl CircuitImpl for Expression {
+    fn draw(&self, _: &CircuitStateContext, paint_ctx: &PaintContext) {
+        Expression::draw(&self.text, self.expr.is_some(), paint_ctx, false);
+    }
+
+    fn create_pins(&mut self, circ: &Arc<Circuit>) -> Box<[CircuitPinInfo]> {
+        let description = Self::describe_props(&circ.props);
+        let (output, inputs) = description.pins.split_last().expect("pins should exist!");
+        self.inputs = inputs.iter().map(|p| p.to_info()).collect();
+        self.output = output.to_info();
+
+        let mut vec = self.inputs.to_vec();
+        vec.push(self.output.clone());
+        vec.into()
+    }
+
+    fn update_signals(&self, state_ctx: &CircuitStateContext, _: Option<usize>) {
+        let expr = match &self.expr {
+            Some(expr) => expr,
+            None => {
+                self.output.set_state(state_ctx, WireState::Error);
+                return;
+            }
+        };
+
+        fn single(state: &WireState) -> Result<Option<bool>, ()> {
+            match state {
+                WireState::True => Ok(Some(true)),
+                WireState::False => Ok(Some(false)),
+                WireState::None => Ok(None),
+                WireState::Error | WireState::Bundle(_) => Err(()),
+            }
+        }
+
+        fn eval(expr: &Expr, states: &[WireState]) -> WireState {
+            let vars: Result<Vec<_>, _> = states.iter().map(single).collect();
+            match vars {
+                Ok(vars) => match expr.eval(&vars) {
+                    Some(v) => v.into(),
+                    None => WireState::None,
+                },
+                Err(_) => WireState::Error,
+            }
+        }
+
+        let states: Vec<_> = self.inputs.iter().map(|i| i.get_state(state_ctx)).collect();
+
+        // Bundles are evaluated bit by bit
+        let out = match states.iter().filter_map(|s| s.bundle_len()).max() {
+            None => eval(&expr.root, &states),
+            Some(len) => {
+                let mut out = PooledStateVec::new();
+                let mut bit_states = Vec::with_capacity(states.len());
+                for i in 0..len {
+                    bit_states.clear();
+                    bit_states.extend(states.iter().map(|s| s.get(i)));
+                    out.push(eval(&expr.root, &bit_states));
+                }
+                WireState::Bundle(Arc::new(out))
+            }
+        };
+        self.output.set_state(state_ctx, out);
+    }
+
+    fn size(&self, circ: &Arc<Circuit>) -> Vec2u {
+        Self::describe_props(&circ.props).size
+    }
+
+    fn prop_changed(&self, prop_id: &str, resize: &mut bool, recreate_pins: &mut bool) {
+        if prop_id == "dir" || prop_id == "expr" {
+            *resize = true;
+            *recreate_pins = true;
+        }
+    }
+
+    fn apply_props(&mut self, circ: &Arc<Circuit>, _: Option<&str>) {
+        self.dir = circ.props.read_clone("dir").unwrap_or(Direction4::Right);
+        self.text = Self::read_text(&circ.props);
+        self.expr = ParsedExpression::parse(&self.text).ok();
+    }
+}
+
+pub struct ExpressionPreview {}
+
+impl CircuitPreviewImpl for ExpressionPreview {
+    fn type_name(&self) -> DynStaticStr {
+        "expression".into()
+    }
+
+    fn display_name(&self) -> DynStaticStr {
+        "Expression".into()
+    }
+
+    fn description(&self) -> DynStaticStr {
+        "Outputs value of a boolean expression, with an input pin for every variable.\n\
+         Operators, from highest priority: ! (not), & (and), ^ (xor), | (or). \
+         0 and 1 are constants, parentheses group operations.\n\
+         Bundles are processed bit by bit. Invalid expressions output Error.\n\
+         Can be expanded into logic gates from the properties panel.\
+        "
+        .into()
+    }
+
+    fn draw_preview(&self, props: &CircuitPropertyStore, ctx: &PaintContext, in_world: bool) {
+        let text = Expression::read_text(props);
+        let valid = ParsedExpression::parse(&text).is_ok();
+        Expression::draw(&text, valid, ctx, in_world);
+    }
+
+    fn create_impl(&self) -> Box<dyn CircuitImpl> {
+        Box::new(Expression::new())
+    }
+
+    fn load_copy_data(
+        &self,
+        _imp: &serde_intermediate::Intermediate,
+        _internal: &serde_intermediate::Intermediate,
+        _ctx: &Arc<SimulationContext>,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn CircuitPreviewImpl>> {
+        Some(Box::new(ExpressionPreview {}))
+    }
+
+    fn default_props(&self) -> CircuitPropertyStore {
+        CircuitPropertyStore::new([
+            CircuitProperty::new("dir", "Direction", Direction4::Right),
+            CircuitProperty::new(
+                "expr",
+                "Expression",
+                ArcString::from(Expression::DEFAULT_EXPR),
+            ),
+        ])
+    }
+
+    fn describe(&self, props: &CircuitPropertyStore) -> DynCircuitDescription {
+        Expression::describe_props(props)
+    }
+}
+
+enum GateSource {
+    Var(usize),
+    Gate(usize),
+}
+
+struct PlannedGate {
+    preview: CircuitPreview,
+    inputs: Vec<GateSource>,
+    column: u32,
+    size: Vec2u,
+    /// Input pin positions, relative to the gate
+    input_pins: Vec<Vec2u>,
+    output_pin: Vec2u,
+    pos: Vec2i,
+}
+
+fn plan_gates(
+    expr: &Expr,
+    sim: &Arc<SimulationContext>,
+    gates: &mut Vec<PlannedGate>,
+) -> Result<GateSource, String> {
+    let (ty, children) = match expr {
+        Expr::Const(_) => return Err("Constants can't be expanded into gates".into()),
+        Expr::Var(i) => return Ok(GateSource::Var(*i)),
+        Expr::Not(e) => ("not", std::slice::from_ref(e.as_ref())),
+        Expr::And(es) => ("and", es.as_slice()),
+        Expr::Or(es) => ("or", es.as_slice()),
+        Expr::Xor(es) => ("xor", es.as_slice()),
+    };
+
+    let mut inputs = vec![];
+    let mut column = 1;
+    for child in children {
+        let source = plan_gates(child, sim, gates)?;
+        if let GateSource::Gate(g) = source {
+            column = column.max(gates[g].column + 1);
+        }
+        inputs.push(source);
+    }
+
+    let preview = sim
+        .previews
+        .get(ty)
+        .ok_or_else(|| format!("Circuit type {ty} doesn't exist"))?;
+    let imp = preview
+        .imp
+        .load_copy_data(
+            &serde_intermediate::Intermediate::Unit,
+            &serde_intermediate::Intermediate::Unit,
+            sim,
+            &mut ErrorList::new(),
+        )
+        .ok_or_else(|| format!("Couldn't create {ty} circuit"))?;
+    let props = imp.default_props();
+    if ty != "not" {
+        props.write("inputs", |v: &mut RangedValue<u32>| {
+            v.set(inputs.len() as u32)
+        });
+    }
+    let preview = CircuitPreview::new(imp, props);
+    let description = preview.describe();
+    let (output, input_pins) = description
+        .pins
+        .split_last()
+        .ok_or_else(|| format!("{ty} circuit has no pins"))?;
+
+    gates.push(PlannedGate {
+        inputs,
+        column,
+        size: description.size,
+        input_pins: input_pins.iter().map(|p| p.pos).collect(),
+        output_pin: output.pos,
+        pos: Vec2i::single_value(0),
+        preview,
+    });
+    Ok(GateSource::Gate(gates.len() - 1))
+}
+
+/// Expands expression into gate circuits wired together, placing them below the expression circuit.<br>
+/// Inputs are wire ends on the left side, in order of expression circuit pins, output is a wire end on the right side.<br>
+/// Returns ids of placed circuits and wire parts that weren't on the board before
+pub fn expand_into_gates(
+    board: &mut EditableCircuitBoard,
+    sim: &Arc<SimulationContext>,
+    circuit: &Arc<Circuit>,
+) -> Result<(Vec<usize>, Vec<WirePart>), String> {
+    let text = Expression::read_text(&circuit.props);
+    let expr = ParsedExpression::parse(&text)?;
+
+    let mut gates = vec![];
+    let root = match plan_gates(&expr.root, sim, &mut gates)? {
+        GateSource::Gate(g) => g,
+        GateSource::Var(_) => return Err("Expression has no operations to expand".into()),
+    };
+
+    // Layout, relative to top left corner:
+    // input wire ends, one vertical rail per variable, then gate columns with vertical wire tracks between them.
+    // Every gate gets its own rows, so horizontal wires never cross each other's points
+    let vars = expr.vars.len() as i32;
+    let rail_x = |var: usize| 2 + var as i32;
+    let mut x = 2 + vars + 1;
+    let mut y = vars + 1;
+    // (gate, input) -> track x
+    let mut tracks = HashMap::new();
+    let max_column = gates.iter().map(|g| g.column).max().unwrap_or(0);
+    for column in 1..=max_column {
+        let mut track_index = 0;
+        let mut track_x = x;
+        for gate in gates.iter().filter(|g| g.column == column) {
+            for source in gate.inputs.iter() {
+                if let GateSource::Gate(_) = source {
+                    track_x += 1;
+                }
+            }
+        }
+        let column_x = track_x + 2;
+
+        let mut column_width = 0;
+        for (i, gate) in gates.iter_mut().enumerate() {
+            if gate.column != column {
+                continue;
+            }
+            gate.pos = [column_x, y].into();
+            y += gate.size.y as i32 + 1;
+            column_width = column_width.max(gate.size.x as i32);
+
+            for (j, source) in gate.inputs.iter().enumerate() {
+                if let GateSource::Gate(_) = source {
+                    track_index += 1;
+                    tracks.insert((i, j), x + track_index);
+                }
+            }
+        }
+        x = column_x + column_width;
+    }
+    let size = Vec2u::new(x as u32 + 3, y as u32);
+
+    // Find free space below the expression circuit
+    let circuit_size = circuit.info.read().size;
+    let mut origin = circuit.pos + [0, circuit_size.y as i32 + 2];
+    let is_occupied = |board: &EditableCircuitBoard, pos: Vec2i| {
+        let pos = pos.convert(|v| v as isize);
+        board
+            .circuit_nodes
+            .get(pos)
+            .is_some_and(|n| n.circuit.is_some())
+            || board.wire_nodes.get(pos).is_some_and(|n| !n.is_empty())
+    };
+    let is_free = |board: &EditableCircuitBoard, origin: Vec2i| {
+        (0..size.y as i32).all(|j| (0..size.x as i32).all(|i| !is_occupied(board, origin + [i, j])))
+    };
+    let mut tries = 0;
+    while !is_free(board, origin) {
+        tries += 1;
+        if tries > 100 {
+            return Err("Couldn't find free space for gates".into());
+        }
+        origin += [0, 4];
+    }
+
+    let sim_lock = board.board.sim_lock.clone();
+    let sim_lock = sim_lock.write();
+
+    fn empty_handler(_: &mut EditableCircuitBoard, _: usize) {}
+    let mut circuits = vec![];
+    for gate in gates.iter_mut() {
+        gate.pos += origin;
+        let id = board.place_circuit(
+            gate.pos,
+            false,
+            &gate.preview,
+            None,
+            false,
+            None,
+            &mut empty_handler,
+        );
+        circuits.extend(id);
+    }
+
+    let mut wires = vec![];
+    let mut place_wire = |board: &mut EditableCircuitBoard, from: Vec2i, to: Vec2i| {
+        let part = EditableCircuitBoard::calc_wire_part(Some(from), Some(to));
+        if let Some(part) = part {
+            wires.extend(crate::ui::history::uncovered_wire_parts(board, &part));
+            board.place_wire_part(part, false, WireColors::default());
+        }
+    };
+
+    // Rails go from input wire ends down to their last use
+    let mut rail_ends = vec![0; expr.vars.len()];
+    for gate in gates.iter() {
+        for (source, pin) in gate.inputs.iter().zip(gate.input_pins.iter()) {
+            if let GateSource::Var(var) = source {
+                let pin_y = gate.pos.y + pin.y as i32;
+                rail_ends[*var] = rail_ends[*var].max(pin_y);
+            }
+        }
+    }
+    for (var, end) in rail_ends.iter().enumerate() {
+        let top = origin + [rail_x(var), var as i32];
+        place_wire(board, top, [top.x, *end].into());
+        place_wire(board, origin + [0, var as i32], top);
+    }
+
+    for (i, gate) in gates.iter().enumerate() {
+        for (j, (source, pin)) in gate.inputs.iter().zip(gate.input_pins.iter()).enumerate() {
+            let pin = gate.pos + pin.convert(|v| v as i32);
+            match source {
+                GateSource::Var(var) => {
+                    place_wire(board, [origin.x + rail_x(*var), pin.y].into(), pin);
+                }
+                GateSource::Gate(source) => {
+                    let source = &gates[*source];
+                    let out = source.pos + source.output_pin.convert(|v| v as i32);
+                    let track = tracks.get(&(i, j));
+                    let track = *unwrap_option_or_continue!(track) + origin.x;
+                    place_wire(board, out, [track, out.y].into());
+                    place_wire(board, [track, out.y].into(), [track, pin.y].into());
+                    place_wire(board, [track, pin.y].into(), pin);
+                }
+            }
+        }
+    }
+
+    let out = gates[root].pos + gates[root].output_pin.convert(|v| v as i32);
+    place_wire(board, out, out + [2, 0]);
+
+    drop(sim_lock);
+    Ok((circuits, wires))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{expand_into_gates, Expr, ExpressionPreview, ParsedExpression};
+    use crate::{
+        app::SimulationContext,
+        board::{CircuitBoard, EditableCircuitBoard},
+        circuits::CircuitPreview,
+        ArcString,
+    };
+
+    fn eval(text: &str, vars: &[bool]) -> Option<bool> {
+        let expr = ParsedExpression::parse(text).expect("expression should parse");
+        let vars: Vec<_> = vars.iter().copied().map(Some).collect();
+        expr.root.eval(&vars)
+    }
+
+    #[test]
+    fn operators_follow_precedence() {
+        let expr = ParsedExpression::parse("a | b ^ c & !d").unwrap();
+        let names: Vec<_> = expr.vars.iter().map(|v| &**v).collect();
+        assert_eq!(names, ["a", "b", "c", "d"]);
+        let Expr::Or(or) = &expr.root else {
+            panic!("`|` should have the lowest priority");
+        };
+        assert!(matches!(&or[..], [Expr::Var(0), Expr::Xor(_)]));
+        let Expr::Xor(xor) = &or[1] else {
+            unreachable!()
+        };
+        assert!(matches!(&xor[..], [Expr::Var(1), Expr::And(_)]));
+        let Expr::And(and) = &xor[1] else {
+            unreachable!()
+        };
+        assert!(matches!(&and[..], [Expr::Var(2), Expr::Not(_)]));
+
+        assert_eq!(eval("(a + b) * c", &[true, false, false]), Some(false));
+        assert_eq!(eval("a + b * c", &[true, false, false]), Some(true));
+        assert_eq!(eval("~a ^ 1", &[false]), Some(false));
+        assert_eq!(eval("a_1 & a_1", &[true]), Some(true));
+    }
+
+    #[test]
+    fn unknown_values_propagate() {
+        let expr = ParsedExpression::parse("a & b | c").unwrap();
+        assert_eq!(expr.root.eval(&[None, Some(false), Some(true)]), Some(true));
+        assert_eq!(expr.root.eval(&[None, Some(true), Some(false)]), None);
+        assert_eq!(
+            expr.root.eval(&[None, Some(false), Some(false)]),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        for text in ["", "a &", "(a | b", "a b", "a $ b", ")"] {
+            assert!(ParsedExpression::parse(text).is_err(), "{text:?} parsed");
+        }
+
+        let vars: Vec<_> = (0..=ParsedExpression::MAX_VARS)
+            .map(|i| format!("v{i}"))
+            .collect();
+        assert!(ParsedExpression::parse(&vars.join(" | ")).is_err());
+    }
+
+    #[test]
+    fn deep_nesting_is_rejected() {
+        let depth = ParsedExpression::MAX_DEPTH;
+        let nested = |depth: usize| format!("{}a{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(ParsedExpression::parse(&nested(depth)).is_ok());
+        assert!(ParsedExpression::parse(&nested(depth + 1)).is_err());
+        let negated = |depth: usize| format!("{}a", "!".repeat(depth));
+        assert!(ParsedExpression::parse(&negated(depth)).is_ok());
+        assert!(ParsedExpression::parse(&negated(depth + 1)).is_err());
+        assert!(ParsedExpression::parse(&"(".repeat(100_000)).is_err());
+        assert!(ParsedExpression::parse(&format!("{}a", "~".repeat(100_000))).is_err());
+    }
+
+    fn board_with_expression(text: &str) -> (EditableCircuitBoard, Arc<SimulationContext>, usize) {
+        let ctx = SimulationContext::with_builtin_previews();
+        let board = Arc::new(CircuitBoard::new(ctx.clone(), "test"));
+        let mut board = EditableCircuitBoard::new_main(board);
+        let preview = CircuitPreview::from_impl(Box::new(ExpressionPreview {}));
+        preview
+            .props
+            .write("expr", |s: &mut ArcString| *s = text.into());
+        let id = board
+            .place_circuit(
+                [0, 0].into(),
+                true,
+                &preview,
+                None,
+                false,
+                None,
+                &mut |_, _| {},
+            )
+            .expect("expression should be placed");
+        (board, ctx, id)
+    }
+
+    fn circuit_types(board: &EditableCircuitBoard) -> Vec<String> {
+        let mut types: Vec<_> = board
+            .board
+            .circuits
+            .read()
+            .iter()
+            .map(|c| c.ty.to_string())
+            .collect();
+        types.sort();
+        types
+    }
+
+    #[test]
+    fn expression_expands_into_gates() {
+        let (mut board, ctx, id) = board_with_expression("(a & !b) | c");
+        let circuit = board.board.circuits.read().get(id).cloned().unwrap();
+        let (circuits, wires) = expand_into_gates(&mut board, &ctx, &circuit).unwrap();
+        assert_eq!(circuits.len(), 3);
+        assert!(!wires.is_empty());
+
+        assert_eq!(circuit_types(&board), ["and", "expression", "not", "or"]);
+        for circuit in board.board.circuits.read().iter() {
+            if &*circuit.ty == "expression" {
+                continue;
+            }
+            let info = circuit.info.read();
+            assert!(
+                info.pins.iter().all(|p| p.pin.read().wire.is_some()),
+                "{} gate isn't fully wired",
+                &*circuit.ty
+            );
+        }
+    }
+
+    #[test]
+    fn trivial_expressions_are_not_expanded() {
+        for text in ["a", "a & 1"] {
+            let (mut board, ctx, id) = board_with_expression(text);
+            let circuit = board.board.circuits.read().get(id).cloned().unwrap();
+            assert!(expand_into_gates(&mut board, &ctx, &circuit).is_err());
+            assert_eq!(circuit_types(&board), ["expression"]);
+        }
+    }
+}
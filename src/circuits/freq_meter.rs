@@ -1,4 +1,4 @@
-use eframe::epaint::{Color32, FontId, Rounding, Stroke};
+use eframe::epaint::{FontId, Rounding, Stroke};
 use emath::{vec2, Align2, Rect};
 
 use crate::{circuits::*, containers::ConstRingBuffer, Direction4};
@@ -34,8 +34,8 @@ impl FreqMeter {
     fn draw(ctx: &PaintContext, state: Option<&CircuitStateContext>, semi_transparent: bool) {
         let opacity = if semi_transparent { 0.6 } else { 1.0 };
 
-        let border_color = Color32::BLACK.linear_multiply(opacity);
-        let fill_color = Color32::from_gray(200).linear_multiply(opacity);
+        let border_color = ctx.style.component_border_color().linear_multiply(opacity);
+        let fill_color = ctx.style.component_fill_color().linear_multiply(opacity);
 
         let rect = Rect::from_min_size(
             ctx.rect.left_top() + ctx.rect.size() * vec2(0.5 / 5.0, 0.5 / 3.0),
@@ -40,8 +40,8 @@ impl GateImpl for And {
 
         let opacity = if in_world_preview { 0.6 } else { 1.0 };
 
-        let border_color = Color32::BLACK.linear_multiply(opacity);
-        let fill_color = Color32::from_gray(200).linear_multiply(opacity);
+        let border_color = ctx.style.component_border_color().linear_multiply(opacity);
+        let fill_color = ctx.style.component_fill_color().linear_multiply(opacity);
         let straightness = (0.3 / (ctx.screen.scale.sqrt())).div(height).max(0.02);
 
         let fill = [
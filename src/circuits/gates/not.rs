@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use eframe::epaint::{Color32, PathShape, Stroke};
+use eframe::epaint::{PathShape, Stroke};
 use emath::{pos2, vec2, Pos2};
 
 use crate::{
@@ -34,8 +34,8 @@ impl Not {
     fn draw(ctx: &PaintContext, angle: f32, semi_transparent: bool) {
         let opacity = if semi_transparent { 0.6 } else { 1.0 };
 
-        let border_color = Color32::BLACK.linear_multiply(opacity);
-        let fill_color = Color32::from_gray(200).linear_multiply(opacity);
+        let border_color = ctx.style.component_border_color().linear_multiply(opacity);
+        let fill_color = ctx.style.component_fill_color().linear_multiply(opacity);
 
         let size = vec2(2.0, 1.0);
         let transformer = |p: Pos2| {
@@ -0,0 +1,552 @@
+use eframe::egui::{self, DragValue, Ui, Widget};
+use eframe::epaint::{FontId, Rounding, Stroke};
+use emath::Align2;
+
+use crate::circuits::props::{CircuitProperty, CircuitPropertyImpl};
+use crate::circuits::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TruthValue {
+    False,
+    True,
+    DontCare,
+}
+
+impl TruthValue {
+    fn char(self) -> char {
+        match self {
+            TruthValue::False => '0',
+            TruthValue::True => '1',
+            TruthValue::DontCare => 'x',
+        }
+    }
+
+    fn from_char(char: char) -> Option<Self> {
+        match char {
+            '0' => Some(TruthValue::False),
+            '1' => Some(TruthValue::True),
+            'x' | 'X' | '-' => Some(TruthValue::DontCare),
+            _ => None,
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            TruthValue::False => TruthValue::True,
+            TruthValue::True => TruthValue::DontCare,
+            TruthValue::DontCare => TruthValue::False,
+        }
+    }
+
+    fn matches(self, state: &WireState) -> bool {
+        match self {
+            TruthValue::False => *state == WireState::False,
+            TruthValue::True => *state == WireState::True,
+            TruthValue::DontCare => true,
+        }
+    }
+
+    /// Don't-care outputs are left undriven
+    fn to_state(self) -> WireState {
+        match self {
+            TruthValue::False => WireState::False,
+            TruthValue::True => WireState::True,
+            TruthValue::DontCare => WireState::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruthTableRow {
+    /// Indexed by input pin
+    pub inputs: Vec<TruthValue>,
+    /// Indexed by output pin
+    pub outputs: Vec<TruthValue>,
+}
+
+impl TruthTableRow {
+    fn matches(&self, inputs: &[WireState]) -> bool {
+        self.inputs
+            .iter()
+            .zip(inputs.iter())
+            .all(|(v, s)| v.matches(s))
+    }
+
+    /// Row string, highest pins first: `01x:1`
+    fn save_str(&self) -> String {
+        let mut str = String::with_capacity(self.inputs.len() + self.outputs.len() + 1);
+        str.extend(self.inputs.iter().rev().map(|v| v.char()));
+        str.push(':');
+        str.extend(self.outputs.iter().rev().map(|v| v.char()));
+        str
+    }
+
+    fn parse(str: &str) -> Option<Self> {
+        let (inputs, outputs) = str.split_once(':')?;
+        let parse = |str: &str| -> Option<Vec<TruthValue>> {
+            str.trim()
+                .chars()
+                .rev()
+                .map(TruthValue::from_char)
+                .collect()
+        };
+        Some(Self {
+            inputs: parse(inputs)?,
+            outputs: parse(outputs)?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct TruthTableData {
+    inputs: u32,
+    outputs: u32,
+    rows: Vec<String>,
+}
+
+/// Truth table property, rows are matched top to bottom and first matching row sets outputs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruthTable {
+    pub inputs: u32,
+    pub outputs: u32,
+    pub rows: Vec<TruthTableRow>,
+}
+
+impl Default for TruthTable {
+    fn default() -> Self {
+        let mut table = Self {
+            inputs: 2,
+            outputs: 1,
+            rows: vec![],
+        };
+        table.fill_combinations();
+        for row in table.rows.iter_mut() {
+            let ones = row
+                .inputs
+                .iter()
+                .filter(|v| **v == TruthValue::True)
+                .count();
+            row.outputs[0] = match ones % 2 {
+                1 => TruthValue::True,
+                _ => TruthValue::False,
+            };
+        }
+        table
+    }
+}
+
+impl TruthTable {
+    pub const MAX_INPUTS: u32 = 8;
+    pub const MAX_OUTPUTS: u32 = 16;
+
+    /// Makes all rows match input and output counts
+    fn normalize(&mut self) {
+        self.inputs = self.inputs.clamp(1, Self::MAX_INPUTS);
+        self.outputs = self.outputs.clamp(1, Self::MAX_OUTPUTS);
+        for row in self.rows.iter_mut() {
+            row.inputs
+                .resize(self.inputs as usize, TruthValue::DontCare);
+            row.outputs.resize(self.outputs as usize, TruthValue::False);
+        }
+    }
+
+    /// Adds rows for all input combinations not yet fully specified, keeping existing ones
+    fn fill_combinations(&mut self) {
+        for value in 0..1u32 << self.inputs {
+            let inputs: Vec<_> = (0..self.inputs)
+                .map(|i| match value & (1 << i) != 0 {
+                    true => TruthValue::True,
+                    false => TruthValue::False,
+                })
+                .collect();
+            if self.rows.iter().any(|r| r.inputs == inputs) {
+                continue;
+            }
+            self.rows.push(TruthTableRow {
+                inputs,
+                outputs: vec![TruthValue::False; self.outputs as usize],
+            });
+        }
+    }
+
+    pub fn evaluate(&self, inputs: &[WireState]) -> Option<&TruthTableRow> {
+        self.rows.iter().find(|r| r.matches(inputs))
+    }
+
+    fn table_ui(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            ui.label("Inputs");
+            changed |= DragValue::new(&mut self.inputs)
+                .clamp_range(1..=Self::MAX_INPUTS)
+                .ui(ui)
+                .changed();
+            ui.label("Outputs");
+            changed |= DragValue::new(&mut self.outputs)
+                .clamp_range(1..=Self::MAX_OUTPUTS)
+                .ui(ui)
+                .changed();
+        });
+        if changed {
+            self.normalize();
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Add row").clicked() {
+                self.rows.push(TruthTableRow {
+                    inputs: vec![TruthValue::DontCare; self.inputs as usize],
+                    outputs: vec![TruthValue::False; self.outputs as usize],
+                });
+                changed = true;
+            }
+            if ui.button("Add all combinations").clicked() {
+                self.fill_combinations();
+                changed = true;
+            }
+            if ui.button("Clear").clicked() {
+                self.rows.clear();
+                changed = true;
+            }
+        });
+
+        ui.label("Click values to cycle 0, 1 and x (don't care)");
+        ui.separator();
+
+        let mut remove = None;
+        let mut swap = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("truth_table_grid")
+                .striped(true)
+                .show(ui, |ui| {
+                    for i in (0..self.inputs).rev() {
+                        ui.monospace(format!("I{i}"));
+                    }
+                    ui.label("│");
+                    for i in (0..self.outputs).rev() {
+                        ui.monospace(format!("O{i}"));
+                    }
+                    ui.end_row();
+
+                    let rows = self.rows.len();
+                    for (index, row) in self.rows.iter_mut().enumerate() {
+                        for value in row.inputs.iter_mut().rev() {
+                            if ui.small_button(value.char().to_string()).clicked() {
+                                *value = value.next();
+                                changed = true;
+                            }
+                        }
+                        ui.label("│");
+                        for value in row.outputs.iter_mut().rev() {
+                            if ui.small_button(value.char().to_string()).clicked() {
+                                *value = value.next();
+                                changed = true;
+                            }
+                        }
+                        if ui
+                            .add_enabled(index > 0, egui::Button::new("⏶").small())
+                            .clicked()
+                        {
+                            swap = Some(index - 1);
+                        }
+                        if ui
+                            .add_enabled(index + 1 < rows, egui::Button::new("⏷").small())
+                            .clicked()
+                        {
+                            swap = Some(index);
+                        }
+                        if ui.small_button("🗑").clicked() {
+                            remove = Some(index);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+
+        if let Some(index) = swap {
+            self.rows.swap(index, index + 1);
+            changed = true;
+        }
+        if let Some(index) = remove {
+            self.rows.remove(index);
+            changed = true;
+        }
+
+        changed
+    }
+}
+
+impl CircuitPropertyImpl for TruthTable {
+    fn equals(&self, other: &dyn CircuitPropertyImpl) -> bool {
+        other.is_type_and(|o: &Self| o == self)
+    }
+
+    fn ui(&mut self, ui: &mut Ui, not_equal: bool) -> Option<Box<dyn CircuitPropertyImpl>> {
+        let id = ui.make_persistent_id("truth_table_window");
+        let mut open = ui.data(|data| data.get_temp::<bool>(id).unwrap_or(false));
+
+        let text = match not_equal {
+            true => "Edit (different)".into(),
+            false => format!(
+                "Edit ({}x{}, {} rows)",
+                self.inputs,
+                self.outputs,
+                self.rows.len()
+            ),
+        };
+        if ui.button(text).clicked() {
+            open = !open;
+        }
+
+        let old = Clone::clone(self);
+        let mut changed = false;
+        egui::Window::new("Truth table")
+            .id(id)
+            .open(&mut open)
+            .vscroll(false)
+            .show(ui.ctx(), |ui| {
+                changed = self.table_ui(ui);
+            });
+        ui.data_mut(|data| data.insert_temp(id, open));
+
+        changed.then(|| Box::new(old) as Box<dyn CircuitPropertyImpl>)
+    }
+
+    fn clone(&self) -> Box<dyn CircuitPropertyImpl> {
+        Box::new(Clone::clone(self))
+    }
+
+    fn load(&mut self, data: &serde_intermediate::Intermediate) {
+        if let Ok(data) = serde_intermediate::from_intermediate::<TruthTableData>(data) {
+            self.inputs = data.inputs;
+            self.outputs = data.outputs;
+            self.rows = data
+                .rows
+                .iter()
+                .filter_map(|r| TruthTableRow::parse(r))
+                .collect();
+            self.normalize();
+        }
+    }
+
+    fn save(&self) -> serde_intermediate::Intermediate {
+        let data = TruthTableData {
+            inputs: self.inputs,
+            outputs: self.outputs,
+            rows: self.rows.iter().map(|r| r.save_str()).collect(),
+        };
+        serde_intermediate::to_intermediate(&data).unwrap_or_default()
+    }
+
+    fn copy_into(&self, other: &mut dyn CircuitPropertyImpl) {
+        if let Some(r) = other.downcast_mut::<Self>() {
+            *r = Clone::clone(self);
+        }
+    }
+}
+
+struct LookupTable {
+    inputs: Box<[CircuitPinInfo]>,
+    outputs: Box<[CircuitPinInfo]>,
+    table: TruthTable,
+}
+
+impl LookupTable {
+    fn new() -> Self {
+        let table = TruthTable::default();
+        let description = Self::describe(Direction4::Right, &table);
+        let (inputs, outputs) = description.pins.split_at(table.inputs as usize);
+        Self {
+            inputs: inputs.iter().map(|d| d.to_info()).collect(),
+            outputs: outputs.iter().map(|d| d.to_info()).collect(),
+            table,
+        }
+    }
+
+    fn draw(ctx: &PaintContext, semi_transparent: bool) {
+        let opacity = if semi_transparent { 0.6 } else { 1.0 };
+
+        let border_color = ctx.style.component_border_color().linear_multiply(opacity);
+        let fill_color = ctx.style.component_fill_color().linear_multiply(opacity);
+
+        ctx.paint.rect(
+            ctx.rect.shrink(ctx.screen.scale * 0.25),
+            Rounding::same(ctx.screen.scale * 0.15),
+            fill_color,
+            Stroke::new(0.15 * ctx.screen.scale, border_color),
+        );
+
+        ctx.paint.text(
+            ctx.rect.center(),
+            Align2::CENTER_CENTER,
+            "LUT",
+            FontId::monospace(ctx.screen.scale * 0.6),
+            border_color,
+        );
+    }
+
+    fn read_table(props: &CircuitPropertyStore) -> TruthTable {
+        props.read_clone("table").unwrap_or_default()
+    }
+
+    fn describe_props(props: &CircuitPropertyStore) -> DynCircuitDescription {
+        let dir = props.read_clone("dir").unwrap_or(Direction4::Right);
+        Self::describe(dir, &Self::read_table(props))
+    }
+
+    fn describe(dir: Direction4, table: &TruthTable) -> DynCircuitDescription {
+        let height = table.inputs.max(table.outputs);
+        let size = Vec2u::new(3, height);
+
+        let pos = |x: u32, y: u32| -> Vec2u {
+            let x = if dir.is_right_bottom() { x } else { 2 - x };
+            if dir.is_horizontal() {
+                [x, y].into()
+            } else {
+                [y, x].into()
+            }
+        };
+
+        let mut pins = vec![];
+        for i in 0..table.inputs {
+            pins.push(CircuitPinDescription {
+                active: true,
+                display_name: format!("In {i}").into(),
+                display_dir: Some(dir.inverted()),
+                dir: InternalPinDirection::Inside,
+                name: format!("in_{i}").into(),
+                pos: pos(0, i),
+            });
+        }
+        for i in 0..table.outputs {
+            pins.push(CircuitPinDescription {
+                active: true,
+                display_name: format!("Out {i}").into(),
+                display_dir: Some(dir),
+                dir: InternalPinDirection::Outside,
+                name: format!("out_{i}").into(),
+                pos: pos(2, i),
+            });
+        }
+
+        DynCircuitDescription {
+            size: if dir.is_horizontal() {
+                size
+            } else {
+                [size.y, size.x].into()
+            },
+            pins: pins.into(),
+        }
+    }
+}
+
+impl CircuitImpl for LookupTable {
+    fn draw(&self, _: &CircuitStateContext, paint_ctx: &PaintContext) {
+        LookupTable::draw(paint_ctx, false);
+    }
+
+    fn create_pins(&mut self, circ: &Arc<Circuit>) -> Box<[CircuitPinInfo]> {
+        self.table = Self::read_table(&circ.props);
+        let description = Self::describe_props(&circ.props);
+        let (inputs, outputs) = description.pins.split_at(self.table.inputs as usize);
+        self.inputs = inputs.iter().map(|d| d.to_info()).collect();
+        self.outputs = outputs.iter().map(|d| d.to_info()).collect();
+
+        let mut vec: Vec<_> = self.inputs.to_vec();
+        vec.extend(self.outputs.iter().cloned());
+        vec.into()
+    }
+
+    fn update_signals(&self, state_ctx: &CircuitStateContext, changed_pin: Option<usize>) {
+        if changed_pin.is_some_and(|p| p >= self.inputs.len()) {
+            return;
+        }
+
+        let inputs: Vec<_> = self.inputs.iter().map(|p| p.get_state(state_ctx)).collect();
+        match self.table.evaluate(&inputs) {
+            Some(row) => {
+                for (pin, value) in self.outputs.iter().zip(row.outputs.iter()) {
+                    pin.set_state(state_ctx, value.to_state());
+                }
+            }
+            None => {
+                // Unmatched combinations output 0, unless some input isn't a valid value
+                let valid = inputs
+                    .iter()
+                    .all(|s| matches!(s, WireState::True | WireState::False));
+                let state = match valid {
+                    true => WireState::False,
+                    false => WireState::Error,
+                };
+                for pin in self.outputs.iter() {
+                    pin.set_state(state_ctx, state.clone());
+                }
+            }
+        }
+    }
+
+    fn size(&self, circ: &Arc<Circuit>) -> Vec2u {
+        Self::describe_props(&circ.props).size
+    }
+
+    fn prop_changed(&self, prop_id: &str, resize: &mut bool, recreate_pins: &mut bool) {
+        if prop_id == "dir" || prop_id == "table" {
+            *resize = true;
+            *recreate_pins = true;
+        }
+    }
+
+    fn apply_props(&mut self, circ: &Arc<Circuit>, _: Option<&str>) {
+        self.table = Self::read_table(&circ.props);
+    }
+}
+
+pub struct LookupTablePreview {}
+
+impl CircuitPreviewImpl for LookupTablePreview {
+    fn type_name(&self) -> DynStaticStr {
+        "lookup_table".into()
+    }
+
+    fn display_name(&self) -> DynStaticStr {
+        "Lookup table".into()
+    }
+
+    fn description(&self) -> DynStaticStr {
+        "Circuit defined by a truth table.\n\
+         Rows are checked top to bottom and the first one matching current inputs sets outputs. \
+         Inputs marked x (don't care) match any value, outputs marked x are left undriven.\n\
+         Input combinations not matched by any row output 0.\
+        "
+        .into()
+    }
+
+    fn draw_preview(&self, _: &CircuitPropertyStore, ctx: &PaintContext, in_world: bool) {
+        LookupTable::draw(ctx, in_world);
+    }
+
+    fn create_impl(&self) -> Box<dyn CircuitImpl> {
+        Box::new(LookupTable::new())
+    }
+
+    fn load_copy_data(
+        &self,
+        _imp: &serde_intermediate::Intermediate,
+        _internal: &serde_intermediate::Intermediate,
+        _ctx: &Arc<SimulationContext>,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn CircuitPreviewImpl>> {
+        Some(Box::new(LookupTablePreview {}))
+    }
+
+    fn default_props(&self) -> CircuitPropertyStore {
+        CircuitPropertyStore::new([
+            CircuitProperty::new("dir", "Direction", Direction4::Right),
+            CircuitProperty::new("table", "Truth table", TruthTable::default()),
+        ])
+    }
+
+    fn describe(&self, props: &CircuitPropertyStore) -> DynCircuitDescription {
+        LookupTable::describe_props(props)
+    }
+}
@@ -0,0 +1,330 @@
+use eframe::egui::Ui;
+use eframe::epaint::{FontId, Rounding, Stroke};
+use emath::Align2;
+
+use crate::circuits::props::{CircuitProperty, CircuitPropertyImpl};
+use crate::circuits::*;
+use crate::io::{CircuitBoardData, CircuitData, CircuitStateData};
+use crate::state::CircuitState;
+
+/// Property of a circuit with unknown type, kept exactly as it was loaded
+#[derive(Clone, PartialEq)]
+pub struct RawProperty(serde_intermediate::Intermediate);
+
+impl CircuitPropertyImpl for RawProperty {
+    fn equals(&self, other: &dyn CircuitPropertyImpl) -> bool {
+        other.is_type_and(|o: &Self| o == self)
+    }
+
+    fn ui(&mut self, ui: &mut Ui, _not_equal: bool) -> Option<Box<dyn CircuitPropertyImpl>> {
+        ui.weak("unavailable");
+        None
+    }
+
+    fn clone(&self) -> Box<dyn CircuitPropertyImpl> {
+        Box::new(Clone::clone(self))
+    }
+
+    fn load(&mut self, data: &serde_intermediate::Intermediate) {
+        self.0 = data.clone();
+    }
+
+    fn save(&self) -> serde_intermediate::Intermediate {
+        self.0.clone()
+    }
+
+    fn copy_into(&self, other: &mut dyn CircuitPropertyImpl) {
+        if let Some(r) = other.downcast_mut::<Self>() {
+            r.0.clone_from(&self.0);
+        }
+    }
+}
+
+/// Pin states as they were saved, in pin order of the original type
+#[derive(Clone)]
+struct RawPinStates {
+    pins: Vec<Option<WireState>>,
+    pin_dirs: Vec<Option<PinDirection>>,
+}
+
+/// Internal state of a circuit with unknown type.
+/// Loaded pin states are kept too, as pin order of the original type isn't known
+struct RawInternalState {
+    internal: serde_intermediate::Intermediate,
+    pins: Option<RawPinStates>,
+}
+
+impl InternalCircuitState for RawInternalState {
+    fn serialize(&self, _copy: bool) -> serde_intermediate::Intermediate {
+        self.internal.clone()
+    }
+}
+
+/// Everything that could be recovered about a circuit with unknown type
+struct MissingCircuitInfo {
+    ty: DynStaticStr,
+    size: Vec2u,
+    pins: Vec<(DynStaticStr, Vec2u)>,
+    props: Vec<DynStaticStr>,
+}
+
+impl MissingCircuitInfo {
+    fn describe(&self) -> DynCircuitDescription {
+        let pins = self
+            .pins
+            .iter()
+            .map(|(name, pos)| CircuitPinDescription {
+                active: true,
+                display_name: name.clone(),
+                display_dir: edge_pin_dir(*pos, self.size),
+                dir: InternalPinDirection::Inside,
+                name: name.clone(),
+                pos: *pos,
+            })
+            .collect();
+        DynCircuitDescription {
+            size: self.size,
+            pins,
+        }
+    }
+
+    fn draw(&self, ctx: &PaintContext, semi_transparent: bool) {
+        let opacity = if semi_transparent { 0.6 } else { 1.0 };
+
+        let rect = ctx.rect.shrink(ctx.screen.scale * 0.25);
+        ctx.paint.rect(
+            rect,
+            Rounding::same(ctx.screen.scale * 0.15),
+            ctx.style.component_fill_color().linear_multiply(opacity),
+            Stroke::new(
+                ctx.screen.scale * 0.15,
+                Color32::from_rgb(200, 0, 0).linear_multiply(opacity),
+            ),
+        );
+        ctx.paint.text(
+            rect.center(),
+            Align2::CENTER_CENTER,
+            format!("? {}", self.ty.deref()),
+            FontId::monospace(ctx.screen.scale * 0.4),
+            ctx.style.component_border_color().linear_multiply(opacity),
+        );
+    }
+}
+
+/// Stand-in for circuits which type isn't registered, for example removed or renamed ones.<br>
+/// Keeps loaded data and saves it back unchanged.
+pub struct MissingCircuit {
+    info: Arc<MissingCircuitInfo>,
+    imp: serde_intermediate::Intermediate,
+}
+
+impl CircuitImpl for MissingCircuit {
+    fn draw(&self, _: &CircuitStateContext, paint_ctx: &PaintContext) {
+        self.info.draw(paint_ctx, false);
+    }
+
+    fn create_pins(&mut self, _: &Arc<Circuit>) -> Box<[CircuitPinInfo]> {
+        self.info
+            .describe()
+            .pins
+            .iter()
+            .map(|p| p.to_info())
+            .collect()
+    }
+
+    fn update_signals(&self, _: &CircuitStateContext, _: Option<usize>) {}
+
+    fn size(&self, _: &Arc<Circuit>) -> Vec2u {
+        self.info.size
+    }
+
+    fn save(&self, _: &Arc<Circuit>, _copy: bool) -> serde_intermediate::Intermediate {
+        self.imp.clone()
+    }
+
+    fn load(
+        &mut self,
+        _: &Arc<Circuit>,
+        data: &serde_intermediate::Intermediate,
+        _paste: bool,
+        _errors: &mut ErrorList,
+    ) {
+        self.imp = data.clone();
+    }
+
+    fn load_internal(
+        &self,
+        _ctx: &CircuitStateContext,
+        data: &serde_intermediate::Intermediate,
+        _paste: bool,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn InternalCircuitState>> {
+        Some(Box::new(RawInternalState {
+            internal: data.clone(),
+            pins: None,
+        }))
+    }
+}
+
+impl MissingCircuit {
+    pub fn preview(&self) -> MissingPreview {
+        MissingPreview {
+            info: self.info.clone(),
+        }
+    }
+
+    /// State loaded from a save, kept to be saved back unchanged
+    pub fn load_state(data: &CircuitStateData) -> Box<dyn InternalCircuitState> {
+        Box::new(RawInternalState {
+            internal: data.internal.clone(),
+            pins: Some(RawPinStates {
+                pins: data.pins.clone(),
+                pin_dirs: data.pin_dirs.clone(),
+            }),
+        })
+    }
+
+    /// State loaded with [`Self::load_state`], None for other states
+    pub fn saved_state(state: &CircuitState) -> Option<CircuitStateData> {
+        let internal = state.internal.as_ref()?;
+        if (**internal).type_id() != TypeId::of::<RawInternalState>() {
+            return None;
+        }
+        let raw = state.get_internal::<RawInternalState>()?;
+        let RawPinStates { pins, pin_dirs } = raw.pins.clone()?;
+        Some(CircuitStateData {
+            pins,
+            pin_dirs,
+            internal: raw.internal.clone(),
+        })
+    }
+}
+
+/// Preview for a single circuit with unknown type, never registered in [`SimulationContext::previews`]
+#[derive(Clone)]
+pub struct MissingPreview {
+    info: Arc<MissingCircuitInfo>,
+}
+
+impl std::fmt::Debug for MissingPreview {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MissingPreview")
+            .field("ty", &self.info.ty)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MissingPreview {
+    /// Recovers circuit size and pins from wires connected to it.
+    /// Pins are kept in their saved order, which is the order of the original type
+    pub fn new(id: usize, circuit: &CircuitData, board: &CircuitBoardData) -> Self {
+        let mut pins: Vec<(DynStaticStr, Vec2u)> = vec![];
+        for (name, wire) in circuit.pin_wires.iter() {
+            let wire = board.wires.get(*wire).and_then(|w| w.as_ref());
+            let wire = unwrap_option_or_continue!(wire);
+            let pos = wire.points.iter().find_map(|(pos, point)| {
+                let pin = point.pin.as_ref()?;
+                (pin.circuit == id && pin.name == *name).then_some(*pos)
+            });
+            let rel = unwrap_option_or_continue!(pos) - circuit.pos;
+            if rel.x < 0 || rel.y < 0 || pins.iter().any(|p| p.0 == *name) {
+                continue;
+            }
+            pins.push((name.clone(), rel.convert(|v| v as u32)));
+        }
+
+        let size = Self::pins_size(&pins);
+
+        let props = circuit
+            .props
+            .0
+            .keys()
+            .filter(|id| !matches!(&***id, "name" | "label_dir"))
+            .cloned()
+            .collect();
+
+        Self {
+            info: Arc::new(MissingCircuitInfo {
+                ty: circuit.ty.clone(),
+                size,
+                pins,
+                props,
+            }),
+        }
+    }
+
+    /// Smallest size covering all `pins`, at least a single tile
+    fn pins_size(pins: &[(DynStaticStr, Vec2u)]) -> Vec2u {
+        pins.iter().fold(Vec2u::from([1, 1]), |size, (_, pos)| {
+            [size.x.max(pos.x + 1), size.y.max(pos.y + 1)].into()
+        })
+    }
+
+    /// Stand-in for a foreign circuit with no counterpart, for example an unsupported imported part
+    pub fn new_placeholder(ty: DynStaticStr, pins: Vec<(DynStaticStr, Vec2u)>) -> Self {
+        let size = Self::pins_size(&pins);
+
+        Self {
+            info: Arc::new(MissingCircuitInfo {
+                ty,
+                size,
+                pins,
+                props: vec![],
+            }),
+        }
+    }
+}
+
+impl CircuitPreviewImpl for MissingPreview {
+    fn type_name(&self) -> DynStaticStr {
+        self.info.ty.clone()
+    }
+
+    fn display_name(&self) -> DynStaticStr {
+        format!("Missing {}", self.info.ty.deref()).into()
+    }
+
+    fn description(&self) -> DynStaticStr {
+        "Circuit of a type that isn't available.\n\
+         Its data is kept and saved unchanged, so it will work again once the type is available.\
+        "
+        .into()
+    }
+
+    fn draw_preview(&self, _: &CircuitPropertyStore, ctx: &PaintContext, in_world: bool) {
+        self.info.draw(ctx, in_world);
+    }
+
+    fn create_impl(&self) -> Box<dyn CircuitImpl> {
+        Box::new(MissingCircuit {
+            info: self.info.clone(),
+            imp: serde_intermediate::Intermediate::Unit,
+        })
+    }
+
+    fn load_copy_data(
+        &self,
+        _imp: &serde_intermediate::Intermediate,
+        _internal: &serde_intermediate::Intermediate,
+        _ctx: &Arc<SimulationContext>,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn CircuitPreviewImpl>> {
+        Some(Box::new(MissingPreview {
+            info: self.info.clone(),
+        }))
+    }
+
+    fn default_props(&self) -> CircuitPropertyStore {
+        CircuitPropertyStore::new(self.info.props.iter().map(|id| {
+            CircuitProperty::new(
+                id.clone(),
+                id.clone(),
+                RawProperty(serde_intermediate::Intermediate::Unit),
+            )
+        }))
+    }
+
+    fn describe(&self, _: &CircuitPropertyStore) -> DynCircuitDescription {
+        self.info.describe()
+    }
+}
@@ -32,13 +32,24 @@ pub mod button;
 pub mod freq_meter;
 pub mod gates;
 pub mod led;
+pub mod lookup_table;
+pub mod missing;
 pub mod pin;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod plugin;
 #[macro_use]
 pub mod props;
 pub mod clock;
+pub mod expression;
+pub mod probe;
 pub mod pullup;
+pub mod pulse;
+pub mod random;
 pub mod relay;
+pub mod script;
+pub mod splitter;
 pub mod transistor;
+pub mod tunnel;
 
 // so templates are always valid
 
@@ -469,6 +480,20 @@ impl Circuit {
             })
             .unwrap_or_default();
 
+        let state_internals = self
+            .board
+            .states
+            .states
+            .read()
+            .iter()
+            .filter_map(|state| {
+                let internal = state.read_circuit(self.id, |circuit| {
+                    circuit.internal.as_ref().map(|i| i.serialize(false))
+                });
+                internal.flatten().map(|i| (state.id, i))
+            })
+            .collect();
+
         let design = self.board.designs.read().current().clone();
         let mut controls = FixedVec::new();
         for (&(circuit, control), design) in design.controls.iter() {
@@ -501,6 +526,8 @@ impl Circuit {
                 .flatten(),
             props: self.props.save(),
             design_controls: controls.inner,
+            placeholder: self.read_imp(|m: &missing::MissingCircuit| m.preview()),
+            state_internals,
         }
     }
 
@@ -767,6 +794,27 @@ impl CircuitPreview {
         }
     }
 
+    /// Preview of copied circuit, placeholder is used only if its type isn't available
+    pub fn from_copy(
+        data: &crate::io::CircuitCopyData,
+        ctx: &Arc<SimulationContext>,
+        errors: &mut ErrorList,
+    ) -> Option<Self> {
+        let placeholder = data
+            .placeholder
+            .clone()
+            .map(|p| CircuitPreview::from_impl(Box::new(p)));
+        let preview = match ctx.previews.get(&data.ty) {
+            Some(preview) => Some(preview.deref()),
+            None => placeholder.as_ref(),
+        };
+        errors
+            .report_none(preview, || {
+                format!("circuit {} does not exist", data.ty.deref())
+            })?
+            .load_copy(data, ctx, errors)
+    }
+
     pub fn load_with_data(
         imp: Box<dyn CircuitPreviewImpl>,
         data: &crate::io::CircuitPreviewData,
@@ -939,6 +987,21 @@ const fn rotate_pos(pos: [u32; 2], size: [u32; 2], dir: Direction4) -> [u32; 2]
     }
 }
 
+/// Direction of a pin at `pos` on the edge of a circuit, `None` for pins inside it
+fn edge_pin_dir(pos: Vec2u, size: Vec2u) -> Option<Direction4> {
+    if pos.x == 0 {
+        Some(Direction4::Left)
+    } else if pos.x + 1 == size.x {
+        Some(Direction4::Right)
+    } else if pos.y == 0 {
+        Some(Direction4::Up)
+    } else if pos.y + 1 == size.y {
+        Some(Direction4::Down)
+    } else {
+        None
+    }
+}
+
 #[macro_export]
 macro_rules! expr_or_default {
     ($e:expr, $def:expr) => {
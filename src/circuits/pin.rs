@@ -66,7 +66,6 @@ enum PinIdType {
     Pasted,
     Loaded,
     Random,
-    BackCompat,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -189,13 +188,6 @@ impl Pin {
         }
     }
 
-    fn gen_back_compat_id(pos: Vec2i) -> String {
-        let hex_x = format!("{}{:x}", if pos.x < 0 { "-" } else { "" }, pos.x.abs());
-        let hex_y = format!("{}{:x}", if pos.y < 0 { "-" } else { "" }, pos.y.abs());
-
-        format!("{:x}:{:x}:{}{}", hex_x.len(), hex_y.len(), hex_x, hex_y)
-    }
-
     fn gen_random_id() -> String {
         format!("{:x}", random_u128())
     }
@@ -554,9 +546,6 @@ impl CircuitImpl for Pin {
             PinIdType::Random => {
                 self.id = Self::gen_random_id().into();
             }
-            PinIdType::BackCompat => {
-                self.id = Self::gen_back_compat_id(circ.pos).into();
-            }
         }
         board_pins.insert(self.id.clone(), circ.id);
         drop(board_pins);
@@ -645,10 +634,7 @@ impl CircuitImpl for Pin {
         paste: bool,
         _errors: &mut ErrorList,
     ) {
-        // Old data, use back-compat id
-        if matches!(data, serde_intermediate::Intermediate::Unit) {
-            self.id_ty = PinIdType::BackCompat;
-        } else if let Ok(m) = serde_intermediate::from_intermediate::<PinModel>(data) {
+        if let Ok(m) = serde_intermediate::from_intermediate::<PinModel>(data) {
             if let Some(id) = m.id {
                 if paste {
                     self.id_ty = PinIdType::Pasted;
@@ -0,0 +1,578 @@
+use std::ffi::{c_char, c_void, CStr};
+use std::path::{Path, PathBuf};
+
+use eframe::epaint::{FontId, Rounding, Stroke};
+use emath::{vec2, Align2};
+
+use crate::circuits::*;
+
+// Native plugins are dynamic libraries placed in `plugins` directory next to the executable.
+// Each library exports two functions:
+//
+// extern "C" fn cuprous_plugin_abi_version() -> u32
+// extern "C" fn cuprous_plugin_entry() -> *const PluginEntry
+//
+// Libraries reporting a different ABI version than `PLUGIN_ABI_VERSION` are not loaded.
+// All data returned by the plugin must stay valid for as long as the library is loaded.
+//
+// Pin values are passed as one byte per bit, see `PLUGIN_STATE_*`.
+// Every circuit state gets its own zero-initialized `state_size` bytes of memory,
+// which are saved together with the board.
+// Drawing is done through `PluginDrawApi`, with coordinates in tiles relative to circuit's top left corner
+// and colors as 0xRRGGBBAA.
+
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+pub const PLUGIN_STATE_NONE: u8 = 0;
+pub const PLUGIN_STATE_FALSE: u8 = 1;
+pub const PLUGIN_STATE_TRUE: u8 = 2;
+pub const PLUGIN_STATE_ERROR: u8 = 3;
+
+const ABI_VERSION_SYMBOL: &[u8] = b"cuprous_plugin_abi_version";
+const ENTRY_SYMBOL: &[u8] = b"cuprous_plugin_entry";
+
+#[repr(C)]
+pub struct PluginEntry {
+    pub name: *const c_char,
+    pub circuits: *const PluginCircuitInfo,
+    pub circuit_count: usize,
+}
+
+#[repr(C)]
+pub struct PluginPinInfo {
+    pub name: *const c_char,
+    pub x: u32,
+    pub y: u32,
+    pub bits: u32,
+    pub output: bool,
+}
+
+#[repr(C)]
+pub struct PluginCircuitInfo {
+    pub type_name: *const c_char,
+    pub display_name: *const c_char,
+    pub description: *const c_char,
+    pub width: u32,
+    pub height: u32,
+    pub pins: *const PluginPinInfo,
+    pub pin_count: usize,
+    pub state_size: usize,
+
+    /// Called when inputs change. Output pin values can be changed, input ones are ignored
+    pub update: extern "C" fn(pins: *mut u8, pins_len: usize, state: *mut u8, state_len: usize),
+
+    /// Optional, circuit is drawn as a box with its name if not set.
+    /// `state` is null when drawing previews
+    pub draw: Option<extern "C" fn(api: *const PluginDrawApi, state: *const u8, state_len: usize)>,
+}
+
+#[repr(C)]
+pub struct PluginDrawApi {
+    pub ctx: *const c_void,
+    pub rect:
+        extern "C" fn(ctx: *const c_void, x: f32, y: f32, w: f32, h: f32, fill: u32, stroke: u32),
+    pub line: extern "C" fn(
+        ctx: *const c_void,
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+        width: f32,
+        color: u32,
+    ),
+    pub circle:
+        extern "C" fn(ctx: *const c_void, x: f32, y: f32, radius: f32, fill: u32, stroke: u32),
+    pub text: extern "C" fn(
+        ctx: *const c_void,
+        x: f32,
+        y: f32,
+        size: f32,
+        text: *const c_char,
+        color: u32,
+    ),
+}
+
+struct DrawTarget<'a> {
+    ctx: &'a PaintContext<'a>,
+    opacity: f32,
+}
+
+impl<'a> DrawTarget<'a> {
+    fn pos(&self, x: f32, y: f32) -> emath::Pos2 {
+        self.ctx.rect.left_top() + vec2(x, y) * self.ctx.screen.scale
+    }
+
+    fn color(&self, color: u32) -> Color32 {
+        let [r, g, b, a] = color.to_be_bytes();
+        Color32::from_rgba_unmultiplied(r, g, b, a).linear_multiply(self.opacity)
+    }
+
+    fn stroke(&self, color: u32) -> Stroke {
+        Stroke::new(self.ctx.screen.scale * 0.1, self.color(color))
+    }
+
+    fn get(ctx: *const c_void) -> &'a Self {
+        unsafe { &*(ctx as *const Self) }
+    }
+
+    extern "C" fn rect(ctx: *const c_void, x: f32, y: f32, w: f32, h: f32, fill: u32, stroke: u32) {
+        let this = Self::get(ctx);
+        let rect = Rect::from_min_size(this.pos(x, y), vec2(w, h) * this.ctx.screen.scale);
+        this.ctx
+            .paint
+            .rect(rect, Rounding::ZERO, this.color(fill), this.stroke(stroke));
+    }
+
+    extern "C" fn line(
+        ctx: *const c_void,
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+        width: f32,
+        color: u32,
+    ) {
+        let this = Self::get(ctx);
+        let stroke = Stroke::new(width * this.ctx.screen.scale, this.color(color));
+        this.ctx
+            .paint
+            .line_segment([this.pos(x1, y1), this.pos(x2, y2)], stroke);
+    }
+
+    extern "C" fn circle(ctx: *const c_void, x: f32, y: f32, radius: f32, fill: u32, stroke: u32) {
+        let this = Self::get(ctx);
+        this.ctx.paint.circle(
+            this.pos(x, y),
+            radius * this.ctx.screen.scale,
+            this.color(fill),
+            this.stroke(stroke),
+        );
+    }
+
+    extern "C" fn text(
+        ctx: *const c_void,
+        x: f32,
+        y: f32,
+        size: f32,
+        text: *const c_char,
+        color: u32,
+    ) {
+        let this = Self::get(ctx);
+        if text.is_null() {
+            return;
+        }
+        let text = unsafe { CStr::from_ptr(text) }.to_string_lossy();
+        this.ctx.paint.text(
+            this.pos(x, y),
+            Align2::CENTER_CENTER,
+            text,
+            FontId::proportional(size * this.ctx.screen.scale),
+            this.color(color),
+        );
+    }
+
+    fn draw(ctx: &PaintContext, opacity: f32, f: impl FnOnce(&PluginDrawApi)) {
+        let target = DrawTarget { ctx, opacity };
+        let api = PluginDrawApi {
+            ctx: &target as *const DrawTarget as *const c_void,
+            rect: Self::rect,
+            line: Self::line,
+            circle: Self::circle,
+            text: Self::text,
+        };
+        f(&api);
+    }
+}
+
+fn read_str(ptr: *const c_char) -> Option<Arc<str>> {
+    if ptr.is_null() {
+        return None;
+    }
+    let str = unsafe { CStr::from_ptr(ptr) };
+    Some(str.to_string_lossy().into())
+}
+
+fn to_plugin_state(state: &WireState, bits: u32, out: &mut Vec<u8>) {
+    for i in 0..bits as usize {
+        let state = match bits {
+            1 => state.clone(),
+            _ => state.get(i),
+        };
+        out.push(match state {
+            WireState::None | WireState::Bundle(_) => PLUGIN_STATE_NONE,
+            WireState::False => PLUGIN_STATE_FALSE,
+            WireState::True => PLUGIN_STATE_TRUE,
+            WireState::Error => PLUGIN_STATE_ERROR,
+        });
+    }
+}
+
+fn from_plugin_state(values: &[u8]) -> WireState {
+    let single = |v: u8| match v {
+        PLUGIN_STATE_FALSE => WireState::False,
+        PLUGIN_STATE_TRUE => WireState::True,
+        PLUGIN_STATE_ERROR => WireState::Error,
+        _ => WireState::None,
+    };
+    match values {
+        [v] => single(*v),
+        values => {
+            let mut state = WireState::Bundle(Default::default());
+            for (i, v) in values.iter().enumerate() {
+                state.set(i, single(*v));
+            }
+            state
+        }
+    }
+}
+
+struct PluginPin {
+    name: Arc<str>,
+    pos: Vec2u,
+    bits: u32,
+    output: bool,
+}
+
+/// Circuit type provided by a plugin, with everything read from it copied to host memory
+struct PluginCircuitType {
+    // Keeps function pointers valid
+    _library: Arc<libloading::Library>,
+
+    plugin: Arc<str>,
+    type_name: Arc<str>,
+    display_name: Arc<str>,
+    description: Arc<str>,
+    size: Vec2u,
+    pins: Vec<PluginPin>,
+    state_size: usize,
+    update: extern "C" fn(*mut u8, usize, *mut u8, usize),
+    draw: Option<extern "C" fn(*const PluginDrawApi, *const u8, usize)>,
+}
+
+impl PluginCircuitType {
+    fn read(
+        info: &PluginCircuitInfo,
+        plugin: &Arc<str>,
+        library: &Arc<libloading::Library>,
+    ) -> Result<Self, String> {
+        let type_name = read_str(info.type_name).ok_or("circuit without type name")?;
+        let size: Vec2u = [info.width, info.height].into();
+        if size.x == 0 || size.y == 0 {
+            return Err(format!("circuit {type_name} has zero size"));
+        }
+
+        let pin_infos = match info.pin_count {
+            0 => &[][..],
+            _ if info.pins.is_null() => return Err(format!("circuit {type_name} has no pin list")),
+            count => unsafe { std::slice::from_raw_parts(info.pins, count) },
+        };
+        let mut pins = vec![];
+        for pin in pin_infos {
+            let name =
+                read_str(pin.name).ok_or_else(|| format!("circuit {type_name} has unnamed pin"))?;
+            if pin.x >= size.x || pin.y >= size.y {
+                return Err(format!(
+                    "pin {name} of circuit {type_name} is outside of it"
+                ));
+            }
+            pins.push(PluginPin {
+                name,
+                pos: [pin.x, pin.y].into(),
+                bits: pin.bits.clamp(1, 64),
+                output: pin.output,
+            });
+        }
+
+        Ok(Self {
+            _library: library.clone(),
+            plugin: plugin.clone(),
+            display_name: read_str(info.display_name).unwrap_or_else(|| type_name.clone()),
+            description: read_str(info.description).unwrap_or_default(),
+            type_name,
+            size,
+            pins,
+            state_size: info.state_size,
+            update: info.update,
+            draw: info.draw,
+        })
+    }
+
+    fn describe(&self) -> DynCircuitDescription {
+        let size = self.size;
+
+        let pins = self
+            .pins
+            .iter()
+            .map(|pin| CircuitPinDescription {
+                active: true,
+                display_name: pin.name.clone().into(),
+                display_dir: edge_pin_dir(pin.pos, size),
+                dir: match pin.output {
+                    true => InternalPinDirection::Outside,
+                    false => InternalPinDirection::Inside,
+                },
+                name: pin.name.clone().into(),
+                pos: pin.pos,
+            })
+            .collect();
+
+        DynCircuitDescription { size, pins }
+    }
+
+    fn draw(&self, state: Option<&[u8]>, ctx: &PaintContext, semi_transparent: bool) {
+        let opacity = if semi_transparent { 0.6 } else { 1.0 };
+
+        let draw = match self.draw {
+            Some(draw) => draw,
+            None => {
+                let rect = ctx.rect.shrink(ctx.screen.scale * 0.25);
+                ctx.paint.rect(
+                    rect,
+                    Rounding::same(ctx.screen.scale * 0.15),
+                    ctx.style.component_fill_color().linear_multiply(opacity),
+                    Stroke::new(
+                        ctx.screen.scale * 0.15,
+                        ctx.style.component_border_color().linear_multiply(opacity),
+                    ),
+                );
+                ctx.paint.text(
+                    rect.center(),
+                    Align2::CENTER_CENTER,
+                    &self.display_name,
+                    FontId::proportional(ctx.screen.scale * 0.5),
+                    ctx.style.component_border_color().linear_multiply(opacity),
+                );
+                return;
+            }
+        };
+
+        let (ptr, len) = match state {
+            Some(state) => (state.as_ptr(), state.len()),
+            None => (std::ptr::null(), 0),
+        };
+        DrawTarget::draw(ctx, opacity, |api| draw(api, ptr, len));
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct PluginCircuitState {
+    data: Vec<u8>,
+}
+
+impl InternalCircuitState for PluginCircuitState {
+    fn serialize(&self, _copy: bool) -> serde_intermediate::Intermediate {
+        serde_intermediate::to_intermediate(self).unwrap_or_default()
+    }
+}
+
+struct PluginCircuit {
+    ty: Arc<PluginCircuitType>,
+    pins: Box<[CircuitPinInfo]>,
+}
+
+impl PluginCircuit {
+    fn state_data<R>(
+        &self,
+        state_ctx: &CircuitStateContext,
+        f: impl FnOnce(&mut Vec<u8>) -> R,
+    ) -> R {
+        state_ctx.write_circuit_internal_state(|s: &mut PluginCircuitState| {
+            s.data.resize(self.ty.state_size, 0);
+            f(&mut s.data)
+        })
+    }
+}
+
+impl CircuitImpl for PluginCircuit {
+    fn draw(&self, state_ctx: &CircuitStateContext, paint_ctx: &PaintContext) {
+        let state = state_ctx.read_circuit_internal_state(|s: &PluginCircuitState| s.data.clone());
+        let state = state.filter(|s| s.len() == self.ty.state_size);
+        self.ty
+            .draw(Some(state.as_deref().unwrap_or(&[])), paint_ctx, false);
+    }
+
+    fn create_pins(&mut self, _: &Arc<Circuit>) -> Box<[CircuitPinInfo]> {
+        self.pins = self
+            .ty
+            .describe()
+            .pins
+            .iter()
+            .map(|p| p.to_info())
+            .collect();
+        self.pins.clone()
+    }
+
+    fn update_signals(&self, state_ctx: &CircuitStateContext, changed_pin: Option<usize>) {
+        if changed_pin.is_some_and(|p| self.ty.pins.get(p).is_some_and(|p| p.output)) {
+            return;
+        }
+
+        let mut values = vec![];
+        for (pin, info) in self.pins.iter().zip(self.ty.pins.iter()) {
+            let state = match info.output {
+                true => WireState::None,
+                false => pin.get_state(state_ctx),
+            };
+            to_plugin_state(&state, info.bits, &mut values);
+        }
+
+        let update = self.ty.update;
+        self.state_data(state_ctx, |data| {
+            update(
+                values.as_mut_ptr(),
+                values.len(),
+                data.as_mut_ptr(),
+                data.len(),
+            )
+        });
+
+        let mut offset = 0;
+        for (pin, info) in self.pins.iter().zip(self.ty.pins.iter()) {
+            let bits = info.bits as usize;
+            if info.output {
+                pin.set_state(state_ctx, from_plugin_state(&values[offset..offset + bits]));
+            }
+            offset += bits;
+        }
+    }
+
+    fn size(&self, _: &Arc<Circuit>) -> Vec2u {
+        self.ty.size
+    }
+
+    fn load_internal(
+        &self,
+        _ctx: &CircuitStateContext,
+        data: &serde_intermediate::Intermediate,
+        _paste: bool,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn InternalCircuitState>> {
+        serde_intermediate::from_intermediate::<PluginCircuitState>(data)
+            .ok()
+            .map(|v| Box::new(v) as _)
+    }
+}
+
+pub struct PluginCircuitPreview {
+    ty: Arc<PluginCircuitType>,
+}
+
+impl CircuitPreviewImpl for PluginCircuitPreview {
+    fn type_name(&self) -> DynStaticStr {
+        self.ty.type_name.clone().into()
+    }
+
+    fn display_name(&self) -> DynStaticStr {
+        self.ty.display_name.clone().into()
+    }
+
+    fn description(&self) -> DynStaticStr {
+        format!(
+            "{}\n\nProvided by plugin {}",
+            self.ty.description, self.ty.plugin
+        )
+        .into()
+    }
+
+    fn draw_preview(&self, _: &CircuitPropertyStore, ctx: &PaintContext, in_world: bool) {
+        self.ty.draw(None, ctx, in_world);
+    }
+
+    fn create_impl(&self) -> Box<dyn CircuitImpl> {
+        Box::new(PluginCircuit {
+            ty: self.ty.clone(),
+            pins: [].into(),
+        })
+    }
+
+    fn load_copy_data(
+        &self,
+        _imp: &serde_intermediate::Intermediate,
+        _internal: &serde_intermediate::Intermediate,
+        _ctx: &Arc<SimulationContext>,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn CircuitPreviewImpl>> {
+        Some(Box::new(PluginCircuitPreview {
+            ty: self.ty.clone(),
+        }))
+    }
+
+    fn default_props(&self) -> CircuitPropertyStore {
+        CircuitPropertyStore::default()
+    }
+
+    fn describe(&self, _: &CircuitPropertyStore) -> DynCircuitDescription {
+        self.ty.describe()
+    }
+}
+
+fn load_plugin(path: &Path) -> Result<Vec<Box<dyn CircuitPreviewImpl>>, String> {
+    let library = unsafe { libloading::Library::new(path) }.map_err(|e| e.to_string())?;
+
+    let version = unsafe { library.get::<extern "C" fn() -> u32>(ABI_VERSION_SYMBOL) }
+        .map_err(|e| e.to_string())?;
+    let version = version();
+    if version != PLUGIN_ABI_VERSION {
+        return Err(format!(
+            "plugin ABI version {version} is not supported, expected {PLUGIN_ABI_VERSION}"
+        ));
+    }
+
+    let entry = unsafe { library.get::<extern "C" fn() -> *const PluginEntry>(ENTRY_SYMBOL) }
+        .map_err(|e| e.to_string())?;
+    let entry = entry();
+    if entry.is_null() {
+        return Err("plugin returned no entry".into());
+    }
+    let entry = unsafe { &*entry };
+
+    let file_name = path.file_stem().map(|s| s.to_string_lossy().into());
+    let name = read_str(entry.name).or(file_name).unwrap_or_default();
+    let circuits = match entry.circuit_count {
+        0 => &[][..],
+        _ if entry.circuits.is_null() => return Err("plugin has no circuit list".into()),
+        count => unsafe { std::slice::from_raw_parts(entry.circuits, count) },
+    };
+
+    let library = Arc::new(library);
+    circuits
+        .iter()
+        .map(|info| {
+            PluginCircuitType::read(info, &name, &library).map(|ty| {
+                Box::new(PluginCircuitPreview { ty: Arc::new(ty) }) as Box<dyn CircuitPreviewImpl>
+            })
+        })
+        .collect()
+}
+
+pub fn plugin_dir() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    Some(exe.parent()?.join("plugins"))
+}
+
+/// Loads circuit previews from all plugins in [`plugin_dir`]
+pub fn load_plugins(errors: &mut ErrorList) -> Vec<Box<dyn CircuitPreviewImpl>> {
+    let entries = match plugin_dir().and_then(|dir| std::fs::read_dir(dir).ok()) {
+        Some(entries) => entries,
+        None => return vec![],
+    };
+
+    let mut previews = vec![];
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_library = path
+            .extension()
+            .is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION);
+        if !is_library {
+            continue;
+        }
+
+        match load_plugin(&path) {
+            Ok(plugin) => previews.extend(plugin),
+            Err(e) => errors
+                .enter_context(|| format!("loading plugin {}", path.display()))
+                .push_error(e),
+        }
+    }
+    previews
+}
@@ -0,0 +1,247 @@
+use eframe::epaint::{FontId, Rounding, Stroke};
+use emath::{vec2, Align2};
+
+use crate::circuits::props::{CircuitProperty, RangedValue};
+use crate::circuits::*;
+
+create_safe_prop_enums! {
+    #[default(Hex)]
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub enum ValueFormat {
+        Hex('h', "Hexadecimal"),
+        Decimal('d'),
+        Signed('s', "Signed decimal"),
+        Binary('b'),
+    }
+}
+
+fn single_state_char(state: &WireState) -> char {
+    match state {
+        WireState::None => 'Z',
+        WireState::True => '1',
+        WireState::False => '0',
+        WireState::Error => 'E',
+        WireState::Bundle(_) => 'B',
+    }
+}
+
+/// Formats wire state for display<br>
+/// Single wires are shown as 0/1/Z/E, bundles are formatted according to `format`.
+/// Bundles with undriven or conflicting wires are always shown as binary
+pub fn format_state(state: &WireState, format: ValueFormat) -> String {
+    let bundle = match state {
+        WireState::Bundle(bundle) => bundle,
+        state => return single_state_char(state).into(),
+    };
+
+    let bits = bundle.len();
+    let value = state.to_bits();
+
+    match (format, value) {
+        (ValueFormat::Hex, Some(value)) => {
+            let digits = bits.div_ceil(4).max(1);
+            format!("{value:0digits$X}")
+        }
+        (ValueFormat::Decimal, Some(value)) => value.to_string(),
+        (ValueFormat::Signed, Some(value)) => {
+            let value = if bits == 0 || bits >= 64 {
+                value as i64
+            } else if value & (1 << (bits - 1)) != 0 {
+                value as i64 - (1i64 << bits)
+            } else {
+                value as i64
+            };
+            value.to_string()
+        }
+        (ValueFormat::Binary, _) | (_, None) => {
+            let mut str = String::with_capacity(bits);
+            for state in bundle.iter().rev() {
+                str.push(single_state_char(state));
+            }
+            str
+        }
+    }
+}
+
+struct Probe {
+    input: CircuitPinInfo,
+    dir: Direction4,
+    width: u32,
+    format: ValueFormat,
+}
+
+impl Probe {
+    const DEFAULT_DIR: Direction4 = Direction4::Left;
+
+    fn new() -> Self {
+        let description = Self::describe(Self::DEFAULT_DIR, 3);
+        Self {
+            input: description.pins[0].to_info(),
+            dir: Self::DEFAULT_DIR,
+            width: 3,
+            format: ValueFormat::Hex,
+        }
+    }
+
+    /// Rect of the value box, in circuit cells
+    fn value_rect(dir: Direction4, width: u32) -> Rect {
+        let width = width as f32;
+        match dir {
+            Direction4::Left => Rect::from_min_size([1.0, 0.0].into(), vec2(width, 1.0)),
+            Direction4::Right => Rect::from_min_size([0.0, 0.0].into(), vec2(width, 1.0)),
+            Direction4::Up => Rect::from_min_size([0.0, 1.0].into(), vec2(width, 1.0)),
+            Direction4::Down => Rect::from_min_size([0.0, 0.0].into(), vec2(width, 1.0)),
+        }
+    }
+
+    fn draw(dir: Direction4, width: u32, text: &str, ctx: &PaintContext, semi_transparent: bool) {
+        let opacity = if semi_transparent { 0.6 } else { 1.0 };
+
+        let border_color = ctx.style.component_border_color().linear_multiply(opacity);
+        let fill_color = Color32::from_gray(230).linear_multiply(opacity);
+
+        let rect = Self::value_rect(dir, width);
+        let rect = Rect::from_min_size(
+            ctx.rect.left_top() + rect.min.to_vec2() * ctx.screen.scale,
+            rect.size() * ctx.screen.scale,
+        )
+        .shrink(ctx.screen.scale * 0.1);
+
+        ctx.paint.rect(
+            rect,
+            Rounding::same(ctx.screen.scale * 0.2),
+            fill_color,
+            Stroke::new(0.1 * ctx.screen.scale, border_color),
+        );
+
+        // Monospace glyphs are roughly 0.6 of font size wide, shrink long values to fit
+        let chars = text.chars().count().max(1) as f32;
+        let size = (ctx.screen.scale * 0.7).min(rect.width() * 0.9 / (chars * 0.6));
+        ctx.paint.text(
+            rect.center(),
+            Align2::CENTER_CENTER,
+            text,
+            FontId::monospace(size),
+            border_color,
+        );
+    }
+
+    fn describe_props(props: &CircuitPropertyStore) -> CircuitDescription<1> {
+        let dir = props.read_clone("dir").unwrap_or(Self::DEFAULT_DIR);
+        let width = props
+            .read("width", |v: &RangedValue<u32>| v.get())
+            .unwrap_or(3);
+        Self::describe(dir, width)
+    }
+
+    fn describe(dir: Direction4, width: u32) -> CircuitDescription<1> {
+        let (size, pos) = match dir {
+            Direction4::Left => ([width + 1, 1], [0, 0]),
+            Direction4::Right => ([width + 1, 1], [width, 0]),
+            Direction4::Up => ([width, 2], [width / 2, 0]),
+            Direction4::Down => ([width, 2], [width / 2, 1]),
+        };
+
+        CircuitDescription {
+            size: size.into(),
+            pins: [CircuitPinDescription {
+                active: true,
+                display_name: "In".into(),
+                display_dir: Some(dir),
+                dir: InternalPinDirection::Inside,
+                name: "in".into(),
+                pos: pos.into(),
+            }],
+        }
+    }
+}
+
+impl CircuitImpl for Probe {
+    fn draw(&self, state_ctx: &CircuitStateContext, paint_ctx: &PaintContext) {
+        let text = format_state(&self.input.get_state(state_ctx), self.format);
+        Probe::draw(self.dir, self.width, &text, paint_ctx, false);
+    }
+
+    fn create_pins(&mut self, circ: &Arc<Circuit>) -> Box<[CircuitPinInfo]> {
+        let description = Self::describe_props(&circ.props);
+        self.input = description.pins[0].to_info();
+        vec![self.input.clone()].into_boxed_slice()
+    }
+
+    fn update_signals(&self, _: &CircuitStateContext, _: Option<usize>) {}
+
+    fn size(&self, circ: &Arc<Circuit>) -> Vec2u {
+        Self::describe_props(&circ.props).size
+    }
+
+    fn prop_changed(&self, prop_id: &str, resize: &mut bool, recreate_pins: &mut bool) {
+        if prop_id == "dir" || prop_id == "width" {
+            *resize = true;
+            *recreate_pins = true;
+        }
+    }
+
+    fn apply_props(&mut self, circ: &Arc<Circuit>, _: Option<&str>) {
+        self.dir = circ.props.read_clone("dir").unwrap_or(Self::DEFAULT_DIR);
+        self.width = circ
+            .props
+            .read("width", |v: &RangedValue<u32>| v.get())
+            .unwrap_or(3);
+        self.format = circ.props.read_clone("format").unwrap_or(ValueFormat::Hex);
+    }
+}
+
+pub struct ProbePreview {}
+
+impl CircuitPreviewImpl for ProbePreview {
+    fn type_name(&self) -> DynStaticStr {
+        "probe".into()
+    }
+
+    fn display_name(&self) -> DynStaticStr {
+        "Probe".into()
+    }
+
+    fn description(&self) -> DynStaticStr {
+        "Displays current value of the wire it's connected to, without driving it.\n\
+         Single wires are shown as 0, 1, Z (no signal) or E (error).\n\
+         Bundles are shown in the selected format, first wire being the lowest bit. \
+         Bundles with Z or E values are always shown in binary.\
+        "
+        .into()
+    }
+
+    fn draw_preview(&self, props: &CircuitPropertyStore, ctx: &PaintContext, in_world: bool) {
+        let dir = props.read_clone("dir").unwrap_or(Probe::DEFAULT_DIR);
+        let width = props
+            .read("width", |v: &RangedValue<u32>| v.get())
+            .unwrap_or(3);
+        Probe::draw(dir, width, "0", ctx, in_world);
+    }
+
+    fn create_impl(&self) -> Box<dyn CircuitImpl> {
+        Box::new(Probe::new())
+    }
+
+    fn load_copy_data(
+        &self,
+        _imp: &serde_intermediate::Intermediate,
+        _internal: &serde_intermediate::Intermediate,
+        _ctx: &Arc<SimulationContext>,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn CircuitPreviewImpl>> {
+        Some(Box::new(ProbePreview {}))
+    }
+
+    fn default_props(&self) -> CircuitPropertyStore {
+        CircuitPropertyStore::new([
+            CircuitProperty::new("dir", "Direction", Probe::DEFAULT_DIR),
+            CircuitProperty::new("width", "Width", RangedValue::new(1..=32, 1, 3)),
+            CircuitProperty::new("format", "Format", ValueFormat::Hex),
+        ])
+    }
+
+    fn describe(&self, props: &CircuitPropertyStore) -> DynCircuitDescription {
+        Probe::describe_props(props).to_dyn()
+    }
+}
@@ -760,7 +760,7 @@ macro_rules! create_safe_prop_enums {
         $( 
             #[default($default:ident)] 
             $(#[$meta:meta])* 
-            enum $name:ident { 
+            $vis:vis enum $name:ident { 
                 $( 
                     $(#[$varmeta:meta])* 
                     $variant:ident($varrep:literal $(, $varname:literal)?),
@@ -770,7 +770,7 @@ macro_rules! create_safe_prop_enums {
     ) => {
         $(
             $(#[$meta])*
-            enum $name {
+            $vis enum $name {
                 $(
                     $(#[$varmeta])* 
                     $variant
@@ -1,4 +1,4 @@
-use eframe::epaint::{Color32, Stroke};
+use eframe::epaint::Stroke;
 
 use crate::circuits::*;
 
@@ -20,7 +20,7 @@ impl Pullup {
         ctx.paint.circle_stroke(
             ctx.rect.center(),
             ctx.screen.scale * 0.5,
-            Stroke::new(1.0, Color32::BLACK),
+            Stroke::new(1.0, ctx.style.component_border_color()),
         )
     }
 
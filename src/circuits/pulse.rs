@@ -0,0 +1,649 @@
+use eframe::epaint::{FontId, Rounding, Stroke};
+use emath::Align2;
+
+use crate::circuits::props::{CircuitProperty, RangedValue, Slider};
+use crate::{circuits::*, describe_directional_circuit};
+
+create_safe_prop_enums! {
+    #[default(Rising)]
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    enum EdgeMode {
+        Rising('r'),
+        Falling('f'),
+        Both('b'),
+    }
+}
+
+// All circuits here only depend on their input edges and on update intervals,
+// so they work with both real and emulated (web) time sources
+
+fn describe_pulse(dir: Direction4, in_name: &'static str) -> CircuitDescription<2> {
+    describe_directional_circuit! {
+        default_dir: Direction4::Right,
+        dir: dir,
+        size: [3, 2],
+        "in": Inside, in_name, Left, [0, 0],
+        "out": Outside, "Out", Right, [2, 0],
+    }
+}
+
+fn draw_pulse(title: &str, ctx: &PaintContext, semi_transparent: bool) {
+    let opacity = if semi_transparent { 0.6 } else { 1.0 };
+
+    let border_color = ctx.style.component_border_color().linear_multiply(opacity);
+    let fill_color = ctx.style.component_fill_color().linear_multiply(opacity);
+
+    ctx.paint.rect(
+        ctx.rect.shrink(ctx.screen.scale * 0.25),
+        Rounding::same(ctx.screen.scale * 0.25),
+        fill_color,
+        Stroke::new(0.15 * ctx.screen.scale, border_color),
+    );
+
+    ctx.paint.text(
+        ctx.rect.center(),
+        Align2::CENTER_CENTER,
+        title,
+        FontId::monospace(ctx.screen.scale * 0.6),
+        border_color,
+    );
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct ClockDividerState {
+    counter: u32,
+    clock: bool,
+}
+
+impl ClockDividerState {
+    /// Counts rising clock edges modulo `divisor`, returns current count
+    fn clock(&mut self, clock: bool, divisor: u32) -> u32 {
+        if clock && !self.clock {
+            self.counter = (self.counter + 1) % divisor;
+        }
+        self.clock = clock;
+        self.counter
+    }
+}
+
+impl InternalCircuitState for ClockDividerState {
+    fn serialize(&self, _copy: bool) -> serde_intermediate::Intermediate {
+        serde_intermediate::to_intermediate(self).unwrap_or_default()
+    }
+}
+
+struct ClockDivider {
+    input: CircuitPinInfo,
+    out: CircuitPinInfo,
+
+    divisor: u32,
+    duty: f32,
+}
+
+impl ClockDivider {
+    fn new() -> Self {
+        let description = describe_pulse(Direction4::Right, "Clock");
+        Self {
+            input: description.pins[0].to_info(),
+            out: description.pins[1].to_info(),
+            divisor: 2,
+            duty: 0.5,
+        }
+    }
+
+    fn describe_props(props: &CircuitPropertyStore) -> CircuitDescription<2> {
+        let dir = props.read_clone("dir").unwrap_or(Direction4::Right);
+        describe_pulse(dir, "Clock")
+    }
+
+    /// How many input cycles of each output cycle the output stays high
+    fn high_cycles(&self) -> u32 {
+        if self.divisor <= 1 {
+            return self.divisor;
+        }
+        ((self.divisor as f32 * self.duty).round() as u32).clamp(1, self.divisor - 1)
+    }
+}
+
+impl CircuitImpl for ClockDivider {
+    fn draw(&self, _: &CircuitStateContext, paint_ctx: &PaintContext) {
+        draw_pulse(&format!("/{}", self.divisor), paint_ctx, false);
+    }
+
+    fn create_pins(&mut self, circ: &Arc<Circuit>) -> Box<[CircuitPinInfo]> {
+        let description = Self::describe_props(&circ.props);
+        self.input = description.pins[0].to_info();
+        self.out = description.pins[1].to_info();
+        vec![self.input.clone(), self.out.clone()].into_boxed_slice()
+    }
+
+    fn update_signals(&self, state_ctx: &CircuitStateContext, changed_pin: Option<usize>) {
+        let clock = self.input.get_state(state_ctx) == WireState::True;
+
+        // Divide by 1 passes input through, including its duty cycle
+        if self.divisor <= 1 {
+            self.out.set_state(state_ctx, clock.into());
+            return;
+        }
+
+        let counter =
+            state_ctx.write_circuit_internal_state(|s: &mut ClockDividerState| match changed_pin {
+                None | Some(0) => s.clock(clock, self.divisor),
+                _ => s.counter,
+            });
+
+        self.out
+            .set_state(state_ctx, (counter < self.high_cycles()).into());
+    }
+
+    fn size(&self, circ: &Arc<Circuit>) -> Vec2u {
+        Self::describe_props(&circ.props).size
+    }
+
+    fn prop_changed(&self, prop_id: &str, resize: &mut bool, recreate_pins: &mut bool) {
+        if prop_id == "dir" {
+            *resize = true;
+            *recreate_pins = true;
+        }
+    }
+
+    fn apply_props(&mut self, circ: &Arc<Circuit>, _: Option<&str>) {
+        self.divisor = circ
+            .props
+            .read("divisor", |v: &RangedValue<u32>| v.get())
+            .unwrap_or(2);
+        self.duty = circ
+            .props
+            .read("duty", |v: &Slider<f32>| v.value)
+            .unwrap_or(0.5);
+    }
+
+    fn load_internal(
+        &self,
+        _ctx: &CircuitStateContext,
+        data: &serde_intermediate::Intermediate,
+        _paste: bool,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn InternalCircuitState>> {
+        serde_intermediate::from_intermediate::<ClockDividerState>(data)
+            .ok()
+            .map(|v| Box::new(v) as _)
+    }
+}
+
+pub struct ClockDividerPreview {}
+
+impl CircuitPreviewImpl for ClockDividerPreview {
+    fn type_name(&self) -> DynStaticStr {
+        "clock_divider".into()
+    }
+
+    fn display_name(&self) -> DynStaticStr {
+        "Clock divider".into()
+    }
+
+    fn description(&self) -> DynStaticStr {
+        "Divides frequency of its input clock by the set divisor.\n\
+         Duty cycle sets how much of the output cycle signal stays on, \
+         rounded to whole input cycles.\
+        "
+        .into()
+    }
+
+    fn draw_preview(&self, props: &CircuitPropertyStore, ctx: &PaintContext, in_world: bool) {
+        let divisor = props
+            .read("divisor", |v: &RangedValue<u32>| v.get())
+            .unwrap_or(2);
+        draw_pulse(&format!("/{divisor}"), ctx, in_world);
+    }
+
+    fn create_impl(&self) -> Box<dyn CircuitImpl> {
+        Box::new(ClockDivider::new())
+    }
+
+    fn load_copy_data(
+        &self,
+        _imp: &serde_intermediate::Intermediate,
+        _internal: &serde_intermediate::Intermediate,
+        _ctx: &Arc<SimulationContext>,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn CircuitPreviewImpl>> {
+        Some(Box::new(ClockDividerPreview {}))
+    }
+
+    fn default_props(&self) -> CircuitPropertyStore {
+        CircuitPropertyStore::new([
+            CircuitProperty::new("dir", "Direction", Direction4::Right),
+            CircuitProperty::new("divisor", "Divisor", RangedValue::new_from(1u32.., 1, 2)),
+            CircuitProperty::new(
+                "duty",
+                "Duty cycle",
+                Slider {
+                    range: 0.0..=1.0,
+                    value: 0.5f32,
+                },
+            ),
+        ])
+    }
+
+    fn describe(&self, props: &CircuitPropertyStore) -> DynCircuitDescription {
+        ClockDivider::describe_props(props).to_dyn()
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct OneShotState {
+    input: bool,
+    active: bool,
+}
+
+impl OneShotState {
+    /// Returns whether input starts a new pulse
+    fn trigger(&mut self, input: bool, input_changed: bool, retrigger: bool) -> bool {
+        let edge = input_changed && input && !self.input;
+        self.input = input;
+
+        let trigger = edge && (!self.active || retrigger);
+        if trigger {
+            self.active = true;
+        }
+        trigger
+    }
+}
+
+impl InternalCircuitState for OneShotState {
+    fn serialize(&self, _copy: bool) -> serde_intermediate::Intermediate {
+        serde_intermediate::to_intermediate(self).unwrap_or_default()
+    }
+}
+
+struct OneShot {
+    input: CircuitPinInfo,
+    out: CircuitPinInfo,
+
+    width: Duration,
+    retrigger: bool,
+}
+
+impl OneShot {
+    /// Longest pulse, one hour
+    const MAX_WIDTH_MS: f32 = 3_600_000.0;
+
+    fn new() -> Self {
+        let description = describe_pulse(Direction4::Right, "Trigger");
+        Self {
+            input: description.pins[0].to_info(),
+            out: description.pins[1].to_info(),
+            width: Duration::from_millis(100),
+            retrigger: false,
+        }
+    }
+
+    fn describe_props(props: &CircuitPropertyStore) -> CircuitDescription<2> {
+        let dir = props.read_clone("dir").unwrap_or(Direction4::Right);
+        describe_pulse(dir, "Trigger")
+    }
+
+    fn pulse_width(ms: f32) -> Duration {
+        Duration::try_from_secs_f32(ms.clamp(0.0, Self::MAX_WIDTH_MS) / 1000.0).unwrap_or_default()
+    }
+}
+
+impl CircuitImpl for OneShot {
+    fn draw(&self, _: &CircuitStateContext, paint_ctx: &PaintContext) {
+        draw_pulse("1S", paint_ctx, false);
+    }
+
+    fn create_pins(&mut self, circ: &Arc<Circuit>) -> Box<[CircuitPinInfo]> {
+        let description = Self::describe_props(&circ.props);
+        self.input = description.pins[0].to_info();
+        self.out = description.pins[1].to_info();
+        vec![self.input.clone(), self.out.clone()].into_boxed_slice()
+    }
+
+    fn update_signals(&self, state_ctx: &CircuitStateContext, changed_pin: Option<usize>) {
+        let input = self.input.get_state(state_ctx) == WireState::True;
+        let input_changed = matches!(changed_pin, None | Some(0));
+        let (trigger, active) = state_ctx.write_circuit_internal_state(|s: &mut OneShotState| {
+            let trigger = s.trigger(input, input_changed, self.retrigger);
+            (trigger, s.active)
+        });
+
+        if trigger {
+            state_ctx.set_update_interval(Some(self.width));
+        }
+        self.out.set_state(state_ctx, active.into());
+    }
+
+    fn update(&self, ctx: &CircuitStateContext, interval: &mut Option<Duration>) {
+        ctx.write_circuit_internal_state(|s: &mut OneShotState| s.active = false);
+        *interval = None;
+        self.out.set_state(ctx, WireState::False);
+    }
+
+    fn size(&self, circ: &Arc<Circuit>) -> Vec2u {
+        Self::describe_props(&circ.props).size
+    }
+
+    fn prop_changed(&self, prop_id: &str, resize: &mut bool, recreate_pins: &mut bool) {
+        if prop_id == "dir" {
+            *resize = true;
+            *recreate_pins = true;
+        }
+    }
+
+    fn apply_props(&mut self, circ: &Arc<Circuit>, _: Option<&str>) {
+        let width = circ
+            .props
+            .read("width", |v: &RangedValue<f32>| v.get())
+            .unwrap_or(100.0);
+        self.width = Self::pulse_width(width);
+        self.retrigger = circ.props.read_clone("retrigger").unwrap_or(false);
+    }
+
+    fn load_internal(
+        &self,
+        _ctx: &CircuitStateContext,
+        data: &serde_intermediate::Intermediate,
+        _paste: bool,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn InternalCircuitState>> {
+        serde_intermediate::from_intermediate::<OneShotState>(data)
+            .ok()
+            .map(|v| Box::new(v) as _)
+    }
+}
+
+pub struct OneShotPreview {}
+
+impl CircuitPreviewImpl for OneShotPreview {
+    fn type_name(&self) -> DynStaticStr {
+        "one_shot".into()
+    }
+
+    fn display_name(&self) -> DynStaticStr {
+        "One-shot".into()
+    }
+
+    fn description(&self) -> DynStaticStr {
+        "Outputs a single pulse of set width when its trigger pin goes high.\n\
+         When retriggerable, new triggers during a pulse restart it, otherwise they are ignored.\
+        "
+        .into()
+    }
+
+    fn draw_preview(&self, _: &CircuitPropertyStore, ctx: &PaintContext, in_world: bool) {
+        draw_pulse("1S", ctx, in_world);
+    }
+
+    fn create_impl(&self) -> Box<dyn CircuitImpl> {
+        Box::new(OneShot::new())
+    }
+
+    fn load_copy_data(
+        &self,
+        _imp: &serde_intermediate::Intermediate,
+        _internal: &serde_intermediate::Intermediate,
+        _ctx: &Arc<SimulationContext>,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn CircuitPreviewImpl>> {
+        Some(Box::new(OneShotPreview {}))
+    }
+
+    fn default_props(&self) -> CircuitPropertyStore {
+        CircuitPropertyStore::new([
+            CircuitProperty::new("dir", "Direction", Direction4::Right),
+            CircuitProperty::new(
+                "width",
+                "Pulse width (ms)",
+                RangedValue::new(0.0..=OneShot::MAX_WIDTH_MS, 1.0, 100.0),
+            ),
+            CircuitProperty::new("retrigger", "Retriggerable", false),
+        ])
+    }
+
+    fn describe(&self, props: &CircuitPropertyStore) -> DynCircuitDescription {
+        OneShot::describe_props(props).to_dyn()
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct EdgeDetectorState {
+    input: bool,
+}
+
+impl EdgeDetectorState {
+    /// Returns whether input change is an edge detected in `mode`
+    fn edge(&mut self, input: bool, mode: EdgeMode) -> bool {
+        let edge = match (self.input, input) {
+            (false, true) => mode != EdgeMode::Falling,
+            (true, false) => mode != EdgeMode::Rising,
+            _ => false,
+        };
+        self.input = input;
+        edge
+    }
+}
+
+impl InternalCircuitState for EdgeDetectorState {
+    fn serialize(&self, _copy: bool) -> serde_intermediate::Intermediate {
+        serde_intermediate::to_intermediate(self).unwrap_or_default()
+    }
+}
+
+struct EdgeDetector {
+    input: CircuitPinInfo,
+    out: CircuitPinInfo,
+
+    mode: EdgeMode,
+}
+
+impl EdgeDetector {
+    fn new() -> Self {
+        let description = describe_pulse(Direction4::Right, "In");
+        Self {
+            input: description.pins[0].to_info(),
+            out: description.pins[1].to_info(),
+            mode: EdgeMode::Rising,
+        }
+    }
+
+    fn describe_props(props: &CircuitPropertyStore) -> CircuitDescription<2> {
+        let dir = props.read_clone("dir").unwrap_or(Direction4::Right);
+        describe_pulse(dir, "In")
+    }
+
+    fn title(mode: EdgeMode) -> &'static str {
+        match mode {
+            EdgeMode::Rising => "_/",
+            EdgeMode::Falling => "\\_",
+            EdgeMode::Both => "_/\\",
+        }
+    }
+}
+
+impl CircuitImpl for EdgeDetector {
+    fn draw(&self, _: &CircuitStateContext, paint_ctx: &PaintContext) {
+        draw_pulse(Self::title(self.mode), paint_ctx, false);
+    }
+
+    fn create_pins(&mut self, circ: &Arc<Circuit>) -> Box<[CircuitPinInfo]> {
+        let description = Self::describe_props(&circ.props);
+        self.input = description.pins[0].to_info();
+        self.out = description.pins[1].to_info();
+        vec![self.input.clone(), self.out.clone()].into_boxed_slice()
+    }
+
+    fn update_signals(&self, state_ctx: &CircuitStateContext, changed_pin: Option<usize>) {
+        if let Some(1) = changed_pin {
+            return;
+        }
+
+        let input = match self.input.get_state(state_ctx) {
+            WireState::True => true,
+            WireState::False => false,
+            _ => return,
+        };
+        let edge = state_ctx
+            .write_circuit_internal_state(|s: &mut EdgeDetectorState| s.edge(input, self.mode));
+
+        // Pulse lasts until the next simulation update
+        if edge {
+            self.out.set_state(state_ctx, WireState::True);
+            state_ctx.set_update_interval(Some(Duration::ZERO));
+        }
+    }
+
+    fn update(&self, ctx: &CircuitStateContext, interval: &mut Option<Duration>) {
+        *interval = None;
+        self.out.set_state(ctx, WireState::False);
+    }
+
+    fn state_init(&self, ctx: &CircuitStateContext, first_init: bool) {
+        if first_init {
+            self.out.set_state(ctx, WireState::False);
+        }
+    }
+
+    fn size(&self, circ: &Arc<Circuit>) -> Vec2u {
+        Self::describe_props(&circ.props).size
+    }
+
+    fn prop_changed(&self, prop_id: &str, resize: &mut bool, recreate_pins: &mut bool) {
+        if prop_id == "dir" {
+            *resize = true;
+            *recreate_pins = true;
+        }
+    }
+
+    fn apply_props(&mut self, circ: &Arc<Circuit>, _: Option<&str>) {
+        self.mode = circ.props.read_clone("mode").unwrap_or(EdgeMode::Rising);
+    }
+
+    fn load_internal(
+        &self,
+        _ctx: &CircuitStateContext,
+        data: &serde_intermediate::Intermediate,
+        _paste: bool,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn InternalCircuitState>> {
+        serde_intermediate::from_intermediate::<EdgeDetectorState>(data)
+            .ok()
+            .map(|v| Box::new(v) as _)
+    }
+}
+
+pub struct EdgeDetectorPreview {}
+
+impl CircuitPreviewImpl for EdgeDetectorPreview {
+    fn type_name(&self) -> DynStaticStr {
+        "edge_detector".into()
+    }
+
+    fn display_name(&self) -> DynStaticStr {
+        "Edge detector".into()
+    }
+
+    fn description(&self) -> DynStaticStr {
+        "Outputs a short pulse when its input changes.\n\
+         Mode selects which changes are detected: rising (low to high), falling (high to low) or both.\
+        "
+        .into()
+    }
+
+    fn draw_preview(&self, props: &CircuitPropertyStore, ctx: &PaintContext, in_world: bool) {
+        let mode = props.read_clone("mode").unwrap_or(EdgeMode::Rising);
+        draw_pulse(EdgeDetector::title(mode), ctx, in_world);
+    }
+
+    fn create_impl(&self) -> Box<dyn CircuitImpl> {
+        Box::new(EdgeDetector::new())
+    }
+
+    fn load_copy_data(
+        &self,
+        _imp: &serde_intermediate::Intermediate,
+        _internal: &serde_intermediate::Intermediate,
+        _ctx: &Arc<SimulationContext>,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn CircuitPreviewImpl>> {
+        Some(Box::new(EdgeDetectorPreview {}))
+    }
+
+    fn default_props(&self) -> CircuitPropertyStore {
+        CircuitPropertyStore::new([
+            CircuitProperty::new("dir", "Direction", Direction4::Right),
+            CircuitProperty::new("mode", "Mode", EdgeMode::Rising),
+        ])
+    }
+
+    fn describe(&self, props: &CircuitPropertyStore) -> DynCircuitDescription {
+        EdgeDetector::describe_props(props).to_dyn()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{ClockDividerState, EdgeDetectorState, EdgeMode, OneShot, OneShotState};
+
+    #[test]
+    fn edges_match_mode() {
+        let inputs = [true, true, false, true, false, false];
+        for (mode, expected) in [
+            (EdgeMode::Rising, [true, false, false, true, false, false]),
+            (EdgeMode::Falling, [false, false, true, false, true, false]),
+            (EdgeMode::Both, [true, false, true, true, true, false]),
+        ] {
+            let mut state = EdgeDetectorState::default();
+            let edges = inputs.map(|input| state.edge(input, mode));
+            assert_eq!(edges, expected, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn divider_counts_rising_edges() {
+        let mut state = ClockDividerState::default();
+        let mut counts = vec![];
+        for _ in 0..4 {
+            counts.push(state.clock(true, 3));
+            counts.push(state.clock(false, 3));
+        }
+        assert_eq!(counts, [1, 1, 2, 2, 0, 0, 1, 1]);
+        assert_eq!(state.clock(false, 3), 1);
+    }
+
+    #[test]
+    fn one_shot_ignores_triggers_during_pulse() {
+        let mut state = OneShotState::default();
+        assert!(state.trigger(true, true, false));
+        assert!(!state.trigger(false, true, false));
+        assert!(!state.trigger(true, true, false));
+
+        // Pulse ended
+        state.active = false;
+        assert!(!state.trigger(true, true, false));
+        assert!(!state.trigger(false, true, false));
+        assert!(state.trigger(true, true, false));
+    }
+
+    #[test]
+    fn one_shot_retriggers() {
+        let mut state = OneShotState::default();
+        assert!(state.trigger(true, true, true));
+        assert!(!state.trigger(false, true, true));
+        assert!(state.trigger(true, true, true));
+        assert!(state.active);
+    }
+
+    #[test]
+    fn one_shot_width_is_clamped() {
+        assert_eq!(OneShot::pulse_width(100.0).as_millis(), 100);
+        assert_eq!(OneShot::pulse_width(-5.0), Duration::ZERO);
+        let max = Duration::from_secs(3600);
+        assert_eq!(OneShot::pulse_width(f32::MAX), max);
+        assert_eq!(OneShot::pulse_width(f32::INFINITY), max);
+        assert_eq!(OneShot::pulse_width(f32::NAN), Duration::ZERO);
+    }
+}
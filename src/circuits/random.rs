@@ -0,0 +1,477 @@
+use eframe::epaint::{FontId, Rounding, Stroke};
+use emath::Align2;
+
+use crate::circuits::props::{CircuitProperty, RangedValue};
+use crate::{circuits::*, describe_directional_circuit, random_u128};
+
+#[derive(Serialize, Deserialize, Default)]
+struct RandomState {
+    rng: u64,
+    value: u64,
+    clock: bool,
+}
+
+impl InternalCircuitState for RandomState {
+    fn serialize(&self, _copy: bool) -> serde_intermediate::Intermediate {
+        serde_intermediate::to_intermediate(self).unwrap_or_default()
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct LfsrState {
+    value: u64,
+    clock: bool,
+}
+
+impl InternalCircuitState for LfsrState {
+    fn serialize(&self, _copy: bool) -> serde_intermediate::Intermediate {
+        serde_intermediate::to_intermediate(self).unwrap_or_default()
+    }
+}
+
+fn bit_mask(bits: u32) -> u64 {
+    if bits >= 64 {
+        u64::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+fn describe_clocked(dir: Direction4) -> CircuitDescription<2> {
+    describe_directional_circuit! {
+        default_dir: Direction4::Right,
+        dir: dir,
+        size: [3, 3],
+        "clk": Inside, "Clock", Left, [0, 1],
+        "out": Outside, "Out", Right, [2, 1],
+    }
+}
+
+fn draw_clocked(
+    title: &str,
+    value: Option<(u64, u32)>,
+    ctx: &PaintContext,
+    semi_transparent: bool,
+) {
+    let opacity = if semi_transparent { 0.6 } else { 1.0 };
+
+    let border_color = ctx.style.component_border_color().linear_multiply(opacity);
+    let fill_color = ctx.style.component_fill_color().linear_multiply(opacity);
+
+    ctx.paint.rect(
+        ctx.rect.expand(ctx.screen.scale * -0.5),
+        Rounding::same(ctx.screen.scale * 0.25),
+        fill_color,
+        Stroke::new(0.15 * ctx.screen.scale, border_color),
+    );
+
+    let font = FontId::monospace(ctx.screen.scale * 0.5);
+    match value {
+        Some((value, bits)) => {
+            ctx.paint.text(
+                ctx.rect.center() - emath::vec2(0.0, ctx.screen.scale * 0.3),
+                Align2::CENTER_CENTER,
+                title,
+                font.clone(),
+                border_color,
+            );
+
+            let digits = (bits as usize).div_ceil(4);
+            ctx.paint.text(
+                ctx.rect.center() + emath::vec2(0.0, ctx.screen.scale * 0.3),
+                Align2::CENTER_CENTER,
+                format!("{value:0digits$X}"),
+                font,
+                border_color,
+            );
+        }
+        None => {
+            ctx.paint.text(
+                ctx.rect.center(),
+                Align2::CENTER_CENTER,
+                title,
+                font,
+                border_color,
+            );
+        }
+    }
+}
+
+/// Returns `true` if clock pin went from low to high since last call
+fn clock_edge(clk: &CircuitPinInfo, ctx: &CircuitStateContext, last: &mut bool) -> bool {
+    let clock = clk.get_state(ctx) == WireState::True;
+    let edge = clock && !*last;
+    *last = clock;
+    edge
+}
+
+struct Random {
+    clk: CircuitPinInfo,
+    out: CircuitPinInfo,
+
+    bits: u32,
+    seed: Option<u32>,
+}
+
+impl Random {
+    fn new() -> Self {
+        let description = describe_clocked(Direction4::Right);
+        Self {
+            clk: description.pins[0].to_info(),
+            out: description.pins[1].to_info(),
+            bits: 8,
+            seed: None,
+        }
+    }
+
+    fn describe_props(props: &CircuitPropertyStore) -> CircuitDescription<2> {
+        let dir = props.read_clone("dir").unwrap_or(Direction4::Right);
+        describe_clocked(dir)
+    }
+
+    /// splitmix64, used to spread seed bits over the whole generator state
+    fn seed_rng(seed: Option<u32>) -> u64 {
+        let seed = match seed {
+            Some(seed) => seed as u64,
+            None => random_u128() as u64,
+        };
+
+        let mut z = seed.wrapping_add(0x9E3779B97F4A7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z = z ^ (z >> 31);
+
+        // xorshift gets stuck on zero state
+        if z == 0 {
+            0x9E3779B97F4A7C15
+        } else {
+            z
+        }
+    }
+
+    /// xorshift64*
+    fn next_random(rng: &mut u64) -> u64 {
+        let mut x = *rng;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        *rng = x;
+        x.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    fn reset_state(&self, state: &mut RandomState) {
+        state.rng = Self::seed_rng(self.seed);
+        state.value = Self::next_random(&mut state.rng);
+    }
+}
+
+impl CircuitImpl for Random {
+    fn draw(&self, state_ctx: &CircuitStateContext, paint_ctx: &PaintContext) {
+        let value = state_ctx
+            .read_circuit_internal_state(|s: &RandomState| s.value)
+            .unwrap_or_default();
+        draw_clocked(
+            "RNG",
+            Some((value & bit_mask(self.bits), self.bits)),
+            paint_ctx,
+            false,
+        );
+    }
+
+    fn create_pins(&mut self, circ: &Arc<Circuit>) -> Box<[CircuitPinInfo]> {
+        let description = Self::describe_props(&circ.props);
+        self.clk = description.pins[0].to_info();
+        self.out = description.pins[1].to_info();
+        vec![self.clk.clone(), self.out.clone()].into_boxed_slice()
+    }
+
+    fn update_signals(&self, state_ctx: &CircuitStateContext, changed_pin: Option<usize>) {
+        let value = state_ctx.write_circuit_internal_state(|s: &mut RandomState| {
+            if let None | Some(0) = changed_pin {
+                if clock_edge(&self.clk, state_ctx, &mut s.clock) {
+                    s.value = Self::next_random(&mut s.rng);
+                }
+            }
+            s.value
+        });
+        self.out
+            .set_state(state_ctx, WireState::from_bits(value, self.bits));
+    }
+
+    fn state_init(&self, ctx: &CircuitStateContext, first_init: bool) {
+        if first_init {
+            ctx.write_circuit_internal_state(|s: &mut RandomState| self.reset_state(s));
+        }
+    }
+
+    fn size(&self, circ: &Arc<Circuit>) -> Vec2u {
+        Self::describe_props(&circ.props).size
+    }
+
+    fn prop_changed(&self, prop_id: &str, resize: &mut bool, recreate_pins: &mut bool) {
+        if prop_id == "dir" {
+            *resize = true;
+            *recreate_pins = true;
+        }
+    }
+
+    fn apply_props(&mut self, circ: &Arc<Circuit>, prop: Option<&str>) {
+        self.bits = circ
+            .props
+            .read("bits", |v: &RangedValue<u32>| v.get())
+            .unwrap_or(8);
+        self.seed = circ
+            .props
+            .read_clone("seeded")
+            .unwrap_or(false)
+            .then(|| circ.props.read_clone("seed").unwrap_or(0));
+
+        // Restart the sequence so seeded generators stay reproducible after edits
+        if let Some("seed") | Some("seeded") = prop {
+            for state in circ.board.states.states.read().iter() {
+                let ctx = CircuitStateContext::new(state.clone(), circ.clone());
+                ctx.write_circuit_internal_state(|s: &mut RandomState| self.reset_state(s));
+            }
+        }
+    }
+
+    fn load_internal(
+        &self,
+        _ctx: &CircuitStateContext,
+        data: &serde_intermediate::Intermediate,
+        _paste: bool,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn InternalCircuitState>> {
+        serde_intermediate::from_intermediate::<RandomState>(data)
+            .ok()
+            .map(|v| Box::new(v) as _)
+    }
+}
+
+pub struct RandomPreview {}
+
+impl CircuitPreviewImpl for RandomPreview {
+    fn type_name(&self) -> DynStaticStr {
+        "random".into()
+    }
+
+    fn display_name(&self) -> DynStaticStr {
+        "Random".into()
+    }
+
+    fn description(&self) -> DynStaticStr {
+        "Outputs a new random value on every rising edge of its clock pin.\n\
+         Values wider than 1 bit are output as bundles, first wire being the lowest bit.\n\
+         When seeded, generated sequence is the same every time the circuit is placed or its seed is changed.\
+        ".into()
+    }
+
+    fn draw_preview(&self, _props: &CircuitPropertyStore, ctx: &PaintContext, in_world: bool) {
+        draw_clocked("RNG", None, ctx, in_world);
+    }
+
+    fn create_impl(&self) -> Box<dyn CircuitImpl> {
+        Box::new(Random::new())
+    }
+
+    fn load_copy_data(
+        &self,
+        _imp: &serde_intermediate::Intermediate,
+        _internal: &serde_intermediate::Intermediate,
+        _ctx: &Arc<SimulationContext>,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn CircuitPreviewImpl>> {
+        Some(Box::new(RandomPreview {}))
+    }
+
+    fn default_props(&self) -> CircuitPropertyStore {
+        CircuitPropertyStore::new([
+            CircuitProperty::new("dir", "Direction", Direction4::Right),
+            CircuitProperty::new("bits", "Bits", RangedValue::new(1..=64, 1, 8)),
+            CircuitProperty::new("seeded", "Seeded", false),
+            CircuitProperty::new("seed", "Seed", 0u32),
+        ])
+    }
+
+    fn describe(&self, props: &CircuitPropertyStore) -> DynCircuitDescription {
+        Random::describe_props(props).to_dyn()
+    }
+}
+
+struct Lfsr {
+    clk: CircuitPinInfo,
+    out: CircuitPinInfo,
+
+    bits: u32,
+    taps: u64,
+    seed: u64,
+}
+
+impl Lfsr {
+    fn new() -> Self {
+        let description = describe_clocked(Direction4::Right);
+        Self {
+            clk: description.pins[0].to_info(),
+            out: description.pins[1].to_info(),
+            bits: 8,
+            taps: 0xB8,
+            seed: 1,
+        }
+    }
+
+    fn describe_props(props: &CircuitPropertyStore) -> CircuitDescription<2> {
+        let dir = props.read_clone("dir").unwrap_or(Direction4::Right);
+        describe_clocked(dir)
+    }
+
+    /// Galois LFSR step, `taps` bit `n` set means feedback into bit `n`
+    fn step(value: u64, taps: u64, bits: u32) -> u64 {
+        let mask = bit_mask(bits);
+        let value = value & mask;
+        let shifted = value >> 1;
+        if value & 1 != 0 {
+            shifted ^ (taps & mask)
+        } else {
+            shifted
+        }
+    }
+
+    fn initial_value(&self) -> u64 {
+        // all zeroes is a lock-up state
+        match self.seed & bit_mask(self.bits) {
+            0 => 1,
+            v => v,
+        }
+    }
+}
+
+impl CircuitImpl for Lfsr {
+    fn draw(&self, state_ctx: &CircuitStateContext, paint_ctx: &PaintContext) {
+        let value = state_ctx
+            .read_circuit_internal_state(|s: &LfsrState| s.value)
+            .unwrap_or_default();
+        draw_clocked(
+            "LFSR",
+            Some((value & bit_mask(self.bits), self.bits)),
+            paint_ctx,
+            false,
+        );
+    }
+
+    fn create_pins(&mut self, circ: &Arc<Circuit>) -> Box<[CircuitPinInfo]> {
+        let description = Self::describe_props(&circ.props);
+        self.clk = description.pins[0].to_info();
+        self.out = description.pins[1].to_info();
+        vec![self.clk.clone(), self.out.clone()].into_boxed_slice()
+    }
+
+    fn update_signals(&self, state_ctx: &CircuitStateContext, changed_pin: Option<usize>) {
+        let value = state_ctx.write_circuit_internal_state(|s: &mut LfsrState| {
+            if let None | Some(0) = changed_pin {
+                if clock_edge(&self.clk, state_ctx, &mut s.clock) {
+                    s.value = Self::step(s.value, self.taps, self.bits);
+                }
+            }
+            s.value
+        });
+        self.out
+            .set_state(state_ctx, WireState::from_bits(value, self.bits));
+    }
+
+    fn state_init(&self, ctx: &CircuitStateContext, first_init: bool) {
+        if first_init {
+            ctx.write_circuit_internal_state(|s: &mut LfsrState| s.value = self.initial_value());
+        }
+    }
+
+    fn size(&self, circ: &Arc<Circuit>) -> Vec2u {
+        Self::describe_props(&circ.props).size
+    }
+
+    fn prop_changed(&self, prop_id: &str, resize: &mut bool, recreate_pins: &mut bool) {
+        if prop_id == "dir" {
+            *resize = true;
+            *recreate_pins = true;
+        }
+    }
+
+    fn apply_props(&mut self, circ: &Arc<Circuit>, prop: Option<&str>) {
+        self.bits = circ
+            .props
+            .read("bits", |v: &RangedValue<u32>| v.get())
+            .unwrap_or(8);
+        self.taps = circ.props.read_clone::<u32>("taps").unwrap_or(0xB8) as u64;
+        self.seed = circ.props.read_clone::<u32>("seed").unwrap_or(1) as u64;
+
+        if let Some("seed") | Some("bits") = prop {
+            for state in circ.board.states.states.read().iter() {
+                let ctx = CircuitStateContext::new(state.clone(), circ.clone());
+                ctx.write_circuit_internal_state(|s: &mut LfsrState| {
+                    s.value = self.initial_value()
+                });
+            }
+        }
+    }
+
+    fn load_internal(
+        &self,
+        _ctx: &CircuitStateContext,
+        data: &serde_intermediate::Intermediate,
+        _paste: bool,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn InternalCircuitState>> {
+        serde_intermediate::from_intermediate::<LfsrState>(data)
+            .ok()
+            .map(|v| Box::new(v) as _)
+    }
+}
+
+pub struct LfsrPreview {}
+
+impl CircuitPreviewImpl for LfsrPreview {
+    fn type_name(&self) -> DynStaticStr {
+        "lfsr".into()
+    }
+
+    fn display_name(&self) -> DynStaticStr {
+        "LFSR".into()
+    }
+
+    fn description(&self) -> DynStaticStr {
+        "Linear-feedback shift register, shifts its value on every rising edge of its clock pin.\n\
+         Tap mask selects bits that receive feedback (Galois form), e.g. 184 (0xB8) for a maximal length 8 bit sequence.\n\
+         Seed is the initial value, zero seed is replaced with 1.\
+        ".into()
+    }
+
+    fn draw_preview(&self, _props: &CircuitPropertyStore, ctx: &PaintContext, in_world: bool) {
+        draw_clocked("LFSR", None, ctx, in_world);
+    }
+
+    fn create_impl(&self) -> Box<dyn CircuitImpl> {
+        Box::new(Lfsr::new())
+    }
+
+    fn load_copy_data(
+        &self,
+        _imp: &serde_intermediate::Intermediate,
+        _internal: &serde_intermediate::Intermediate,
+        _ctx: &Arc<SimulationContext>,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn CircuitPreviewImpl>> {
+        Some(Box::new(LfsrPreview {}))
+    }
+
+    fn default_props(&self) -> CircuitPropertyStore {
+        CircuitPropertyStore::new([
+            CircuitProperty::new("dir", "Direction", Direction4::Right),
+            CircuitProperty::new("bits", "Bits", RangedValue::new(1..=32, 1, 8)),
+            CircuitProperty::new("taps", "Tap mask", 0xB8u32),
+            CircuitProperty::new("seed", "Seed", 1u32),
+        ])
+    }
+
+    fn describe(&self, props: &CircuitPropertyStore) -> DynCircuitDescription {
+        Lfsr::describe_props(props).to_dyn()
+    }
+}
@@ -0,0 +1,1195 @@
+use std::{collections::HashMap, iter::Peekable, str::Chars};
+
+use eframe::egui::{self, Ui};
+use eframe::epaint::{FontId, Rounding, Stroke};
+use emath::Align2;
+
+use crate::circuits::props::{CircuitProperty, CircuitPropertyImpl};
+use crate::circuits::*;
+
+// Scripts consist of declarations followed by statements, run every time inputs change:
+//
+// size 3 3                 # circuit size
+// input clk 0 1            # input pin `clk` at [0, 1]
+// input reset 1 2
+// output count 2 1 4       # 4-bit output pin `count` at [2, 1]
+// state value = 0          # variable kept between runs
+//
+// if reset { value = 0 }
+// else if rising(clk) { value = value + 1 }
+// count = value
+//
+// All values are 64-bit integers. Names assigned without declaration are local to a single run.
+// Scripts have no loops, so every run is guaranteed to finish.
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(i64),
+    Ident(String),
+    Op(&'static str),
+    Sep,
+    Eof,
+}
+
+const OPERATORS: &[&str] = &[
+    "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "&", "|", "^", "!",
+    "~", "<", ">", "=", "(", ")", "{", "}", ",",
+];
+
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn tokenize(str: &'a str) -> Result<Vec<(Token, usize)>, String> {
+        let mut lexer = Lexer {
+            chars: str.chars().peekable(),
+            line: 1,
+        };
+        let mut tokens = vec![];
+        loop {
+            let token = lexer.next()?;
+            let eof = token == Token::Eof;
+            tokens.push((token, lexer.line));
+            if eof {
+                return Ok(tokens);
+            }
+        }
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        while let Some(c) = self.chars.peek().copied() {
+            match c {
+                '\n' | ';' => {
+                    self.chars.next();
+                    let token = Token::Sep;
+                    if c == '\n' {
+                        self.line += 1;
+                    }
+                    return Ok(token);
+                }
+                '#' => while self.chars.next_if(|c| *c != '\n').is_some() {},
+                c if c.is_whitespace() => {
+                    self.chars.next();
+                }
+                '0'..='9' => return self.number(),
+                c if c.is_alphabetic() || c == '_' => {
+                    let mut ident = String::new();
+                    while let Some(c) = self.chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                        ident.push(c);
+                    }
+                    return Ok(Token::Ident(ident));
+                }
+                _ => {
+                    let first = self.chars.next().unwrap_or_default();
+                    let second = self.chars.peek().copied();
+                    if let Some(second) = second {
+                        let pair: String = [first, second].into_iter().collect();
+                        if let Some(op) = OPERATORS.iter().find(|op| **op == pair) {
+                            self.chars.next();
+                            return Ok(Token::Op(op));
+                        }
+                    }
+                    let op = OPERATORS
+                        .iter()
+                        .find(|op| op.len() == 1 && op.starts_with(first));
+                    return match op {
+                        Some(op) => Ok(Token::Op(op)),
+                        None => Err(format!("line {}: unexpected `{first}`", self.line)),
+                    };
+                }
+            }
+        }
+        Ok(Token::Eof)
+    }
+
+    fn number(&mut self) -> Result<Token, String> {
+        let mut str = String::new();
+        while let Some(c) = self.chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+            if c != '_' {
+                str.push(c);
+            }
+        }
+        // Hex and binary numbers are bit patterns, so they can set the sign bit
+        let parsed = if let Some(hex) = str.strip_prefix("0x") {
+            u64::from_str_radix(hex, 16).map(|n| n as i64)
+        } else if let Some(bin) = str.strip_prefix("0b") {
+            u64::from_str_radix(bin, 2).map(|n| n as i64)
+        } else {
+            str.parse()
+        };
+        parsed
+            .map(Token::Num)
+            .map_err(|_| format!("line {}: invalid number `{str}`", self.line))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Slot {
+    Input(usize),
+    Output(usize),
+    State(usize),
+    Local(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Builtin {
+    Rising,
+    Falling,
+    Known,
+    Bit,
+    Min,
+    Max,
+}
+
+enum Expr {
+    Num(i64),
+    Var(Slot),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Call(Builtin, Vec<Expr>),
+}
+
+impl Expr {
+    fn depth(&self) -> usize {
+        match self {
+            Expr::Num(_) | Expr::Var(_) => 1,
+            Expr::Unary(_, e) => e.depth() + 1,
+            Expr::Binary(_, a, b) => a.depth().max(b.depth()) + 1,
+            Expr::Call(_, args) => args.iter().map(Expr::depth).max().unwrap_or(0) + 1,
+        }
+    }
+}
+
+enum Stmt {
+    Assign(Slot, Expr),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+}
+
+pub struct ScriptPin {
+    pub name: Arc<str>,
+    pub pos: Vec2u,
+    pub bits: u32,
+}
+
+/// Parsed script, ready to run
+pub struct ScriptProgram {
+    pub size: Vec2u,
+    pub inputs: Vec<ScriptPin>,
+    pub outputs: Vec<ScriptPin>,
+    state: Vec<i64>,
+    locals: usize,
+    body: Vec<Stmt>,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    names: HashMap<String, Slot>,
+    program: ScriptProgram,
+    /// Current nesting of blocks and expressions
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos.min(self.tokens.len() - 1)].0
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.peek().clone();
+        self.pos += 1;
+        token
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, String> {
+        let line = self.tokens[self.pos.min(self.tokens.len() - 1)].1;
+        Err(format!("line {line}: {}", message.into()))
+    }
+
+    fn skip_separators(&mut self) {
+        while *self.peek() == Token::Sep {
+            self.pos += 1;
+        }
+    }
+
+    fn expect_op(&mut self, op: &str) -> Result<(), String> {
+        match self.next() {
+            Token::Op(o) if o == op => Ok(()),
+            _ => {
+                self.pos -= 1;
+                self.error(format!("expected `{op}`"))
+            }
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        match self.next() {
+            Token::Ident(ident) => Ok(ident),
+            _ => {
+                self.pos -= 1;
+                self.error("expected a name")
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<i64, String> {
+        let negative = *self.peek() == Token::Op("-");
+        if negative {
+            self.pos += 1;
+        }
+        match self.next() {
+            Token::Num(n) => Ok(if negative { n.wrapping_neg() } else { n }),
+            _ => {
+                self.pos -= 1;
+                self.error("expected a number")
+            }
+        }
+    }
+
+    /// Parses one nesting level deeper, so deeply nested scripts can't overflow the stack
+    fn nested<T>(
+        &mut self,
+        inner: impl FnOnce(&mut Self) -> Result<T, String>,
+    ) -> Result<T, String> {
+        if self.depth >= ScriptProgram::MAX_DEPTH {
+            return self.error("script is nested too deeply");
+        }
+        self.depth += 1;
+        let result = inner(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Checks depth of a new expression node, long operator chains nest without recursion
+    fn node(&self, expr: Expr) -> Result<Expr, String> {
+        match expr.depth() > ScriptProgram::MAX_DEPTH {
+            true => self.error("expression is nested too deeply"),
+            false => Ok(expr),
+        }
+    }
+
+    fn declare(&mut self, name: String, slot: Slot) -> Result<(), String> {
+        if self.names.contains_key(&name) {
+            return self.error(format!("`{name}` is already declared"));
+        }
+        self.names.insert(name, slot);
+        Ok(())
+    }
+
+    fn parse(str: &str) -> Result<ScriptProgram, String> {
+        let mut parser = Parser {
+            tokens: Lexer::tokenize(str)?,
+            pos: 0,
+            names: HashMap::new(),
+            depth: 0,
+            program: ScriptProgram {
+                size: [2, 2].into(),
+                inputs: vec![],
+                outputs: vec![],
+                state: vec![],
+                locals: 0,
+                body: vec![],
+            },
+        };
+
+        loop {
+            parser.skip_separators();
+            match parser.peek().clone() {
+                Token::Eof => break,
+                Token::Ident(ident) if ident == "size" => {
+                    parser.pos += 1;
+                    let width = parser.number()?;
+                    let height = parser.number()?;
+                    if !(1..=64).contains(&width) || !(1..=64).contains(&height) {
+                        return parser.error("size must be between 1 and 64");
+                    }
+                    parser.program.size = [width as u32, height as u32].into();
+                }
+                Token::Ident(ident) if ident == "input" || ident == "output" => {
+                    parser.pos += 1;
+                    let name = parser.ident()?;
+                    let x = parser.number()?;
+                    let y = parser.number()?;
+                    let bits = match parser.peek() {
+                        Token::Num(_) => parser.number()?,
+                        _ => 1,
+                    };
+                    let size = parser.program.size;
+                    if x < 0 || y < 0 || x >= size.x as i64 || y >= size.y as i64 {
+                        return parser.error(format!("pin `{name}` is outside of the circuit"));
+                    }
+                    if !(1..=64).contains(&bits) {
+                        return parser.error("pin width must be between 1 and 64 bits");
+                    }
+                    let pin = ScriptPin {
+                        name: name.as_str().into(),
+                        pos: [x as u32, y as u32].into(),
+                        bits: bits as u32,
+                    };
+                    if ident == "input" {
+                        parser.declare(name, Slot::Input(parser.program.inputs.len()))?;
+                        parser.program.inputs.push(pin);
+                    } else {
+                        parser.declare(name, Slot::Output(parser.program.outputs.len()))?;
+                        parser.program.outputs.push(pin);
+                    }
+                }
+                Token::Ident(ident) if ident == "state" => {
+                    parser.pos += 1;
+                    let name = parser.ident()?;
+                    let init = match parser.peek() {
+                        Token::Op("=") => {
+                            parser.pos += 1;
+                            parser.number()?
+                        }
+                        _ => 0,
+                    };
+                    parser.declare(name, Slot::State(parser.program.state.len()))?;
+                    parser.program.state.push(init);
+                }
+                _ => {
+                    let stmt = parser.statement()?;
+                    parser.program.body.push(stmt);
+                }
+            }
+        }
+
+        let pins = parser
+            .program
+            .inputs
+            .iter()
+            .chain(parser.program.outputs.iter());
+        let mut positions = HashMap::new();
+        for pin in pins {
+            if let Some(other) = positions.insert(pin.pos, pin.name.clone()) {
+                return Err(format!(
+                    "pins `{other}` and `{}` are at the same position",
+                    pin.name
+                ));
+            }
+        }
+
+        Ok(parser.program)
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, String> {
+        self.expect_op("{")?;
+        let mut stmts = vec![];
+        loop {
+            self.skip_separators();
+            match self.peek() {
+                Token::Op("}") => {
+                    self.pos += 1;
+                    return Ok(stmts);
+                }
+                Token::Eof => return self.error("expected `}`"),
+                _ => stmts.push(self.statement()?),
+            }
+        }
+    }
+
+    fn statement(&mut self) -> Result<Stmt, String> {
+        let name = self.ident()?;
+        if name == "if" {
+            let cond = self.expr(0)?;
+            let then = self.nested(Self::block)?;
+
+            // `else` may be on the next line
+            let before_else = self.pos;
+            self.skip_separators();
+            let otherwise = match self.peek() {
+                Token::Ident(ident) if ident == "else" => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Token::Ident(ident) if ident == "if" => {
+                            vec![self.nested(Self::statement)?]
+                        }
+                        _ => self.nested(Self::block)?,
+                    }
+                }
+                _ => {
+                    self.pos = before_else;
+                    vec![]
+                }
+            };
+            return Ok(Stmt::If(cond, then, otherwise));
+        }
+
+        self.expect_op("=")?;
+        let slot = match self.names.get(&name) {
+            Some(Slot::Input(_)) => return self.error(format!("can't assign to input `{name}`")),
+            Some(slot) => *slot,
+            None => {
+                let slot = Slot::Local(self.program.locals);
+                self.program.locals += 1;
+                self.names.insert(name, slot);
+                slot
+            }
+        };
+        let value = self.expr(0)?;
+        Ok(Stmt::Assign(slot, value))
+    }
+
+    fn binary_priority(op: &str) -> Option<u32> {
+        Some(match op {
+            "||" => 1,
+            "&&" => 2,
+            "|" => 3,
+            "^" => 4,
+            "&" => 5,
+            "==" | "!=" => 6,
+            "<" | ">" | "<=" | ">=" => 7,
+            "<<" | ">>" => 8,
+            "+" | "-" => 9,
+            "*" | "/" | "%" => 10,
+            _ => return None,
+        })
+    }
+
+    fn expr(&mut self, min_priority: u32) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while let Token::Op(op) = *self.peek() {
+            let priority = match Self::binary_priority(op) {
+                Some(p) if p > min_priority => p,
+                _ => break,
+            };
+            self.pos += 1;
+            let right = self.expr(priority)?;
+            left = self.node(Expr::Binary(op, Box::new(left), Box::new(right)))?;
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Token::Num(n) => Ok(Expr::Num(n)),
+            Token::Op(op @ ("-" | "!" | "~")) => {
+                let expr = self.nested(Self::unary)?;
+                self.node(Expr::Unary(op, Box::new(expr)))
+            }
+            Token::Op("(") => {
+                let expr = self.nested(|p| p.expr(0))?;
+                self.expect_op(")")?;
+                Ok(expr)
+            }
+            Token::Ident(name) => {
+                if *self.peek() == Token::Op("(") {
+                    return self.call(&name);
+                }
+                match self.names.get(&name) {
+                    Some(slot) => Ok(Expr::Var(*slot)),
+                    None => {
+                        self.pos -= 1;
+                        self.error(format!("unknown name `{name}`"))
+                    }
+                }
+            }
+            _ => {
+                self.pos -= 1;
+                self.error("expected a value")
+            }
+        }
+    }
+
+    fn call(&mut self, name: &str) -> Result<Expr, String> {
+        let (builtin, args) = match name {
+            "rising" => (Builtin::Rising, 1),
+            "falling" => (Builtin::Falling, 1),
+            "known" => (Builtin::Known, 1),
+            "bit" => (Builtin::Bit, 2),
+            "min" => (Builtin::Min, 2),
+            "max" => (Builtin::Max, 2),
+            _ => return self.error(format!("unknown function `{name}`")),
+        };
+        self.expect_op("(")?;
+        let mut list = vec![];
+        for i in 0..args {
+            if i > 0 {
+                self.expect_op(",")?;
+            }
+            list.push(self.nested(|p| p.expr(0))?);
+        }
+        self.expect_op(")")?;
+
+        if let Builtin::Rising | Builtin::Falling | Builtin::Known = builtin {
+            if !matches!(list[0], Expr::Var(Slot::Input(_))) {
+                return self.error(format!("`{name}` needs an input name"));
+            }
+        }
+        self.node(Expr::Call(builtin, list))
+    }
+}
+
+struct ScriptRun<'a> {
+    inputs: &'a [Option<i64>],
+    state: &'a mut ScriptState,
+    locals: Vec<i64>,
+}
+
+impl<'a> ScriptRun<'a> {
+    fn eval(&self, expr: &Expr) -> i64 {
+        match expr {
+            Expr::Num(n) => *n,
+            Expr::Var(slot) => self.read(*slot),
+            Expr::Unary(op, e) => {
+                let v = self.eval(e);
+                match *op {
+                    "-" => v.wrapping_neg(),
+                    "!" => (v == 0) as i64,
+                    _ => !v,
+                }
+            }
+            Expr::Binary(op, a, b) => {
+                let a = self.eval(a);
+
+                // Short-circuit logic operators
+                match *op {
+                    "&&" if a == 0 => return 0,
+                    "||" if a != 0 => return 1,
+                    _ => {}
+                }
+
+                let b = self.eval(b);
+                match *op {
+                    "+" => a.wrapping_add(b),
+                    "-" => a.wrapping_sub(b),
+                    "*" => a.wrapping_mul(b),
+                    "/" => a.checked_div(b).unwrap_or(0),
+                    "%" => a.checked_rem(b).unwrap_or(0),
+                    "&" => a & b,
+                    "|" => a | b,
+                    "^" => a ^ b,
+                    "<<" => a.checked_shl(b as u32).unwrap_or(0),
+                    ">>" => a.checked_shr(b as u32).unwrap_or(0),
+                    "==" => (a == b) as i64,
+                    "!=" => (a != b) as i64,
+                    "<" => (a < b) as i64,
+                    ">" => (a > b) as i64,
+                    "<=" => (a <= b) as i64,
+                    ">=" => (a >= b) as i64,
+                    "&&" | "||" => (b != 0) as i64,
+                    _ => 0,
+                }
+            }
+            Expr::Call(builtin, args) => {
+                let input = match args.first() {
+                    Some(Expr::Var(Slot::Input(i))) => Some(*i),
+                    _ => None,
+                };
+                match builtin {
+                    Builtin::Rising | Builtin::Falling => {
+                        let i = input.unwrap_or_default();
+                        let prev = self.state.prev_inputs.get(i).copied().unwrap_or(0) & 1;
+                        let current = self.inputs.get(i).copied().flatten().unwrap_or(0) & 1;
+                        match builtin {
+                            Builtin::Rising => (prev == 0 && current == 1) as i64,
+                            _ => (prev == 1 && current == 0) as i64,
+                        }
+                    }
+                    Builtin::Known => {
+                        let i = input.unwrap_or_default();
+                        self.inputs.get(i).is_some_and(|v| v.is_some()) as i64
+                    }
+                    Builtin::Bit => {
+                        let value = self.eval(&args[0]);
+                        let bit = self.eval(&args[1]);
+                        value.checked_shr(bit as u32).unwrap_or(0) & 1
+                    }
+                    Builtin::Min => self.eval(&args[0]).min(self.eval(&args[1])),
+                    Builtin::Max => self.eval(&args[0]).max(self.eval(&args[1])),
+                }
+            }
+        }
+    }
+
+    fn read(&self, slot: Slot) -> i64 {
+        match slot {
+            Slot::Input(i) => self.inputs.get(i).copied().flatten().unwrap_or(0),
+            Slot::Output(i) => self.state.outputs.get(i).copied().unwrap_or(0),
+            Slot::State(i) => self.state.vars.get(i).copied().unwrap_or(0),
+            Slot::Local(i) => self.locals.get(i).copied().unwrap_or(0),
+        }
+    }
+
+    fn write(&mut self, slot: Slot, value: i64) {
+        let target = match slot {
+            Slot::Input(_) => None,
+            Slot::Output(i) => self.state.outputs.get_mut(i),
+            Slot::State(i) => self.state.vars.get_mut(i),
+            Slot::Local(i) => self.locals.get_mut(i),
+        };
+        if let Some(target) = target {
+            *target = value;
+        }
+    }
+
+    fn run(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            match stmt {
+                Stmt::Assign(slot, expr) => {
+                    let value = self.eval(expr);
+                    self.write(*slot, value);
+                }
+                Stmt::If(cond, then, otherwise) => match self.eval(cond) != 0 {
+                    true => self.run(then),
+                    false => self.run(otherwise),
+                },
+            }
+        }
+    }
+}
+
+impl ScriptProgram {
+    /// Limits nesting of blocks and expressions, so scripts can't overflow the stack
+    pub const MAX_DEPTH: usize = 64;
+
+    pub fn parse(str: &str) -> Result<Self, String> {
+        Parser::parse(str)
+    }
+
+    /// Runs script body, returning output values
+    fn run(&self, inputs: &[Option<i64>], state: &mut ScriptState) -> Vec<i64> {
+        state.reset_to(self, false);
+
+        let mut run = ScriptRun {
+            inputs,
+            state,
+            locals: vec![0; self.locals],
+        };
+        run.run(&self.body);
+
+        state.prev_inputs.clear();
+        state
+            .prev_inputs
+            .extend(inputs.iter().map(|v| v.unwrap_or(0)));
+        state.outputs.clone()
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct ScriptState {
+    vars: Vec<i64>,
+    outputs: Vec<i64>,
+    prev_inputs: Vec<i64>,
+}
+
+impl ScriptState {
+    /// Makes state match script variables, resetting them to initial values if `force` is set
+    fn reset_to(&mut self, program: &ScriptProgram, force: bool) {
+        if force || self.vars.len() != program.state.len() {
+            self.vars = program.state.clone();
+        }
+        if force || self.outputs.len() != program.outputs.len() {
+            self.outputs = vec![0; program.outputs.len()];
+        }
+        if force {
+            self.prev_inputs.clear();
+        }
+    }
+}
+
+impl InternalCircuitState for ScriptState {
+    fn serialize(&self, _copy: bool) -> serde_intermediate::Intermediate {
+        serde_intermediate::to_intermediate(self).unwrap_or_default()
+    }
+}
+
+/// Multiline script text property, edited in a separate window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptSource(pub String);
+
+impl CircuitPropertyImpl for ScriptSource {
+    fn equals(&self, other: &dyn CircuitPropertyImpl) -> bool {
+        other.is_type_and(|o: &Self| o == self)
+    }
+
+    fn ui(&mut self, ui: &mut Ui, not_equal: bool) -> Option<Box<dyn CircuitPropertyImpl>> {
+        let id = ui.make_persistent_id("script_window");
+        let draft_id = id.with("draft");
+        let mut open = ui.data(|data| data.get_temp::<bool>(id).unwrap_or(false));
+
+        let text = match not_equal {
+            true => "Edit (different)",
+            false => "Edit",
+        };
+        if ui.button(text).clicked() {
+            open = !open;
+        }
+
+        // Window is shared by all script circuits, so draft is kept along with the source it was
+        // started from and is dropped when a circuit with different source is selected
+        let draft: Option<(String, String)> = ui.data(|data| data.get_temp(draft_id));
+        let mut draft = match draft {
+            Some((base, draft)) if base == self.0 => draft,
+            _ => self.0.clone(),
+        };
+        let mut apply = false;
+        egui::Window::new("Script")
+            .id(id)
+            .open(&mut open)
+            .default_size([400.0, 300.0])
+            .show(ui.ctx(), |ui| {
+                ui.horizontal(|ui| {
+                    apply = ui.button("Apply").clicked();
+                    if ui.button("Revert").clicked() {
+                        draft = self.0.clone();
+                    }
+                    match ScriptProgram::parse(&draft) {
+                        Ok(_) => ui.label("No errors"),
+                        Err(e) => ui.colored_label(Color32::RED, e),
+                    };
+                });
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut draft)
+                            .code_editor()
+                            .desired_width(f32::INFINITY)
+                            .desired_rows(16),
+                    );
+                });
+            });
+        let base = match apply {
+            true => draft.clone(),
+            false => self.0.clone(),
+        };
+        ui.data_mut(|data| {
+            data.insert_temp(id, open);
+            data.insert_temp(draft_id, (base, draft.clone()));
+        });
+
+        (apply && draft != self.0).then(|| {
+            let old = std::mem::replace(&mut self.0, draft);
+            Box::new(ScriptSource(old)) as Box<dyn CircuitPropertyImpl>
+        })
+    }
+
+    fn clone(&self) -> Box<dyn CircuitPropertyImpl> {
+        Box::new(Clone::clone(self))
+    }
+
+    fn load(&mut self, data: &serde_intermediate::Intermediate) {
+        if let Some(str) = data.as_str() {
+            self.0 = str.into();
+        }
+    }
+
+    fn save(&self) -> serde_intermediate::Intermediate {
+        serde_intermediate::to_intermediate(&self.0).unwrap_or_default()
+    }
+
+    fn copy_into(&self, other: &mut dyn CircuitPropertyImpl) {
+        if let Some(r) = other.downcast_mut::<Self>() {
+            r.0.clone_from(&self.0);
+        }
+    }
+}
+
+struct ScriptCircuit {
+    program: Result<Arc<ScriptProgram>, Arc<str>>,
+    inputs: Box<[CircuitPinInfo]>,
+    outputs: Box<[CircuitPinInfo]>,
+}
+
+impl ScriptCircuit {
+    const DEFAULT_SCRIPT: &'static str = "\
+# 4-bit counter
+size 3 3
+input clk 0 1
+input reset 1 2
+output count 2 1 4
+state value = 0
+
+if reset { value = 0 }
+else if rising(clk) { value = value + 1 }
+count = value
+";
+
+    fn new() -> Self {
+        let mut this = Self {
+            program: Err("".into()),
+            inputs: [].into(),
+            outputs: [].into(),
+        };
+        this.load_program(Self::DEFAULT_SCRIPT);
+        let description = Self::describe(&this.program);
+        this.create_pin_infos(&description);
+        this
+    }
+
+    fn load_program(&mut self, source: &str) {
+        self.program = ScriptProgram::parse(source)
+            .map(Arc::new)
+            .map_err(|e| e.into());
+    }
+
+    fn read_program(props: &CircuitPropertyStore) -> Result<Arc<ScriptProgram>, Arc<str>> {
+        let source = props.read("script", |s: &ScriptSource| s.0.clone());
+        let source = source.as_deref().unwrap_or(Self::DEFAULT_SCRIPT);
+        ScriptProgram::parse(source)
+            .map(Arc::new)
+            .map_err(|e| e.into())
+    }
+
+    fn create_pin_infos(&mut self, description: &DynCircuitDescription) {
+        let inputs = match &self.program {
+            Ok(program) => program.inputs.len(),
+            Err(_) => 0,
+        };
+        let (inputs, outputs) = description.pins.split_at(inputs);
+        self.inputs = inputs.iter().map(|p| p.to_info()).collect();
+        self.outputs = outputs.iter().map(|p| p.to_info()).collect();
+    }
+
+    fn draw(error: Option<&str>, ctx: &PaintContext, semi_transparent: bool) {
+        let opacity = if semi_transparent { 0.6 } else { 1.0 };
+
+        let border_color = ctx.style.component_border_color().linear_multiply(opacity);
+        let fill_color = ctx.style.component_fill_color().linear_multiply(opacity);
+
+        let rect = ctx.rect.shrink(ctx.screen.scale * 0.25);
+        ctx.paint.rect(
+            rect,
+            Rounding::same(ctx.screen.scale * 0.15),
+            fill_color,
+            Stroke::new(0.15 * ctx.screen.scale, border_color),
+        );
+
+        let (text, color) = match error {
+            None => ("{ }", border_color),
+            Some(_) => ("!", Color32::RED.linear_multiply(opacity)),
+        };
+        ctx.paint.text(
+            rect.center(),
+            Align2::CENTER_CENTER,
+            text,
+            FontId::monospace(ctx.screen.scale * 0.6),
+            color,
+        );
+    }
+
+    fn describe(program: &Result<Arc<ScriptProgram>, Arc<str>>) -> DynCircuitDescription {
+        let program = match program {
+            Ok(program) => program,
+            Err(_) => {
+                return DynCircuitDescription {
+                    size: [2, 2].into(),
+                    pins: [].into(),
+                }
+            }
+        };
+
+        let size = program.size;
+
+        let inputs = program
+            .inputs
+            .iter()
+            .map(|p| (p, InternalPinDirection::Inside));
+        let outputs = program
+            .outputs
+            .iter()
+            .map(|p| (p, InternalPinDirection::Outside));
+        let pins = inputs
+            .chain(outputs)
+            .map(|(pin, dir)| CircuitPinDescription {
+                active: true,
+                display_name: pin.name.clone().into(),
+                display_dir: edge_pin_dir(pin.pos, size),
+                dir,
+                name: pin.name.clone().into(),
+                pos: pin.pos,
+            })
+            .collect();
+
+        DynCircuitDescription { size, pins }
+    }
+}
+
+impl CircuitImpl for ScriptCircuit {
+    fn draw(&self, _: &CircuitStateContext, paint_ctx: &PaintContext) {
+        ScriptCircuit::draw(self.program.as_ref().err().map(|e| &**e), paint_ctx, false);
+    }
+
+    fn create_pins(&mut self, circ: &Arc<Circuit>) -> Box<[CircuitPinInfo]> {
+        self.program = Self::read_program(&circ.props);
+        let description = Self::describe(&self.program);
+        self.create_pin_infos(&description);
+
+        let mut vec = self.inputs.to_vec();
+        vec.extend(self.outputs.iter().cloned());
+        vec.into()
+    }
+
+    fn update_signals(&self, state_ctx: &CircuitStateContext, changed_pin: Option<usize>) {
+        let program = match &self.program {
+            Ok(program) => program,
+            Err(_) => return,
+        };
+        if changed_pin.is_some_and(|p| p >= self.inputs.len()) {
+            return;
+        }
+
+        let inputs: Vec<_> = self
+            .inputs
+            .iter()
+            .zip(program.inputs.iter())
+            .map(|(pin, decl)| {
+                let value = pin.get_state(state_ctx).to_bits()?;
+                let mask = u64::MAX.checked_shr(64 - decl.bits).unwrap_or(0);
+                Some((value & mask) as i64)
+            })
+            .collect();
+
+        let outputs =
+            state_ctx.write_circuit_internal_state(|s: &mut ScriptState| program.run(&inputs, s));
+
+        for ((pin, decl), value) in self.outputs.iter().zip(program.outputs.iter()).zip(outputs) {
+            pin.set_state(state_ctx, WireState::from_bits(value as u64, decl.bits));
+        }
+    }
+
+    fn state_init(&self, ctx: &CircuitStateContext, first_init: bool) {
+        if let (Ok(program), true) = (&self.program, first_init) {
+            ctx.write_circuit_internal_state(|s: &mut ScriptState| s.reset_to(program, true));
+        }
+    }
+
+    fn size(&self, circ: &Arc<Circuit>) -> Vec2u {
+        Self::describe(&Self::read_program(&circ.props)).size
+    }
+
+    fn prop_changed(&self, prop_id: &str, resize: &mut bool, recreate_pins: &mut bool) {
+        if prop_id == "script" {
+            *resize = true;
+            *recreate_pins = true;
+        }
+    }
+
+    fn apply_props(&mut self, circ: &Arc<Circuit>, prop: Option<&str>) {
+        self.program = Self::read_program(&circ.props);
+
+        // Script variables might have changed, start over from initial state
+        if let (Ok(program), Some("script")) = (&self.program, prop) {
+            for state in circ.board.states.states.read().iter() {
+                state.write_circuit(circ.id, |cs| {
+                    cs.get_internal_mut::<ScriptState>().reset_to(program, true)
+                });
+            }
+        }
+    }
+
+    fn load_internal(
+        &self,
+        _ctx: &CircuitStateContext,
+        data: &serde_intermediate::Intermediate,
+        _paste: bool,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn InternalCircuitState>> {
+        serde_intermediate::from_intermediate::<ScriptState>(data)
+            .ok()
+            .map(|v| Box::new(v) as _)
+    }
+}
+
+pub struct ScriptPreview {}
+
+impl CircuitPreviewImpl for ScriptPreview {
+    fn type_name(&self) -> DynStaticStr {
+        "script".into()
+    }
+
+    fn display_name(&self) -> DynStaticStr {
+        "Script".into()
+    }
+
+    fn description(&self) -> DynStaticStr {
+        "Circuit defined by a small script.\n\
+         Script declares circuit size, pins and state variables, \
+         followed by statements run every time inputs change:\n\
+         \n\
+         size W H\n\
+         input NAME X Y [BITS]\n\
+         output NAME X Y [BITS]\n\
+         state NAME [= VALUE]\n\
+         NAME = EXPRESSION\n\
+         if EXPRESSION { ... } else { ... }\n\
+         \n\
+         Values are integers, operators are the same as in C. \
+         Functions: rising(input), falling(input), known(input), bit(value, n), min(a, b), max(a, b).\n\
+         Outputs and state variables keep their values between runs, other names are reset every run. \
+         Unknown input values read as 0.\
+        "
+        .into()
+    }
+
+    fn draw_preview(&self, props: &CircuitPropertyStore, ctx: &PaintContext, in_world: bool) {
+        let program = ScriptCircuit::read_program(props);
+        ScriptCircuit::draw(program.as_ref().err().map(|e| &**e), ctx, in_world);
+    }
+
+    fn create_impl(&self) -> Box<dyn CircuitImpl> {
+        Box::new(ScriptCircuit::new())
+    }
+
+    fn load_copy_data(
+        &self,
+        _imp: &serde_intermediate::Intermediate,
+        _internal: &serde_intermediate::Intermediate,
+        _ctx: &Arc<SimulationContext>,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn CircuitPreviewImpl>> {
+        Some(Box::new(ScriptPreview {}))
+    }
+
+    fn default_props(&self) -> CircuitPropertyStore {
+        CircuitPropertyStore::new([CircuitProperty::new(
+            "script",
+            "Script",
+            ScriptSource(ScriptCircuit::DEFAULT_SCRIPT.into()),
+        )])
+    }
+
+    fn describe(&self, props: &CircuitPropertyStore) -> DynCircuitDescription {
+        ScriptCircuit::describe(&ScriptCircuit::read_program(props))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ScriptProgram, ScriptState};
+
+    /// Runs script once for every set of inputs, returning outputs of the last run
+    fn run(source: &str, runs: &[&[i64]]) -> Vec<i64> {
+        let program = ScriptProgram::parse(source).expect("script should parse");
+        let mut state = ScriptState::default();
+        state.reset_to(&program, true);
+        let mut outputs = vec![];
+        for inputs in runs {
+            let inputs: Vec<_> = inputs.iter().copied().map(Some).collect();
+            outputs = program.run(&inputs, &mut state);
+        }
+        outputs
+    }
+
+    fn eval(expr: &str) -> i64 {
+        run(&format!("output y 0 0 64\ny = {expr}"), &[&[]])[0]
+    }
+
+    fn parse_error(source: &str) -> String {
+        match ScriptProgram::parse(source) {
+            Ok(_) => panic!("{source:?} parsed"),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn operators_follow_precedence() {
+        assert_eq!(eval("1 + 2 * 3"), 7);
+        assert_eq!(eval("(1 + 2) * 3"), 9);
+        assert_eq!(eval("1 << 2 + 1"), 8);
+        assert_eq!(eval("6 & 3 == 3"), 0);
+        assert_eq!(eval("1 | 2 ^ 3 & 1"), 3);
+        assert_eq!(eval("10 - 4 - 3"), 3);
+        assert_eq!(eval("-2 * -3"), 6);
+        assert_eq!(eval("!0 + ~0"), 0);
+        assert_eq!(eval("1 < 2 && 2 < 1 || 3 >= 3"), 1);
+        assert_eq!(eval("min(5, 3) + max(5, 3) * bit(2, 1)"), 8);
+    }
+
+    #[test]
+    fn hex_and_binary_numbers_are_bit_patterns() {
+        assert_eq!(eval("0xff + 0b101"), 260);
+        assert_eq!(eval("0x8000_0000_0000_0000"), i64::MIN);
+        assert_eq!(eval("0xffffffffffffffff"), -1);
+        assert_eq!(eval(&format!("0b1{}", "0".repeat(63))), i64::MIN);
+        assert!(parse_error("output y 0 0\ny = 0x1_0000_0000_0000_0000").contains("invalid number"));
+    }
+
+    #[test]
+    fn invalid_operations_give_zero() {
+        assert_eq!(eval("7 / 0"), 0);
+        assert_eq!(eval("7 % 0"), 0);
+        assert_eq!(eval("1 << 64"), 0);
+        assert_eq!(eval("1 << -1"), 0);
+        assert_eq!(eval("8 >> -1"), 0);
+        assert_eq!(eval("bit(1, -1)"), 0);
+    }
+
+    #[test]
+    fn else_if_chains_pick_first_match() {
+        let source = "\
+input a 0 0 4
+output y 1 0 4
+if a == 0 { y = 10 }
+else if a == 1 { y = 11 }
+else if a < 4 {
+    y = 12
+} else { y = 13 }
+";
+        for (a, y) in [(0, 10), (1, 11), (2, 12), (3, 12), (9, 13)] {
+            assert_eq!(run(source, &[&[a]]), [y]);
+        }
+    }
+
+    #[test]
+    fn edges_are_detected_across_runs() {
+        let source = "\
+input clk 0 0
+output ups 1 0 8
+output downs 1 1 8
+if rising(clk) { ups = ups + 1 }
+if falling(clk) { downs = downs + 1 }
+";
+        assert_eq!(run(source, &[&[0], &[1], &[1], &[0], &[1]]), [2, 1]);
+        assert_eq!(run(source, &[&[1]]), [1, 0]);
+    }
+
+    #[test]
+    fn state_is_kept_and_locals_are_not() {
+        let source = "\
+input clk 0 0
+output y 1 0 8
+output z 1 1 8
+state count = 5
+if rising(clk) { count = count + 1; tmp = tmp + 1 }
+y = count
+z = tmp
+";
+        assert_eq!(run(source, &[&[0], &[1], &[0], &[1]]), [7, 1]);
+    }
+
+    #[test]
+    fn invalid_scripts_are_rejected() {
+        assert!(parse_error("input a 0 0\na = 1").contains("can't assign to input `a`"));
+        assert!(parse_error("output y 0 0\ny = x").contains("unknown name `x`"));
+        assert!(parse_error("output y 0 0\ny = foo(1)").contains("unknown function"));
+        assert!(parse_error("input a 0 0\ninput a 1 1").contains("already declared"));
+        assert!(parse_error("input a 0 0\noutput b 0 0").contains("same position"));
+        assert!(parse_error("input a 5 5").contains("outside"));
+        assert!(parse_error("output y 0 0\ny = (1").contains("expected `)`"));
+        assert!(parse_error("output y 0 0\nif 1 { y = 1").contains("expected `}`"));
+    }
+
+    #[test]
+    fn deep_nesting_is_rejected() {
+        let depth = ScriptProgram::MAX_DEPTH;
+        let parens = |n: usize| format!("x = {}1{}", "(".repeat(n), ")".repeat(n));
+        assert!(ScriptProgram::parse(&parens(depth)).is_ok());
+        assert!(ScriptProgram::parse(&parens(depth + 1)).is_err());
+
+        let chain = |n: usize| format!("x = 1{}", "+1".repeat(n));
+        assert!(ScriptProgram::parse(&chain(depth - 1)).is_ok());
+        assert!(ScriptProgram::parse(&chain(depth)).is_err());
+
+        let huge = 100_000;
+        assert!(ScriptProgram::parse(&parens(huge)).is_err());
+        assert!(ScriptProgram::parse(&chain(huge)).is_err());
+        assert!(ScriptProgram::parse(&format!("x = {}1", "-".repeat(huge))).is_err());
+        assert!(ScriptProgram::parse(&format!("x = {}1", "!~".repeat(huge))).is_err());
+        assert!(ScriptProgram::parse(&"if 1 { ".repeat(huge)).is_err());
+        let else_ifs = |n: usize| format!("x = 0\n{}{{ x = 2 }}", "if x { x = 1 } else ".repeat(n));
+        assert!(ScriptProgram::parse(&else_ifs(depth)).is_ok());
+        assert!(ScriptProgram::parse(&else_ifs(huge)).is_err());
+    }
+}
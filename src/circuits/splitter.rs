@@ -0,0 +1,412 @@
+use eframe::epaint::{Color32, FontId, Stroke};
+use emath::Align2;
+
+use crate::circuits::props::CircuitProperty;
+use crate::pool::{PooledColor32Vec, PooledStateVec};
+use crate::ui::editor::CircuitBoardEditor;
+use crate::vector::Vec2f;
+use crate::wires::WireColors;
+use crate::{circuits::*, ArcString};
+
+create_safe_prop_enums! {
+    #[default(Split)]
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    pub enum SplitterMode {
+        Split('s'),
+        Merge('m'),
+    }
+}
+
+/// Bit field of a bundle
+struct BitField {
+    /// Bundle bit indices, first being the lowest bit of the field
+    bits: Vec<u32>,
+    name: Arc<str>,
+}
+
+impl BitField {
+    /// Limits bundle width, so typos won't create huge bundles
+    const MAX_BIT: u32 = 255;
+
+    /// Parses fields separated by `;`, each being a comma-separated list of bits or bit ranges,
+    /// highest bit first, in optional brackets: `[7:4]; [3]; [2:0]`<br>
+    /// Ranges can be reversed (`[0:3]`) and bits scattered (`[6, 4, 2, 0]`). Invalid fields are skipped
+    fn parse_list(str: &str) -> Vec<BitField> {
+        str.split(';').filter_map(Self::parse).collect()
+    }
+
+    fn parse(str: &str) -> Option<BitField> {
+        let str = str.trim();
+        let inner = str
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .unwrap_or(str)
+            .trim();
+        if inner.is_empty() {
+            return None;
+        }
+
+        let mut bits = vec![];
+        for part in inner.split(',') {
+            let part = part.trim();
+            match part.split_once(':') {
+                Some((high, low)) => {
+                    let high: u32 = high.trim().parse().ok()?;
+                    let low: u32 = low.trim().parse().ok()?;
+                    if high.max(low) > Self::MAX_BIT {
+                        return None;
+                    }
+                    if high >= low {
+                        bits.extend((low..=high).rev());
+                    } else {
+                        bits.extend(high..=low);
+                    }
+                }
+                None => {
+                    let bit: u32 = part.parse().ok()?;
+                    if bit > Self::MAX_BIT {
+                        return None;
+                    }
+                    bits.push(bit);
+                }
+            }
+        }
+        bits.reverse();
+
+        Some(BitField {
+            bits,
+            name: format!("[{inner}]").into(),
+        })
+    }
+}
+
+/// Bundle bits of every splitter field, lowest first
+pub fn field_bits(props: &CircuitPropertyStore) -> Vec<Vec<u32>> {
+    Splitter::read_fields(props)
+        .into_iter()
+        .map(|f| f.bits)
+        .collect()
+}
+
+struct Splitter {
+    dir: Direction4,
+    mode: SplitterMode,
+    fields: Vec<BitField>,
+
+    bundle: CircuitPinInfo,
+    io: Box<[CircuitPinInfo]>,
+}
+
+impl Splitter {
+    const DEFAULT_FIELDS: &'static str = "[7:4]; [3:0]";
+    const BUNDLER_COLOR: Color32 = Color32::from_gray(16);
+
+    fn new() -> Self {
+        let fields = BitField::parse_list(Self::DEFAULT_FIELDS);
+        let description = Self::describe(Direction4::Right, SplitterMode::Split, &fields);
+        Self {
+            dir: Direction4::Right,
+            mode: SplitterMode::Split,
+            bundle: description.pins[0].to_info(),
+            io: description.pins[1..].iter().map(|d| d.to_info()).collect(),
+            fields,
+        }
+    }
+
+    fn read_fields(props: &CircuitPropertyStore) -> Vec<BitField> {
+        let fields = props.read("fields", |s: &ArcString| s.get_arc());
+        match fields {
+            Some(fields) => BitField::parse_list(&fields),
+            None => BitField::parse_list(Self::DEFAULT_FIELDS),
+        }
+    }
+
+    fn size(dir: Direction4, fields: usize) -> Vec2u {
+        let size = Vec2u::new(2, fields as u32 + 1);
+        if dir.is_horizontal() {
+            size
+        } else {
+            [size.y, size.x].into()
+        }
+    }
+
+    fn draw(
+        dir: Direction4,
+        fields: &[BitField],
+        bundle: Color32,
+        io: &[Color32],
+        ctx: &PaintContext,
+    ) {
+        let size = Vec2f::new(2.0, fields.len() as f32 + 1.0);
+        let transformer = |v: Vec2f| {
+            let v = v / size;
+            let v = if dir.is_horizontal() {
+                v
+            } else {
+                [v.y, v.x].into()
+            };
+            ctx.rect.lerp_inside(v.into())
+        };
+
+        let (io_x, bundle_x) = if dir.is_right_bottom() {
+            (1.5, 0.5)
+        } else {
+            (0.5, 1.5)
+        };
+        let body_x = 1.0;
+
+        let wire_stroke =
+            |color| Stroke::new(CircuitBoardEditor::WIRE_THICKNESS * ctx.screen.scale, color);
+        let body_stroke = Stroke::new(
+            CircuitBoardEditor::WIRE_POINT_THICKNESS * ctx.screen.scale,
+            Self::BUNDLER_COLOR,
+        );
+
+        ctx.paint.line_segment(
+            [
+                transformer([bundle_x, 0.5].into()),
+                transformer([body_x, 0.5].into()),
+            ],
+            wire_stroke(bundle),
+        );
+
+        for (i, (field, color)) in fields.iter().zip(io.iter().copied()).enumerate() {
+            let y = i as f32 + 1.5;
+            ctx.paint.line_segment(
+                [
+                    transformer([io_x, y].into()),
+                    transformer([body_x, y].into()),
+                ],
+                wire_stroke(color),
+            );
+            if ctx.screen.scale > 10.0 {
+                ctx.paint.text(
+                    transformer([(io_x + body_x) / 2.0, y - 0.2].into()),
+                    Align2::CENTER_BOTTOM,
+                    &field.name,
+                    FontId::monospace(ctx.screen.scale * 0.3),
+                    Color32::GRAY,
+                );
+            }
+        }
+
+        ctx.paint.line_segment(
+            [
+                transformer([body_x, 0.5].into()),
+                transformer([body_x, fields.len() as f32 + 0.5].into()),
+            ],
+            body_stroke,
+        );
+    }
+
+    fn describe_props(props: &CircuitPropertyStore) -> DynCircuitDescription {
+        let dir = props.read_clone("dir").unwrap_or(Direction4::Right);
+        let mode = props.read_clone("mode").unwrap_or(SplitterMode::Split);
+        Self::describe(dir, mode, &Self::read_fields(props))
+    }
+
+    fn describe(dir: Direction4, mode: SplitterMode, fields: &[BitField]) -> DynCircuitDescription {
+        let (io_x, bundle_x) = if dir.is_right_bottom() {
+            (1, 0)
+        } else {
+            (0, 1)
+        };
+        let pos = |x: u32, y: u32| -> Vec2u {
+            if dir.is_horizontal() {
+                [x, y].into()
+            } else {
+                [y, x].into()
+            }
+        };
+
+        let (bundle_dir, io_dir, bundle_name) = match mode {
+            SplitterMode::Split => (
+                InternalPinDirection::Inside,
+                InternalPinDirection::Outside,
+                "Bundle In",
+            ),
+            SplitterMode::Merge => (
+                InternalPinDirection::Outside,
+                InternalPinDirection::Inside,
+                "Bundle Out",
+            ),
+        };
+
+        let mut pins = vec![CircuitPinDescription {
+            active: true,
+            display_name: bundle_name.into(),
+            display_dir: Some(dir.inverted()),
+            dir: bundle_dir,
+            name: "bundle".into(),
+            pos: pos(bundle_x, 0),
+        }];
+
+        for (i, field) in fields.iter().enumerate() {
+            pins.push(CircuitPinDescription {
+                active: true,
+                display_name: field.name.clone().into(),
+                display_dir: Some(dir),
+                dir: io_dir,
+                name: format!("field_{i}").into(),
+                pos: pos(io_x, i as u32 + 1),
+            });
+        }
+
+        DynCircuitDescription {
+            size: Self::size(dir, fields.len()),
+            pins: pins.into(),
+        }
+    }
+}
+
+impl CircuitImpl for Splitter {
+    fn draw(&self, state_ctx: &CircuitStateContext, paint_ctx: &PaintContext) {
+        let mut io = PooledColor32Vec::new();
+        for pin in self.io.iter() {
+            io.push(pin.wire_or_self_color(state_ctx, paint_ctx.style));
+        }
+        let bundle = self.bundle.wire_or_self_color(state_ctx, paint_ctx.style);
+        Splitter::draw(self.dir, &self.fields, bundle, &io, paint_ctx);
+    }
+
+    fn create_pins(&mut self, circ: &Arc<Circuit>) -> Box<[CircuitPinInfo]> {
+        let description = Self::describe_props(&circ.props);
+        self.fields = Self::read_fields(&circ.props);
+        self.bundle = description.pins[0].to_info();
+        self.io = description.pins[1..].iter().map(|d| d.to_info()).collect();
+
+        let mut vec = vec![self.bundle.clone()];
+        vec.extend(self.io.iter().cloned());
+        vec.into()
+    }
+
+    fn update_signals(&self, state_ctx: &CircuitStateContext, _changed_pin: Option<usize>) {
+        match self.mode {
+            SplitterMode::Split => {
+                let bundle = self.bundle.get_state(state_ctx);
+                for (field, io) in self.fields.iter().zip(self.io.iter()) {
+                    let state = match field.bits.as_slice() {
+                        [bit] => bundle.get(*bit as usize),
+                        bits => {
+                            let mut out = PooledStateVec::new();
+                            out.extend(bits.iter().map(|bit| bundle.get(*bit as usize)));
+                            WireState::Bundle(Arc::new(out))
+                        }
+                    };
+                    io.set_state(state_ctx, state);
+                }
+            }
+            SplitterMode::Merge => {
+                let width = self
+                    .fields
+                    .iter()
+                    .flat_map(|f| f.bits.iter())
+                    .max()
+                    .map(|max| *max as usize + 1)
+                    .unwrap_or(0);
+
+                let mut out = PooledStateVec::new();
+                out.extend((0..width).map(|_| WireState::None));
+
+                // Overlapping fields are merged like wires, conflicting values become errors
+                for (field, io) in self.fields.iter().zip(self.io.iter()) {
+                    let state = io.get_state(state_ctx);
+                    for (i, bit) in field.bits.iter().enumerate() {
+                        out[*bit as usize].merge(&state.get(i));
+                    }
+                }
+
+                self.bundle
+                    .set_state(state_ctx, WireState::Bundle(Arc::new(out)));
+            }
+        }
+    }
+
+    fn size(&self, circ: &Arc<Circuit>) -> Vec2u {
+        let dir = circ.props.read_clone("dir").unwrap_or(Direction4::Right);
+        Self::size(dir, Self::read_fields(&circ.props).len())
+    }
+
+    fn prop_changed(&self, prop_id: &str, resize: &mut bool, recreate_pins: &mut bool) {
+        match prop_id {
+            "dir" | "fields" => {
+                *resize = true;
+                *recreate_pins = true;
+            }
+            "mode" => {
+                *recreate_pins = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_props(&mut self, circ: &Arc<Circuit>, _: Option<&str>) {
+        self.dir = circ.props.read_clone("dir").unwrap_or(Direction4::Right);
+        self.mode = circ.props.read_clone("mode").unwrap_or(SplitterMode::Split);
+        self.fields = Self::read_fields(&circ.props);
+    }
+}
+
+pub struct SplitterPreview {}
+
+impl CircuitPreviewImpl for SplitterPreview {
+    fn type_name(&self) -> DynStaticStr {
+        "splitter".into()
+    }
+
+    fn display_name(&self) -> DynStaticStr {
+        "Splitter".into()
+    }
+
+    fn description(&self) -> DynStaticStr {
+        "Splits a bundle into bit fields, or merges bit fields into a bundle.\n\
+         Fields are separated by `;`, each being a list of bits or bit ranges, highest bit first. \
+         For example `[7:4]; [3]; [2:0]` slices a byte into three fields, \
+         `[0:3]` reverses bit order and `[6, 4, 2, 0]` picks every other bit.\
+        "
+        .into()
+    }
+
+    fn draw_preview(&self, props: &CircuitPropertyStore, ctx: &PaintContext, _in_world: bool) {
+        let dir = props.read_clone("dir").unwrap_or(Direction4::Right);
+        let fields = Splitter::read_fields(props);
+        let false_color = ctx.style.wire_colors.false_color();
+
+        let mut io = PooledColor32Vec::new();
+        io.extend(fields.iter().map(|f| match f.bits.len() {
+            1 => false_color,
+            _ => WireColors::BUNDLE,
+        }));
+        Splitter::draw(dir, &fields, WireColors::BUNDLE, &io, ctx);
+    }
+
+    fn create_impl(&self) -> Box<dyn CircuitImpl> {
+        Box::new(Splitter::new())
+    }
+
+    fn load_copy_data(
+        &self,
+        _imp: &serde_intermediate::Intermediate,
+        _internal: &serde_intermediate::Intermediate,
+        _ctx: &Arc<SimulationContext>,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn CircuitPreviewImpl>> {
+        Some(Box::new(SplitterPreview {}))
+    }
+
+    fn default_props(&self) -> CircuitPropertyStore {
+        CircuitPropertyStore::new([
+            CircuitProperty::new("dir", "Direction", Direction4::Right),
+            CircuitProperty::new("mode", "Mode", SplitterMode::Split),
+            CircuitProperty::new(
+                "fields",
+                "Fields",
+                ArcString::from(Splitter::DEFAULT_FIELDS),
+            ),
+        ])
+    }
+
+    fn describe(&self, props: &CircuitPropertyStore) -> DynCircuitDescription {
+        Splitter::describe_props(props)
+    }
+}
@@ -0,0 +1,299 @@
+use eframe::epaint::{Shape, Stroke};
+
+use crate::circuits::props::CircuitProperty;
+use crate::{circuits::*, unwrap_option_or_return};
+use crate::vector::Vec2f;
+
+/// Connects wires of all tunnels with the same name on the same board, without drawing them
+struct Tunnel {
+    pin: CircuitPinInfo,
+    dir: Direction4,
+
+    /// Name this tunnel is registered with in `CircuitBoard::tunnels`
+    registered: Option<Arc<str>>,
+}
+
+impl Tunnel {
+    const DEFAULT_DIR: Direction4 = Direction4::Left;
+
+    fn new() -> Self {
+        let description = Self::describe(Self::DEFAULT_DIR);
+        Self {
+            pin: description.pins[0].to_info(),
+            dir: Self::DEFAULT_DIR,
+            registered: None,
+        }
+    }
+
+    fn draw(dir: Direction4, border: Color32, ctx: &PaintContext, semi_transparent: bool) {
+        let opacity = if semi_transparent { 0.6 } else { 1.0 };
+
+        let fill_color = ctx.style.component_fill_color().linear_multiply(opacity);
+        let border_color = border.linear_multiply(opacity);
+
+        // Flag pointing to the pin, drawn for Left direction and rotated
+        let size = if dir.is_horizontal() {
+            Vec2f::new(2.0, 1.0)
+        } else {
+            Vec2f::new(1.0, 2.0)
+        };
+        let transformer = |x: f32, y: f32| {
+            let (x, y) = match dir {
+                Direction4::Left => (x, y),
+                Direction4::Right => (2.0 - x, y),
+                Direction4::Up => (y, x),
+                Direction4::Down => (y, 2.0 - x),
+            };
+            ctx.rect.lerp_inside((Vec2f::new(x, y) / size).into())
+        };
+
+        let points = vec![
+            transformer(0.15, 0.5),
+            transformer(0.6, 0.15),
+            transformer(1.85, 0.15),
+            transformer(1.85, 0.85),
+            transformer(0.6, 0.85),
+        ];
+
+        ctx.paint.add(Shape::convex_polygon(
+            points,
+            fill_color,
+            Stroke::new(ctx.screen.scale * 0.1, border_color),
+        ));
+    }
+
+    fn describe_props(props: &CircuitPropertyStore) -> CircuitDescription<1> {
+        let dir = props.read_clone("dir").unwrap_or(Self::DEFAULT_DIR);
+        Self::describe(dir)
+    }
+
+    fn describe(dir: Direction4) -> CircuitDescription<1> {
+        let (size, pos) = match dir {
+            Direction4::Left => ([2, 1], [0, 0]),
+            Direction4::Right => ([2, 1], [1, 0]),
+            Direction4::Up => ([1, 2], [0, 0]),
+            Direction4::Down => ([1, 2], [0, 1]),
+        };
+
+        CircuitDescription {
+            size: size.into(),
+            pins: [CircuitPinDescription {
+                active: true,
+                display_name: "".into(),
+                display_dir: None,
+                dir: InternalPinDirection::Custom,
+                name: "pin".into(),
+                pos: pos.into(),
+            }],
+        }
+    }
+
+    fn register(&mut self, circ: &Arc<Circuit>) {
+        let name = circ.name();
+        if name == self.registered {
+            return;
+        }
+        self.unregister(circ);
+
+        if let Some(name) = &name {
+            circ.board
+                .tunnels
+                .write()
+                .entry(name.clone())
+                .or_default()
+                .insert(circ.id);
+        }
+        self.registered = name;
+    }
+
+    fn unregister(&mut self, circ: &Arc<Circuit>) {
+        let name = unwrap_option_or_return!(self.registered.take());
+
+        let linked = {
+            let mut tunnels = circ.board.tunnels.write();
+            let group = unwrap_option_or_return!(tunnels.get_mut(&name));
+            group.remove(&circ.id);
+            if group.is_empty() {
+                tunnels.remove(&name);
+                return;
+            }
+            group.iter().copied().collect::<Vec<_>>()
+        };
+
+        // Tunnels left in the old group are no longer connected to this one
+        let circuits = circ.board.circuits.read();
+        for id in linked {
+            let circuit = unwrap_option_or_continue!(circuits.get(id));
+            let info = circuit.info.read();
+            let pin = unwrap_option_or_continue!(info.pins.first());
+            if let Some(wire) = pin.connected_wire() {
+                circ.board.states.update_wire(wire, true);
+            }
+        }
+    }
+
+    /// Calls `f` with pins of all other tunnels with the same name
+    fn for_each_linked_pin(&self, ctx: &CircuitStateContext, mut f: impl FnMut(&CircuitPin)) {
+        let name = unwrap_option_or_return!(&self.registered);
+        let linked = {
+            let tunnels = ctx.circuit.board.tunnels.read();
+            let group = unwrap_option_or_return!(tunnels.get(name));
+            group
+                .iter()
+                .copied()
+                .filter(|id| *id != ctx.circuit.id)
+                .collect::<Vec<_>>()
+        };
+
+        for id in linked {
+            let circuit = ctx.circuit.board.circuits.read().get(id).cloned();
+            let circuit = unwrap_option_or_continue!(circuit);
+            let pin = circuit.info.read().pins.first().map(|p| p.pin.clone());
+            let pin = unwrap_option_or_continue!(pin);
+            let pin = pin.read();
+            f(&pin);
+        }
+    }
+}
+
+impl CircuitImpl for Tunnel {
+    fn draw(&self, state_ctx: &CircuitStateContext, paint_ctx: &PaintContext) {
+        let border = self.pin.wire_or_self_color(state_ctx, paint_ctx.style);
+        Tunnel::draw(self.dir, border, paint_ctx, false);
+    }
+
+    fn create_pins(&mut self, circ: &Arc<Circuit>) -> Box<[CircuitPinInfo]> {
+        let description = Self::describe_props(&circ.props);
+        self.pin = description.pins[0].to_info();
+        vec![self.pin.clone()].into_boxed_slice()
+    }
+
+    fn update_signals(&self, state_ctx: &CircuitStateContext, changed_pin: Option<usize>) {
+        // Tunnel group might have changed, recalculate everything connected to this tunnel
+        if changed_pin.is_none() {
+            if let Some(wire) = self.pin.connected_wire() {
+                state_ctx.global_state.update_wire(wire, true);
+            }
+        }
+    }
+
+    fn circuit_init(&mut self, circ: &Arc<Circuit>, _first_init: bool) {
+        self.register(circ);
+    }
+
+    fn circuit_remove(&mut self, circ: &Arc<Circuit>) {
+        self.unregister(circ);
+    }
+
+    fn custom_pin_mutate_state(
+        &self,
+        ctx: &CircuitStateContext,
+        _pin: usize,
+        state: &mut WireState,
+        visited_items: &mut VisitedList,
+    ) {
+        let uid = ctx.circuit.board.uid;
+        self.for_each_linked_pin(ctx, |pin| {
+            if visited_items.contains(uid, VisitedItem::Pin(pin.id)) {
+                return;
+            }
+            if let Some(wire) = pin.connected_wire() {
+                visited_items.push(uid, VisitedItem::Pin(pin.id));
+                ctx.global_state
+                    .compute_wire_state(wire, state, visited_items);
+                visited_items.pop(uid);
+            }
+        });
+    }
+
+    fn custom_pin_apply_state(
+        &self,
+        ctx: &CircuitStateContext,
+        _pin: usize,
+        state: &WireState,
+        visited_items: &mut VisitedList,
+    ) {
+        let uid = ctx.circuit.board.uid;
+        self.for_each_linked_pin(ctx, |pin| {
+            if visited_items.contains(uid, VisitedItem::Pin(pin.id)) {
+                return;
+            }
+            pin.set_input(&ctx.global_state, state, false, None);
+            if let Some(wire) = pin.connected_wire() {
+                visited_items.push(uid, VisitedItem::Pin(pin.id));
+                ctx.global_state
+                    .apply_wire_state(wire, state, false, visited_items);
+                visited_items.pop(uid);
+            }
+        });
+    }
+
+    fn size(&self, circ: &Arc<Circuit>) -> Vec2u {
+        Self::describe_props(&circ.props).size
+    }
+
+    fn prop_changed(&self, prop_id: &str, resize: &mut bool, recreate_pins: &mut bool) {
+        if prop_id == "dir" {
+            *resize = true;
+            *recreate_pins = true;
+        }
+    }
+
+    fn apply_props(&mut self, circ: &Arc<Circuit>, prop: Option<&str>) {
+        self.dir = circ.props.read_clone("dir").unwrap_or(Self::DEFAULT_DIR);
+
+        // Before `circuit_init` this circuit isn't on the board yet
+        if prop == Some("name") {
+            self.register(circ);
+        }
+    }
+}
+
+pub struct TunnelPreview {}
+
+impl CircuitPreviewImpl for TunnelPreview {
+    fn type_name(&self) -> DynStaticStr {
+        "tunnel".into()
+    }
+
+    fn display_name(&self) -> DynStaticStr {
+        "Tunnel".into()
+    }
+
+    fn description(&self) -> DynStaticStr {
+        "Connects its wire to wires of all other tunnels with the same name on this board.\n\
+         Tunnels without a name aren't connected to anything.\
+        "
+        .into()
+    }
+
+    fn draw_preview(&self, props: &CircuitPropertyStore, ctx: &PaintContext, in_world: bool) {
+        let dir = props.read_clone("dir").unwrap_or(Tunnel::DEFAULT_DIR);
+        Tunnel::draw(dir, ctx.style.component_border_color(), ctx, in_world);
+    }
+
+    fn create_impl(&self) -> Box<dyn CircuitImpl> {
+        Box::new(Tunnel::new())
+    }
+
+    fn load_copy_data(
+        &self,
+        _imp: &serde_intermediate::Intermediate,
+        _internal: &serde_intermediate::Intermediate,
+        _ctx: &Arc<SimulationContext>,
+        _errors: &mut ErrorList,
+    ) -> Option<Box<dyn CircuitPreviewImpl>> {
+        Some(Box::new(TunnelPreview {}))
+    }
+
+    fn default_props(&self) -> CircuitPropertyStore {
+        CircuitPropertyStore::new([
+            CircuitProperty::new("dir", "Direction", Tunnel::DEFAULT_DIR),
+            CircuitProperty::new("label_dir", "Label dir", Direction4::Right),
+        ])
+    }
+
+    fn describe(&self, props: &CircuitPropertyStore) -> DynCircuitDescription {
+        Tunnel::describe_props(props).to_dyn()
+    }
+}
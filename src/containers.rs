@@ -678,6 +678,62 @@ impl<const CHUNK_SIZE: usize, T: Default> Chunks2D<CHUNK_SIZE, T> {
     }
 }
 
+impl<const CHUNK_SIZE: usize, T: Default> Chunks2D<CHUNK_SIZE, T> {
+    /// Top-left and bottom-right inclusive positions of all occupied items.
+    /// Only existing chunks are checked, skipping ones fully inside already found bounds
+    pub fn occupied_bounds(
+        &self,
+        occupied: impl Fn(&T) -> bool,
+    ) -> Option<(Vec2isize, Vec2isize)> {
+        let mut bounds: Option<(Vec2isize, Vec2isize)> = None;
+        for (qid, quarter) in self.quarters.iter().enumerate() {
+            for (qy, row) in quarter.iter().enumerate() {
+                let row = unwrap_option_or_continue!(row.as_ref());
+                for (qx, chunk) in row.iter().enumerate() {
+                    let chunk = unwrap_option_or_continue!(chunk.as_ref());
+                    let cx = match qid & Self::QUARTERS_RIGHT != 0 {
+                        true => qx as isize,
+                        false => -(qx as isize) - 1,
+                    };
+                    let cy = match qid & Self::QUARTERS_BOTTOM != 0 {
+                        true => qy as isize,
+                        false => -(qy as isize) - 1,
+                    };
+                    let chunk_tl = Vec2isize::from([cx, cy]) * CHUNK_SIZE as isize;
+                    let chunk_br = chunk_tl + (CHUNK_SIZE as isize - 1);
+
+                    if let Some((tl, br)) = bounds {
+                        if chunk_tl.x >= tl.x
+                            && chunk_tl.y >= tl.y
+                            && chunk_br.x <= br.x
+                            && chunk_br.y <= br.y
+                        {
+                            continue;
+                        }
+                    }
+
+                    for (x, col) in chunk.iter().enumerate() {
+                        for (y, item) in col.iter().enumerate() {
+                            if !occupied(item) {
+                                continue;
+                            }
+                            let pos = chunk_tl + [x as isize, y as isize];
+                            bounds = Some(match bounds {
+                                None => (pos, pos),
+                                Some((tl, br)) => (
+                                    [tl.x.min(pos.x), tl.y.min(pos.y)].into(),
+                                    [br.x.max(pos.x), br.y.max(pos.y)].into(),
+                                ),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        bounds
+    }
+}
+
 // literally just copied from generated std code
 const fn div_floor_isize(lhs: isize, rhs: isize) -> isize {
     let d = lhs / rhs;
@@ -0,0 +1,22 @@
+module inv (
+    input wire a,
+    output wire y
+);
+
+    not g1 (y, a);
+endmodule
+
+module main (
+    input wire a,
+    input wire b,
+    output wire y,
+    output wire [1:0] bus
+);
+
+    wire n2;
+
+    and g2 (n2, a, b);
+    inv u3 (.a(n2), .y(y));
+    assign bus = {b, a};
+endmodule
+
@@ -0,0 +1,1517 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Deref,
+    rc::Rc,
+    str::FromStr,
+    sync::Arc,
+};
+
+use eframe::epaint::Color32;
+use emath::{pos2, vec2, Rect};
+use serde_intermediate::Intermediate;
+
+use crate::{
+    app::SimulationContext,
+    board::{CircuitBoard, CircuitDesignPin, Decoration, EditableCircuitBoard},
+    circuits::{
+        board::BoardPreview,
+        missing::MissingPreview,
+        pin::PinType,
+        props::{CircuitPropertyStore, RangedValue},
+        script::ScriptSource,
+        splitter::SplitterMode,
+        CircuitPreview, InternalPinDirection,
+    },
+    error::ErrorList,
+    routing::{self, RoutingOptions},
+    vector::{Vec2i, Vec2u},
+    wires::{TileWires, WireColors},
+    ArcString, Direction4, DynStaticStr,
+};
+
+use super::CircuitBoardData;
+
+// Logisim parts sit on a 10px grid and have shapes and pin layouts different from cuprous ones.
+// Wires are copied scaled, parts are placed between their scaled ports and routed to them.
+// Pins that can't be routed to their port are joined to its net with tunnels sharing the net name.
+
+/// Board tiles per Logisim grid cell
+const SCALE: i32 = 3;
+/// How far a part can be moved from its original position when it doesn't fit there
+const SEARCH_RADIUS: i32 = 16;
+
+type Point = (i32, i32);
+
+fn parse_pair(str: &str) -> Option<Point> {
+    let (x, y) = str.split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+fn parse_point(str: &str) -> Option<Point> {
+    parse_pair(str.trim().strip_prefix('(')?.strip_suffix(')')?)
+}
+
+/// Board tile of a Logisim point
+fn tile(point: Point) -> Vec2i {
+    Vec2i::from([
+        (point.0 * SCALE).div_euclid(10),
+        (point.1 * SCALE).div_euclid(10),
+    ])
+}
+
+/// Size of a tunnel facing `dir` and position of its pin
+fn tunnel_layout(dir: Direction4) -> (Vec2u, Vec2i) {
+    let (size, pin) = match dir {
+        Direction4::Left => ([2, 1], [0, 0]),
+        Direction4::Right => ([2, 1], [1, 0]),
+        Direction4::Up => ([1, 2], [0, 0]),
+        Direction4::Down => ([1, 2], [0, 1]),
+    };
+    (size.into(), pin.into())
+}
+
+fn parse_facing(str: Option<&str>, default: Direction4) -> Direction4 {
+    match str {
+        Some("east") => Direction4::Right,
+        Some("west") => Direction4::Left,
+        Some("north") => Direction4::Up,
+        Some("south") => Direction4::Down,
+        _ => default,
+    }
+}
+
+/// Rotates an offset given for an east-facing part
+fn rotate(offset: Point, facing: Direction4) -> Point {
+    let (x, y) = offset;
+    match facing {
+        Direction4::Right => (x, y),
+        Direction4::Down => (-y, x),
+        Direction4::Left => (-x, -y),
+        Direction4::Up => (y, -x),
+    }
+}
+
+/// Inverse of [`rotate`]
+fn unrotate(offset: Point, facing: Direction4) -> Point {
+    match facing {
+        Direction4::Up => rotate(offset, Direction4::Down),
+        Direction4::Down => rotate(offset, Direction4::Up),
+        _ => rotate(offset, facing),
+    }
+}
+
+struct LogisimPart {
+    /// Library description, like `#Gates`. None for subcircuits
+    lib: Option<String>,
+    name: String,
+    loc: Point,
+    attrs: BTreeMap<String, String>,
+}
+
+impl LogisimPart {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.get(name).map(|s| s.as_str())
+    }
+
+    fn attr_num<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.attr(name).and_then(|v| v.trim().parse().ok())
+    }
+
+    fn facing(&self, default: Direction4) -> Direction4 {
+        parse_facing(self.attr("facing"), default)
+    }
+
+    fn label(&self) -> &str {
+        self.attr("label").unwrap_or("")
+    }
+
+    /// Position of a port, given by its offset for an east-facing part
+    fn port(&self, offset: Point, facing: Direction4) -> Point {
+        let (x, y) = rotate(offset, facing);
+        (self.loc.0 + x, self.loc.1 + y)
+    }
+
+    fn describe(&self) -> String {
+        let lib = self.lib.as_deref().unwrap_or("").trim_start_matches('#');
+        format!("{lib}/{} at ({}, {})", self.name, self.loc.0, self.loc.1)
+    }
+}
+
+/// Custom subcircuit appearance, only its ports are used
+struct Appearance {
+    anchor: Point,
+    facing: Direction4,
+    /// Pin location in the subcircuit, port location in the appearance
+    ports: Vec<(Point, Point)>,
+}
+
+struct LogisimCircuit {
+    name: String,
+    wires: Vec<(Point, Point)>,
+    parts: Vec<LogisimPart>,
+    appear: Option<Appearance>,
+}
+
+struct LogisimProject {
+    main: Option<String>,
+    circuits: Vec<Rc<LogisimCircuit>>,
+}
+
+impl LogisimProject {
+    fn parse(xml: &str) -> Result<Self, roxmltree::Error> {
+        let doc = roxmltree::Document::parse(xml)?;
+        let root = doc.root_element();
+
+        let libs: HashMap<&str, &str> = root
+            .children()
+            .filter(|n| n.has_tag_name("lib"))
+            .filter_map(|n| Some((n.attribute("name")?, n.attribute("desc")?)))
+            .collect();
+
+        let main = root
+            .children()
+            .find(|n| n.has_tag_name("main"))
+            .and_then(|n| n.attribute("name"))
+            .map(String::from);
+
+        let circuits = root
+            .children()
+            .filter(|n| n.has_tag_name("circuit"))
+            .map(|circuit| {
+                let wires = circuit
+                    .children()
+                    .filter(|n| n.has_tag_name("wire"))
+                    .filter_map(|n| {
+                        let from = parse_point(n.attribute("from")?)?;
+                        let to = parse_point(n.attribute("to")?)?;
+                        Some((from, to))
+                    })
+                    .collect();
+
+                let parts = circuit
+                    .children()
+                    .filter(|n| n.has_tag_name("comp"))
+                    .filter_map(|n| {
+                        let lib = n
+                            .attribute("lib")
+                            .map(|lib| libs.get(lib).copied().unwrap_or(lib).to_owned());
+                        let attrs = n
+                            .children()
+                            .filter(|a| a.has_tag_name("a"))
+                            .filter_map(|a| {
+                                let value = a.attribute("val").or_else(|| a.text())?;
+                                Some((a.attribute("name")?.to_owned(), value.to_owned()))
+                            })
+                            .collect();
+                        Some(LogisimPart {
+                            lib,
+                            name: n.attribute("name")?.to_owned(),
+                            loc: parse_point(n.attribute("loc")?)?,
+                            attrs,
+                        })
+                    })
+                    .collect();
+
+                let appear = circuit
+                    .children()
+                    .find(|n| n.has_tag_name("appear"))
+                    .and_then(|appear| {
+                        let center = |n: roxmltree::Node| -> Option<Point> {
+                            let num = |name| n.attribute(name)?.trim().parse::<i32>().ok();
+                            Some((
+                                num("x")? + num("width")? / 2,
+                                num("y")? + num("height")? / 2,
+                            ))
+                        };
+                        let anchor = appear.children().find(|n| n.has_tag_name("circ-anchor"))?;
+                        let ports = appear
+                            .children()
+                            .filter(|n| n.has_tag_name("circ-port"))
+                            .filter_map(|n| Some((parse_pair(n.attribute("pin")?)?, center(n)?)))
+                            .collect();
+                        Some(Appearance {
+                            anchor: center(anchor)?,
+                            facing: parse_facing(anchor.attribute("facing"), Direction4::Right),
+                            ports,
+                        })
+                    });
+
+                Rc::new(LogisimCircuit {
+                    name: circuit.attribute("name").unwrap_or_default().to_owned(),
+                    wires,
+                    parts,
+                    appear,
+                })
+            })
+            .collect();
+
+        Ok(Self { main, circuits })
+    }
+}
+
+/// Union-find over Logisim points
+#[derive(Default)]
+struct Nets {
+    ids: HashMap<Point, usize>,
+    parents: Vec<usize>,
+}
+
+impl Nets {
+    fn node(&mut self, point: Point) -> usize {
+        let next = self.parents.len();
+        let id = *self.ids.entry(point).or_insert(next);
+        if id == next {
+            self.parents.push(id);
+        }
+        id
+    }
+
+    fn find(&mut self, mut id: usize) -> usize {
+        while self.parents[id] != id {
+            self.parents[id] = self.parents[self.parents[id]];
+            id = self.parents[id];
+        }
+        id
+    }
+
+    fn net(&mut self, point: Point) -> usize {
+        let id = self.node(point);
+        self.find(id)
+    }
+
+    fn join(&mut self, a: Point, b: Point) {
+        let a = self.net(a);
+        let b = self.net(b);
+        self.parents[a] = b;
+    }
+}
+
+/// Tunnel names of nets, Logisim tunnel labels when there are some
+#[derive(Default)]
+struct NetNames {
+    names: HashMap<usize, Arc<str>>,
+    used: HashSet<Arc<str>>,
+    next: usize,
+}
+
+impl NetNames {
+    fn set(&mut self, net: usize, name: &str) {
+        if !self.names.contains_key(&net) {
+            let name: Arc<str> = name.into();
+            self.used.insert(name.clone());
+            self.names.insert(net, name);
+        }
+    }
+
+    fn get(&mut self, net: usize) -> Arc<str> {
+        if let Some(name) = self.names.get(&net) {
+            return name.clone();
+        }
+        let name: Arc<str> = loop {
+            self.next += 1;
+            let name = format!("net{}", self.next);
+            if !self.used.contains(name.as_str()) {
+                break name.into();
+            }
+        };
+        self.used.insert(name.clone());
+        self.names.insert(net, name.clone());
+        name
+    }
+}
+
+/// Part converted to cuprous, not yet placed
+struct ConvertedPart {
+    preview: CircuitPreview,
+    imp: Option<Intermediate>,
+    loc: Point,
+    /// Logisim port locations and names of cuprous pins they map to
+    ports: Vec<(Point, DynStaticStr)>,
+    /// Set for pins of the circuit itself: output and facing
+    board_pin: Option<(bool, Direction4)>,
+}
+
+struct BoardPin {
+    loc: Point,
+    facing: Direction4,
+    output: bool,
+    id: DynStaticStr,
+    name: DynStaticStr,
+}
+
+struct BuiltCircuit {
+    board: Arc<CircuitBoard>,
+    pins: Vec<BoardPin>,
+}
+
+struct Importer<'a> {
+    ctx: &'a Arc<SimulationContext>,
+    circuits: Vec<Rc<LogisimCircuit>>,
+    built: HashMap<String, Rc<BuiltCircuit>>,
+    building: HashSet<String>,
+    boards: Vec<(String, Arc<CircuitBoard>)>,
+    offsets: Vec<Vec2i>,
+}
+
+impl<'a> Importer<'a> {
+    fn new(ctx: &'a Arc<SimulationContext>, circuits: Vec<Rc<LogisimCircuit>>) -> Self {
+        let mut offsets: Vec<Vec2i> = (-SEARCH_RADIUS..=SEARCH_RADIUS)
+            .flat_map(|y| (-SEARCH_RADIUS..=SEARCH_RADIUS).map(move |x| Vec2i::from([x, y])))
+            .collect();
+        offsets.sort_by_key(|v| v.x.abs() + v.y.abs());
+
+        Self {
+            ctx,
+            circuits,
+            built: HashMap::new(),
+            building: HashSet::new(),
+            boards: vec![],
+            offsets,
+        }
+    }
+
+    fn preview(
+        &self,
+        ty: &'static str,
+        setup: impl FnOnce(&CircuitPropertyStore),
+    ) -> Result<CircuitPreview, String> {
+        let preview = self
+            .ctx
+            .previews
+            .get(&DynStaticStr::Static(ty))
+            .ok_or_else(|| format!("circuit type {ty} isn't available"))?;
+        let imp = preview
+            .imp
+            .load_copy_data(
+                &Intermediate::Unit,
+                &Intermediate::Unit,
+                self.ctx,
+                &mut ErrorList::new(),
+            )
+            .ok_or_else(|| format!("circuit type {ty} can't be created"))?;
+        let props = imp.default_props();
+        setup(&props);
+        Ok(CircuitPreview::new(imp, props))
+    }
+
+    fn build(&mut self, name: &str, errors: &mut ErrorList) -> Result<Rc<BuiltCircuit>, String> {
+        if let Some(built) = self.built.get(name) {
+            return Ok(built.clone());
+        }
+        let circuit = self
+            .circuits
+            .iter()
+            .find(|c| c.name == name)
+            .cloned()
+            .ok_or_else(|| format!("circuit {name} isn't defined"))?;
+        if !self.building.insert(name.to_owned()) {
+            return Err(format!("circuit {name} contains itself"));
+        }
+
+        let built = {
+            let mut errors = errors.enter_context(|| format!("importing circuit {name}"));
+            Rc::new(self.build_circuit(&circuit, &mut errors))
+        };
+
+        self.building.remove(name);
+        self.built.insert(name.to_owned(), built.clone());
+        self.boards.push((name.to_owned(), built.board.clone()));
+        Ok(built)
+    }
+
+    fn build_circuit(&mut self, circuit: &LogisimCircuit, errors: &mut ErrorList) -> BuiltCircuit {
+        let mut nets = Nets::default();
+        let mut junctions = HashSet::new();
+        for (a, b) in circuit.wires.iter() {
+            nets.join(*a, *b);
+        }
+        // Wires ending in the middle of other wires are connected to them
+        for point in circuit.wires.iter().flat_map(|(a, b)| [*a, *b]) {
+            for (a, b) in circuit.wires.iter() {
+                let inside_x = point.0 > a.0.min(b.0) && point.0 < a.0.max(b.0);
+                let inside_y = point.1 > a.1.min(b.1) && point.1 < a.1.max(b.1);
+                if (inside_x && point.1 == a.1 && a.1 == b.1)
+                    || (inside_y && point.0 == a.0 && a.0 == b.0)
+                {
+                    nets.join(point, *a);
+                    junctions.insert(point);
+                }
+            }
+        }
+
+        let mut names = NetNames::default();
+        let mut tunnels: HashMap<&str, Point> = HashMap::new();
+        for part in circuit.parts.iter() {
+            if part.lib.as_deref() == Some("#Wiring") && part.name == "Tunnel" {
+                junctions.insert(part.loc);
+                let label = part.label();
+                if label.is_empty() {
+                    continue;
+                }
+                match tunnels.get(label) {
+                    Some(other) => nets.join(part.loc, *other),
+                    None => {
+                        tunnels.insert(label, part.loc);
+                    }
+                }
+            }
+        }
+        for (label, loc) in tunnels {
+            let net = nets.net(loc);
+            names.set(net, label);
+        }
+
+        let mut converted = vec![];
+        let mut unsupported = vec![];
+        for part in circuit.parts.iter().filter(|p| p.name != "Splitter") {
+            match self.convert(part, errors) {
+                Ok(Some(part)) => converted.push(part),
+                Ok(None) => {}
+                Err(reason) => unsupported.push((part, reason)),
+            }
+        }
+
+        let mut driven = HashSet::new();
+        for part in converted.iter() {
+            let desc = part.preview.describe();
+            for (loc, name) in part.ports.iter() {
+                let pin = desc.pins.iter().find(|p| p.name == *name);
+                if pin.is_some_and(|p| matches!(p.dir, InternalPinDirection::Outside)) {
+                    driven.insert(nets.net(*loc));
+                }
+            }
+        }
+
+        for part in circuit.parts.iter().filter(|p| p.name == "Splitter") {
+            match self.convert_splitter(part, &mut nets, &driven) {
+                Ok(part) => converted.push(part),
+                Err(reason) => unsupported.push((part, reason)),
+            }
+        }
+
+        // Ports of unsupported parts aren't known, so dangling wire ends next to them are used
+        let known: HashSet<Point> = converted
+            .iter()
+            .flat_map(|p| p.ports.iter().map(|p| p.0))
+            .chain(junctions)
+            .collect();
+        let mut ends: HashMap<Point, usize> = HashMap::new();
+        for point in circuit.wires.iter().flat_map(|(a, b)| [*a, *b]) {
+            *ends.entry(point).or_default() += 1;
+        }
+        let mut dangling: Vec<Point> = ends
+            .into_iter()
+            .filter(|(p, count)| *count == 1 && !known.contains(p))
+            .map(|(p, _)| p)
+            .collect();
+        dangling.sort();
+
+        let mut placeholder_ports: Vec<Vec<Point>> = vec![vec![]; unsupported.len()];
+        for point in dangling {
+            let nearest = unsupported
+                .iter()
+                .enumerate()
+                .map(|(i, (part, _))| {
+                    (
+                        i,
+                        (part.loc.0 - point.0).abs() + (part.loc.1 - point.1).abs(),
+                    )
+                })
+                .filter(|(_, dist)| *dist <= 80)
+                .min_by_key(|(_, dist)| *dist);
+            if let Some((i, _)) = nearest {
+                placeholder_ports[i].push(point);
+            }
+        }
+
+        for ((part, reason), ports) in unsupported.into_iter().zip(placeholder_ports) {
+            errors.push_error(format!(
+                "{}: {reason}, replaced with a placeholder",
+                part.describe()
+            ));
+            converted.push(Self::placeholder(part, ports));
+        }
+
+        let mut counts: HashMap<usize, usize> = HashMap::new();
+        for part in converted.iter() {
+            for (loc, _) in part.ports.iter() {
+                *counts.entry(nets.net(*loc)).or_default() += 1;
+            }
+        }
+
+        let board = Arc::new(CircuitBoard::new(self.ctx.clone(), &circuit.name));
+        let mut editor = EditableCircuitBoard::new_main(board.clone());
+        let mut pins = vec![];
+
+        for (a, b) in circuit.wires.iter() {
+            if let Some(part) = EditableCircuitBoard::calc_wire_part(Some(tile(*a)), Some(tile(*b)))
+            {
+                editor.place_wire_part(part, true, WireColors::default());
+            }
+        }
+        let wired = |editor: &EditableCircuitBoard, loc: Point| {
+            !matches!(editor.wires_at(tile(loc)), TileWires::None)
+        };
+        // Ports with a tunnel joining their wires to the rest of the net
+        let mut port_tunnels = HashSet::new();
+
+        for part in converted.iter() {
+            let desc = part.preview.describe();
+            let connected: Vec<_> = part
+                .ports
+                .iter()
+                .filter_map(|(loc, name)| {
+                    let net = nets.net(*loc);
+                    let pin = desc.pins.iter().find(|p| p.name == *name)?;
+                    let shared = counts.get(&net).copied().unwrap_or(0) > 1;
+                    (shared || wired(&editor, *loc)).then_some((
+                        pin.pos,
+                        pin.display_dir,
+                        *loc,
+                        net,
+                    ))
+                })
+                .collect();
+
+            let center = match part.ports.len() {
+                0 => tile(part.loc),
+                len => {
+                    let sum = part
+                        .ports
+                        .iter()
+                        .fold(Vec2i::default(), |sum, (loc, _)| sum + tile(*loc));
+                    sum / len as i32
+                }
+            };
+            let base = center - desc.size.convert(|v| v as i32 / 2);
+            let pos = self
+                .offsets
+                .iter()
+                .map(|o| base + *o)
+                .find(|pos| Self::fits(&editor, *pos, desc.size));
+
+            let pos = match pos {
+                Some(pos) => pos,
+                None => {
+                    errors.push_error(format!(
+                        "no room to place {}",
+                        part.preview.imp.display_name().deref()
+                    ));
+                    continue;
+                }
+            };
+
+            let id = editor.place_circuit(
+                pos,
+                true,
+                &part.preview,
+                None,
+                false,
+                part.imp.as_ref(),
+                &mut |_, _| {},
+            );
+            let id = crate::unwrap_option_or_continue!(id);
+
+            let part_pins: Vec<_> = connected.iter().map(|c| c.0).collect();
+            for (pin, display_dir, loc, net) in connected.iter().copied() {
+                let start = pos + pin.convert(|v| v as i32);
+                let port_wired = wired(&editor, loc);
+                let route = port_wired
+                    .then(|| routing::route(&editor, start, tile(loc), RoutingOptions::default()))
+                    .flatten();
+                if let Some(route) = route {
+                    for wire in route {
+                        editor.place_wire_part(wire, true, WireColors::default());
+                    }
+                    continue;
+                }
+
+                let name = names.get(net);
+                let stub = self.place_stub(
+                    &mut editor,
+                    pos,
+                    desc.size,
+                    pin,
+                    display_dir,
+                    &part_pins,
+                    &name,
+                );
+                let port_tunnel = !port_wired
+                    || !port_tunnels.insert(loc)
+                    || self.place_port_tunnel(&mut editor, tile(loc), &name);
+                if !(stub && port_tunnel) {
+                    errors.push_error(format!(
+                        "no room to connect {} to ({}, {})",
+                        part.preview.imp.display_name().deref(),
+                        loc.0,
+                        loc.1
+                    ));
+                }
+            }
+
+            if let Some((output, facing)) = part.board_pin {
+                let pin_id = board.pins.read().get_by_right(&id).cloned();
+                let name = board.circuits.read().get(id).and_then(|c| c.name());
+                if let Some(pin_id) = pin_id {
+                    pins.push(BoardPin {
+                        loc: part.loc,
+                        facing,
+                        output,
+                        id: pin_id.into(),
+                        name: name
+                            .map(DynStaticStr::Dynamic)
+                            .unwrap_or(DynStaticStr::Static("")),
+                    });
+                }
+            }
+        }
+
+        Self::generate_design(&board, &pins);
+        BuiltCircuit { board, pins }
+    }
+
+    /// Whether a circuit fits at `pos` without touching any wires
+    fn fits(editor: &EditableCircuitBoard, pos: Vec2i, size: Vec2u) -> bool {
+        editor.can_place_circuit_at(size, pos, None)
+            && tiles(pos, size).all(|t| wire_free(editor, t))
+    }
+
+    /// Connects a pin of a circuit placed at `pos` to a tunnel right outside of it
+    #[allow(clippy::too_many_arguments)]
+    fn place_stub(
+        &self,
+        editor: &mut EditableCircuitBoard,
+        pos: Vec2i,
+        size: Vec2u,
+        pin: Vec2u,
+        display_dir: Option<Direction4>,
+        pins: &[Vec2u],
+        name: &str,
+    ) -> bool {
+        let inside = |p: Vec2i| {
+            p.x >= pos.x
+                && p.y >= pos.y
+                && p.x < pos.x + size.x as i32
+                && p.y < pos.y + size.y as i32
+        };
+
+        let start = pos + pin.convert(|v| v as i32);
+        let dirs = display_dir
+            .into_iter()
+            .chain(Direction4::iter_all().filter(|d| Some(*d) != display_dir));
+        for dir in dirs {
+            let mut end = start;
+            while inside(end) {
+                end = dir.move_vector(end, 1);
+            }
+            let crosses = pins.iter().any(|other| {
+                let other = pos + other.convert(|v| v as i32);
+                other != start
+                    && other.x >= start.x.min(end.x)
+                    && other.x <= start.x.max(end.x)
+                    && other.y >= start.y.min(end.y)
+                    && other.y <= start.y.max(end.y)
+            });
+            if crosses {
+                continue;
+            }
+
+            let tunnel_dir = dir.inverted();
+            let (tunnel_size, tunnel_pin) = tunnel_layout(tunnel_dir);
+            let tunnel_pos = end - tunnel_pin;
+            if !Self::fits(editor, tunnel_pos, tunnel_size) {
+                continue;
+            }
+            if !self.place_tunnel(editor, tunnel_pos, tunnel_dir, name) {
+                return false;
+            }
+            if let Some(wire) = EditableCircuitBoard::calc_wire_part(Some(start), Some(end)) {
+                editor.place_wire_part(wire, true, WireColors::default());
+            }
+            return true;
+        }
+        false
+    }
+
+    /// Puts a tunnel on the end of wires connected to a Logisim port
+    fn place_port_tunnel(
+        &self,
+        editor: &mut EditableCircuitBoard,
+        port: Vec2i,
+        name: &str,
+    ) -> bool {
+        for dir in Direction4::iter_all() {
+            let (size, pin) = tunnel_layout(dir);
+            let pos = port - pin;
+            let free = editor.can_place_circuit_at(size, pos, None)
+                && tiles(pos, size).all(|t| t == port || wire_free(editor, t));
+            if free {
+                return self.place_tunnel(editor, pos, dir, name);
+            }
+        }
+        false
+    }
+
+    fn place_tunnel(
+        &self,
+        editor: &mut EditableCircuitBoard,
+        pos: Vec2i,
+        dir: Direction4,
+        name: &str,
+    ) -> bool {
+        let tunnel = self.preview("tunnel", |props| {
+            props.write("dir", |d: &mut Direction4| *d = dir);
+            set_string(props, "name", name);
+        });
+        let tunnel = match tunnel {
+            Ok(tunnel) => tunnel,
+            Err(_) => return false,
+        };
+        editor
+            .place_circuit(pos, true, &tunnel, None, false, None, &mut |_, _| {})
+            .is_some()
+    }
+
+    fn convert(
+        &mut self,
+        part: &LogisimPart,
+        errors: &mut ErrorList,
+    ) -> Result<Option<ConvertedPart>, String> {
+        let single = |preview: CircuitPreview, pin: &'static str| ConvertedPart {
+            preview,
+            imp: None,
+            loc: part.loc,
+            ports: vec![(part.loc, pin.into())],
+            board_pin: None,
+        };
+
+        let lib = match &part.lib {
+            Some(lib) => lib.as_str(),
+            None => return self.convert_subcircuit(part, errors).map(Some),
+        };
+
+        let converted = match (lib, part.name.as_str()) {
+            ("#Base", "Text") => return Ok(None),
+            ("#Wiring", "Tunnel") => {
+                if part.label().is_empty() {
+                    return Ok(None);
+                }
+                let facing = part.facing(Direction4::Left);
+                let preview = self.preview("tunnel", |props| {
+                    props.write("dir", |d: &mut Direction4| *d = facing);
+                    set_string(props, "name", part.label());
+                })?;
+                single(preview, "pin")
+            }
+            ("#Gates", "NOT Gate") => {
+                if part.attr_num::<u32>("width").unwrap_or(1) > 1 {
+                    return Err("multi-bit gates aren't supported".into());
+                }
+                let facing = part.facing(Direction4::Right);
+                let size = part.attr_num::<i32>("size").unwrap_or(30);
+                let preview = self.preview("not", |props| {
+                    props.write("dir", |d: &mut Direction4| *d = facing);
+                })?;
+                ConvertedPart {
+                    preview,
+                    imp: None,
+                    loc: part.loc,
+                    ports: vec![
+                        (part.port((-size, 0), facing), "in".into()),
+                        (part.loc, "out".into()),
+                    ],
+                    board_pin: None,
+                }
+            }
+            ("#Gates", name) => {
+                let ty = match name {
+                    "AND Gate" => "and",
+                    "OR Gate" => "or",
+                    "NAND Gate" => "nand",
+                    "NOR Gate" => "nor",
+                    "XOR Gate" => "xor",
+                    "XNOR Gate" => "xnor",
+                    _ => return Err("part isn't supported".into()),
+                };
+                if part.attr_num::<u32>("width").unwrap_or(1) > 1 {
+                    return Err("multi-bit gates aren't supported".into());
+                }
+                let inputs = part.attr_num::<i32>("inputs").unwrap_or(2).clamp(2, 64);
+                if (0..inputs).any(|i| part.attr(&format!("negate{i}")) == Some("true")) {
+                    return Err("negated inputs aren't supported".into());
+                }
+                let facing = part.facing(Direction4::Right);
+                let preview = self.preview(ty, |props| {
+                    props.write("dir", |d: &mut Direction4| *d = facing);
+                    props.write("inputs", |v: &mut RangedValue<u32>| v.set(inputs as u32));
+                })?;
+                let mut ports: Vec<(Point, DynStaticStr)> = gate_input_offsets(part, inputs)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, offset)| (part.port(offset, facing), format!("in_{i}").into()))
+                    .collect();
+                ports.push((part.loc, "out".into()));
+                ConvertedPart {
+                    preview,
+                    imp: None,
+                    loc: part.loc,
+                    ports,
+                    board_pin: None,
+                }
+            }
+            ("#Wiring", "Pin") => {
+                let width = part.attr_num::<u32>("width").unwrap_or(1);
+                if width > 1 {
+                    errors.push_error(format!(
+                        "{}: {width}-bit pin is imported as a single-bit one",
+                        part.describe()
+                    ));
+                }
+                let facing = part.facing(Direction4::Right);
+                let output =
+                    part.attr("output") == Some("true") || part.attr("type") == Some("output");
+                let preview = self.preview("pin", |props| {
+                    props.write("dir", |d: &mut Direction4| *d = facing);
+                    props.write("ty", |t: &mut PinType| {
+                        *t = if output { PinType::Cipo } else { PinType::Pico }
+                    });
+                    set_string(props, "name", part.label());
+                })?;
+                ConvertedPart {
+                    board_pin: Some((output, facing)),
+                    ..single(preview, "pin")
+                }
+            }
+            ("#Wiring", "Constant" | "Power" | "Ground") => {
+                let width = part.attr_num::<u32>("width").unwrap_or(1).clamp(1, 64);
+                let mask = u64::MAX >> (64 - width);
+                let value = match part.name.as_str() {
+                    "Power" => mask,
+                    "Ground" => 0,
+                    _ => part.attr("value").and_then(parse_value).unwrap_or(1) & mask,
+                };
+                let bits = if width > 1 {
+                    format!(" {width}")
+                } else {
+                    String::new()
+                };
+                let script = format!("size 2 1\noutput out 1 0{bits}\nout = {value:#x}\n");
+                let preview = self.preview("script", |props| {
+                    props.write("script", |s: &mut ScriptSource| s.0 = script);
+                })?;
+                single(preview, "out")
+            }
+            ("#Wiring", "Clock") => {
+                let facing = part.facing(Direction4::Right);
+                let preview = self.preview("clock", |props| {
+                    props.write("dir", |d: &mut Direction4| *d = facing);
+                })?;
+                single(preview, "out")
+            }
+            ("#Wiring", "Probe") => single(self.preview("probe", |_| {})?, "in"),
+            ("#Wiring", "Pull Resistor") => {
+                if part.attr("pull").is_some_and(|p| p != "0") {
+                    return Err("only pulling to 0 is supported".into());
+                }
+                single(self.preview("pullup", |_| {})?, "pin")
+            }
+            ("#I/O", "Button") => {
+                let facing = part.facing(Direction4::Right);
+                let preview = self.preview("button", |props| {
+                    props.write("dir", |d: &mut Direction4| *d = facing);
+                })?;
+                single(preview, "out")
+            }
+            ("#I/O", "LED") => {
+                let facing = part.facing(Direction4::Left);
+                let color = part.attr("color").and_then(parse_color);
+                let preview = self.preview("led", |props| {
+                    props.write("dir", |d: &mut Direction4| *d = facing);
+                    if let Some(color) = color {
+                        props.write("on_color", |c: &mut Color32| *c = color);
+                    }
+                })?;
+                single(preview, "in")
+            }
+            _ => return Err("part isn't supported".into()),
+        };
+        Ok(Some(converted))
+    }
+
+    fn convert_subcircuit(
+        &mut self,
+        part: &LogisimPart,
+        errors: &mut ErrorList,
+    ) -> Result<ConvertedPart, String> {
+        let built = self.build(&part.name, errors)?;
+        let circuit = self
+            .circuits
+            .iter()
+            .find(|c| c.name == part.name)
+            .cloned()
+            .ok_or_else(|| format!("circuit {} isn't defined", part.name))?;
+
+        let facing = part.facing(Direction4::Right);
+        let ports = subcircuit_port_offsets(&circuit, &built)
+            .into_iter()
+            .map(|(offset, id)| (part.port(offset, facing), id))
+            .collect();
+
+        let preview =
+            CircuitPreview::from_impl(Box::new(BoardPreview::new_from_board(built.board.clone())));
+        Ok(ConvertedPart {
+            preview,
+            imp: None,
+            loc: part.loc,
+            ports,
+            board_pin: None,
+        })
+    }
+
+    fn convert_splitter(
+        &self,
+        part: &LogisimPart,
+        nets: &mut Nets,
+        driven: &HashSet<usize>,
+    ) -> Result<ConvertedPart, String> {
+        let facing = part.facing(Direction4::Right);
+        let fanout = part.attr_num::<usize>("fanout").unwrap_or(2).clamp(1, 64);
+        let incoming = part.attr_num::<usize>("incoming").unwrap_or(2).clamp(1, 64);
+        let spacing = part.attr_num::<i32>("spacing").unwrap_or(1).max(1);
+        let justify = match part.attr("appear") {
+            Some("center" | "legacy") => 0,
+            Some("right") => 1,
+            _ => -1,
+        };
+
+        // Same as Logisim default bit distribution
+        let per_end = incoming / fanout;
+        let extra = incoming % fanout;
+        let default_end = |bit: usize| {
+            if fanout >= incoming {
+                bit
+            } else if bit < extra * (per_end + 1) {
+                bit / (per_end + 1)
+            } else {
+                extra + (bit - extra * (per_end + 1)) / per_end
+            }
+        };
+        let ends: Vec<Option<usize>> = (0..incoming)
+            .map(|bit| match part.attr(&format!("bit{bit}")) {
+                Some("none") => None,
+                Some(end) => end.trim().parse().ok().filter(|e| *e < fanout),
+                None => Some(default_end(bit)),
+            })
+            .collect();
+
+        let f = fanout as i32;
+        let (end0, step) = match facing {
+            Direction4::Up | Direction4::Down => {
+                let m = if facing == Direction4::Up { 1 } else { -1 };
+                let x = if justify == 0 {
+                    10 * ((f + 1) / 2 - 1)
+                } else if m * justify < 0 {
+                    -10
+                } else {
+                    10 * f
+                };
+                ((x * spacing, -m * 20), (-10 * spacing, 0))
+            }
+            Direction4::Left | Direction4::Right => {
+                let m = if facing == Direction4::Left { -1 } else { 1 };
+                let y = if justify == 0 {
+                    -10 * (f / 2)
+                } else if m * justify > 0 {
+                    10
+                } else {
+                    -10 * f
+                };
+                ((m * 20, y * spacing), (0, 10 * spacing))
+            }
+        };
+
+        let mut fields = vec![];
+        let mut ports: Vec<(Point, DynStaticStr)> = vec![(part.loc, "bundle".into())];
+        for end in 0..fanout {
+            let bits: Vec<usize> = (0..incoming).filter(|b| ends[*b] == Some(end)).collect();
+            if bits.is_empty() {
+                continue;
+            }
+            let i = end as i32;
+            let loc = (
+                part.loc.0 + end0.0 + step.0 * i,
+                part.loc.1 + end0.1 + step.1 * i,
+            );
+            ports.push((loc, format!("field_{}", fields.len()).into()));
+            fields.push(format_field(&bits));
+        }
+
+        let mode = if driven.contains(&nets.net(part.loc)) {
+            SplitterMode::Split
+        } else if ports[1..]
+            .iter()
+            .any(|(loc, _)| driven.contains(&nets.net(*loc)))
+        {
+            SplitterMode::Merge
+        } else {
+            SplitterMode::Split
+        };
+
+        let dir = facing;
+        let fields = fields.join("; ");
+        let preview = self.preview("splitter", |props| {
+            props.write("dir", |d: &mut Direction4| *d = dir);
+            props.write("mode", |m: &mut SplitterMode| *m = mode);
+            set_string(props, "fields", &fields);
+        })?;
+        Ok(ConvertedPart {
+            preview,
+            imp: None,
+            loc: part.loc,
+            ports,
+            board_pin: None,
+        })
+    }
+
+    fn placeholder(part: &LogisimPart, ports: Vec<Point>) -> ConvertedPart {
+        let min = ports
+            .iter()
+            .fold(part.loc, |min, p| (min.0.min(p.0), min.1.min(p.1)));
+        let pins = ports
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let pos = [((p.0 - min.0) / 10) as u32, ((p.1 - min.1) / 10) as u32];
+                (format!("p{i}").into(), pos.into())
+            })
+            .collect();
+        let lib = part.lib.as_deref().unwrap_or("").trim_start_matches('#');
+        let ty = format!("logisim:{lib}/{}", part.name);
+        let preview =
+            CircuitPreview::from_impl(Box::new(MissingPreview::new_placeholder(ty.into(), pins)));
+        ConvertedPart {
+            preview,
+            imp: serde_intermediate::to_intermediate(&part.attrs).ok(),
+            loc: part.loc,
+            ports: ports
+                .into_iter()
+                .enumerate()
+                .map(|(i, p)| (p, format!("p{i}").into()))
+                .collect(),
+            board_pin: None,
+        }
+    }
+
+    /// Inputs on the left, outputs on the right, both sorted the same way as in Logisim
+    fn generate_design(board: &CircuitBoard, pins: &[BoardPin]) {
+        let sorted = |output: bool| {
+            let mut pins: Vec<_> = pins.iter().filter(|p| p.output == output).collect();
+            pins.sort_by_key(|p| (p.loc.1, p.loc.0));
+            pins
+        };
+        let inputs = sorted(false);
+        let outputs = sorted(true);
+
+        let width = 4;
+        let height = inputs.len().max(outputs.len()).max(1) as u32 * 2 + 1;
+
+        let mut designs = board.designs.write();
+        let design = designs.current_mut();
+        design.size = [width, height].into();
+
+        let inputs = inputs.iter().enumerate().map(|(i, p)| CircuitDesignPin {
+            id: p.id.clone(),
+            pos: [0, i as u32 * 2 + 1].into(),
+            dir: InternalPinDirection::Inside,
+            display_dir: Some(Direction4::Left),
+            display_name: p.name.clone(),
+        });
+        let outputs = outputs.iter().enumerate().map(|(i, p)| CircuitDesignPin {
+            id: p.id.clone(),
+            pos: [width - 1, i as u32 * 2 + 1].into(),
+            dir: InternalPinDirection::Outside,
+            display_dir: Some(Direction4::Right),
+            display_name: p.name.clone(),
+        });
+        design.pins = inputs.chain(outputs).collect();
+
+        for decoration in design.decorations.iter_mut() {
+            match decoration {
+                Decoration::Rect { rect, .. } => {
+                    *rect = Rect::from_min_size(pos2(0.0, 0.0), vec2(width as f32, height as f32))
+                }
+            }
+        }
+    }
+}
+
+fn tiles(pos: Vec2i, size: Vec2u) -> impl Iterator<Item = Vec2i> {
+    (0..size.y as i32).flat_map(move |y| (0..size.x as i32).map(move |x| pos + [x, y]))
+}
+
+fn wire_free(editor: &EditableCircuitBoard, pos: Vec2i) -> bool {
+    let node = editor.wire_nodes.get(pos.convert(|v| v as isize));
+    node.copied().unwrap_or_default().is_empty()
+}
+
+fn set_string(props: &CircuitPropertyStore, id: &str, value: &str) {
+    props.write(id, |s: &mut ArcString| {
+        let string = s.get_mut();
+        string.clear();
+        string.push_str(value);
+    });
+}
+
+fn parse_value(str: &str) -> Option<u64> {
+    let str = str.trim();
+    match str.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => str.parse().ok(),
+    }
+}
+
+fn parse_color(str: &str) -> Option<Color32> {
+    let hex = u32::from_str_radix(str.trim().strip_prefix('#')?, 16).ok()?;
+    Some(Color32::from_rgb(
+        (hex >> 16) as u8,
+        (hex >> 8) as u8,
+        hex as u8,
+    ))
+}
+
+/// Field list for the splitter, bundle bits are sorted and grouped into ranges
+fn format_field(bits: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = vec![];
+    for bit in bits.iter().rev().copied() {
+        match ranges.last_mut() {
+            Some((_, low)) if *low == bit + 1 => *low = bit,
+            _ => ranges.push((bit, bit)),
+        }
+    }
+    let ranges: Vec<_> = ranges
+        .into_iter()
+        .map(|(high, low)| {
+            if high == low {
+                high.to_string()
+            } else {
+                format!("{high}:{low}")
+            }
+        })
+        .collect();
+    format!("[{}]", ranges.join(", "))
+}
+
+/// Input offsets of an east-facing Logisim gate
+fn gate_input_offsets(part: &LogisimPart, inputs: i32) -> Vec<Point> {
+    let size = part.attr_num::<i32>("size").unwrap_or(50);
+    let bonus = if matches!(part.name.as_str(), "XOR Gate" | "XNOR Gate") {
+        10
+    } else {
+        0
+    };
+    let negated = if matches!(part.name.as_str(), "NAND Gate" | "NOR Gate" | "XNOR Gate") {
+        10
+    } else {
+        0
+    };
+    let axis = size + bonus + negated;
+
+    let (skip_start, dist, lower_even) = if inputs <= 3 {
+        if size < 40 {
+            (-5, 10, 10)
+        } else if size < 60 || inputs <= 2 {
+            (-10, 20, 20)
+        } else {
+            (-15, 30, 30)
+        }
+    } else if inputs == 4 && size >= 60 {
+        (-5, 20, 0)
+    } else {
+        (-5, 10, 10)
+    };
+
+    (0..inputs)
+        .map(|i| {
+            let dy = if inputs % 2 == 1 {
+                skip_start * (inputs - 1) + dist * i
+            } else if i >= inputs / 2 {
+                skip_start * inputs + dist * i + lower_even
+            } else {
+                skip_start * inputs + dist * i
+            };
+            (-axis, dy)
+        })
+        .collect()
+}
+
+/// Port offsets of an east-facing subcircuit instance with the ids of board pins they map to
+fn subcircuit_port_offsets(
+    circuit: &LogisimCircuit,
+    built: &BuiltCircuit,
+) -> Vec<(Point, DynStaticStr)> {
+    if let Some(appear) = &circuit.appear {
+        return appear
+            .ports
+            .iter()
+            .filter_map(|(pin, port)| {
+                let pin = built.pins.iter().find(|p| p.loc == *pin)?;
+                let offset = (port.0 - appear.anchor.0, port.1 - appear.anchor.1);
+                Some((unrotate(offset, appear.facing), pin.id.clone()))
+            })
+            .collect();
+    }
+
+    // Logisim default appearance: pins are put on the edge opposite to where they face
+    let edge = |edge: Direction4| {
+        let mut pins: Vec<_> = built
+            .pins
+            .iter()
+            .filter(|p| p.facing.inverted() == edge)
+            .collect();
+        if edge.is_horizontal() {
+            pins.sort_by_key(|p| (p.loc.1, p.loc.0));
+        } else {
+            pins.sort_by_key(|p| (p.loc.0, p.loc.1));
+        }
+        pins
+    };
+    let (north, south) = (edge(Direction4::Up), edge(Direction4::Down));
+    let (west, east) = (edge(Direction4::Left), edge(Direction4::Right));
+
+    let dimension = |this: usize, others: usize| -> i32 {
+        if this < 3 {
+            30
+        } else if others == 0 {
+            10 * this as i32
+        } else {
+            10 * this as i32 + 10
+        }
+    };
+    let offset = |facing: usize, opposite: usize, others: usize| -> i32 {
+        let this = facing.max(opposite);
+        let max = match this {
+            0 | 1 if others == 0 => 15,
+            0..=2 => 10,
+            _ if others == 0 => 5,
+            _ => 10,
+        };
+        max + 10 * ((this - facing) / 2) as i32
+    };
+
+    let max_vert = north.len().max(south.len());
+    let max_horz = west.len().max(east.len());
+    let offs_north = offset(north.len(), south.len(), max_horz);
+    let offs_south = offset(south.len(), north.len(), max_horz);
+    let offs_east = offset(east.len(), west.len(), max_vert);
+    let offs_west = offset(west.len(), east.len(), max_vert);
+    let width = dimension(max_vert, max_horz);
+    let height = dimension(max_horz, max_vert);
+
+    let anchor = if !east.is_empty() {
+        (width, offs_east)
+    } else if !north.is_empty() {
+        (offs_north, 0)
+    } else if !west.is_empty() {
+        (0, offs_west)
+    } else if !south.is_empty() {
+        (offs_south, height)
+    } else {
+        (0, 0)
+    };
+
+    let mut ports = vec![];
+    let mut place = |pins: Vec<&BoardPin>, start: Point, step: Point| {
+        for (i, pin) in pins.into_iter().enumerate() {
+            let i = i as i32;
+            let pos = (start.0 + step.0 * i, start.1 + step.1 * i);
+            ports.push(((pos.0 - anchor.0, pos.1 - anchor.1), pin.id.clone()));
+        }
+    };
+    place(west, (0, offs_west), (0, 10));
+    place(east, (width, offs_east), (0, 10));
+    place(north, (offs_north, 0), (10, 0));
+    place(south, (offs_south, height), (10, 0));
+    ports
+}
+
+/// Converts a Logisim project into boards, the main circuit being first.<br>
+/// Wires are kept, parts are routed to them or connected through tunnels where that's not possible.
+pub fn import(
+    xml: &str,
+    ctx: &Arc<SimulationContext>,
+    errors: &mut ErrorList,
+) -> Vec<CircuitBoardData> {
+    let project = match LogisimProject::parse(xml) {
+        Ok(project) => project,
+        Err(e) => {
+            errors.push_error(e);
+            return vec![];
+        }
+    };
+
+    let main = project
+        .main
+        .clone()
+        .or_else(|| project.circuits.first().map(|c| c.name.clone()));
+    let names: Vec<String> = project.circuits.iter().map(|c| c.name.clone()).collect();
+
+    let mut importer = Importer::new(ctx, project.circuits);
+    for name in main.iter().chain(names.iter()) {
+        if let Err(e) = importer.build(name, errors) {
+            errors.push_error(e);
+        }
+    }
+
+    importer
+        .boards
+        .sort_by_key(|(name, _)| Some(name) != main.as_ref());
+
+    let boards: Vec<_> = importer
+        .boards
+        .iter()
+        .map(|(_, board)| {
+            let mut data = board.save(true);
+            // Imported boards start with fresh simulation state
+            data.states.clear();
+            data
+        })
+        .collect();
+    for (_, board) in importer.boards.iter() {
+        board.destroy();
+    }
+    boards
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        app::SimulationContext,
+        circuits::script::{ScriptProgram, ScriptSource},
+        error::ErrorList,
+        io::{CircuitBoardData, CircuitData},
+    };
+
+    const BASIC: &str = include_str!("corpus/logisim_basic.circ");
+
+    fn circuits<'a>(board: &'a CircuitBoardData, ty: &str) -> Vec<&'a CircuitData> {
+        board
+            .circuits
+            .iter()
+            .flatten()
+            .filter(|c| &*c.ty == ty)
+            .collect()
+    }
+
+    fn wire(circuit: &CircuitData, pin: &str) -> Option<usize> {
+        circuit
+            .pin_wires
+            .iter()
+            .find(|(name, _)| &**name == pin)
+            .map(|(_, wire)| *wire)
+    }
+
+    #[test]
+    fn basic_project_imports() {
+        let ctx = SimulationContext::with_builtin_previews();
+        let mut errors = ErrorList::new();
+        let boards = super::import(BASIC, &ctx, &mut errors);
+
+        assert!(errors.is_empty());
+        let names: Vec<_> = boards.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["main", "inv"]);
+
+        let main = &boards[0];
+        assert!(circuits(main, "tunnel").is_empty());
+        let [and] = circuits(main, "and")[..] else {
+            panic!("main should have one and gate");
+        };
+        let [constant] = circuits(main, "script")[..] else {
+            panic!("main should have one constant");
+        };
+        let [instance] = circuits(main, "board")[..] else {
+            panic!("main should have one inv instance");
+        };
+        let pins: Vec<_> = circuits(main, "pin")
+            .iter()
+            .map(|p| wire(p, "pin"))
+            .collect();
+        assert_eq!(pins.len(), 2);
+
+        let inputs = [wire(and, "in_0"), wire(and, "in_1")];
+        assert!(inputs.iter().all(Option::is_some));
+        assert_ne!(inputs[0], inputs[1]);
+        assert!(inputs.contains(&wire(constant, "out")));
+        let input_pin = pins.iter().position(|p| inputs.contains(p));
+        let output_pin = match input_pin {
+            Some(0) => pins[1],
+            Some(_) => pins[0],
+            None => panic!("input pin isn't wired to the and gate"),
+        };
+
+        let mut instance_wires: Vec<_> = instance.pin_wires.iter().map(|(_, w)| Some(*w)).collect();
+        let mut expected = vec![wire(and, "out"), output_pin];
+        instance_wires.sort();
+        expected.sort();
+        assert_eq!(instance_wires, expected);
+
+        let inv = &boards[1];
+        assert!(circuits(inv, "tunnel").is_empty());
+        let [not] = circuits(inv, "not")[..] else {
+            panic!("inv should have one not gate");
+        };
+        let mut pins: Vec<_> = circuits(inv, "pin")
+            .iter()
+            .map(|p| wire(p, "pin"))
+            .collect();
+        let mut expected = vec![wire(not, "in"), wire(not, "out")];
+        pins.sort();
+        expected.sort();
+        assert!(expected.iter().all(Option::is_some));
+        assert_eq!(pins, expected);
+    }
+
+    #[test]
+    fn wide_constants_become_parsable_scripts() {
+        let xml = r##"<project>
+            <lib desc="#Wiring" name="0"/>
+            <circuit name="main">
+                <comp lib="0" loc="(100,100)" name="Constant">
+                    <a name="width" val="64"/>
+                    <a name="value" val="0xffffffffffffffff"/>
+                </comp>
+            </circuit>
+        </project>"##;
+        let ctx = SimulationContext::with_builtin_previews();
+        let mut errors = ErrorList::new();
+        let boards = super::import(xml, &ctx, &mut errors);
+
+        assert!(errors.is_empty());
+        let [constant] = circuits(&boards[0], "script")[..] else {
+            panic!("main should have one constant");
+        };
+        let props = ctx.previews.get("script").unwrap().props.clone();
+        props.load(&constant.props);
+        let script = props.read_clone::<ScriptSource>("script").unwrap();
+        assert!(script.0.contains("out = 0xffffffffffffffff"));
+        ScriptProgram::parse(&script.0).unwrap();
+    }
+
+    #[test]
+    fn negated_gate_inputs_are_reported() {
+        let xml = r##"<project>
+            <lib desc="#Gates" name="1"/>
+            <circuit name="main">
+                <comp lib="1" loc="(100,100)" name="AND Gate">
+                    <a name="negate0" val="true"/>
+                </comp>
+            </circuit>
+        </project>"##;
+        let ctx = SimulationContext::with_builtin_previews();
+        let mut errors = ErrorList::new();
+        let boards = super::import(xml, &ctx, &mut errors);
+
+        assert!(!errors.is_empty());
+        assert!(circuits(&boards[0], "and").is_empty());
+        assert_eq!(circuits(&boards[0], "logisim:Gates/AND Gate").len(), 1);
+    }
+}
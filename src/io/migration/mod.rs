@@ -0,0 +1,167 @@
+//! Save format versioning.
+//!
+//! Every [`SaveStateData`] carries the format version it was written with, saves without one are version 0.
+//! Loading runs the save through [`MIGRATIONS`], each converting version N to N+1,
+//! until it reaches [`SAVE_VERSION`].
+//!
+//! Migrations work on the typed save data and rewrite its untyped [`Intermediate`] parts
+//! (circuit implementation data, properties and internal states).
+//! Whole saves can't be round-tripped through [`Intermediate`] because RON loses enum variants doing so.
+
+use serde::Serialize;
+use serde_intermediate::Intermediate;
+
+use crate::{
+    error::{ErrorList, ResultReport},
+    vector::Vec2i,
+};
+
+use super::{CircuitBoardData, SaveStateData};
+
+/// Current save format version
+pub const SAVE_VERSION: u32 = 1;
+
+type Migration = fn(&mut CircuitBoardData, &mut ErrorList);
+
+/// Migration at index N converts board data from version N to N+1
+const MIGRATIONS: [Migration; SAVE_VERSION as usize] = [v0_pin_ids];
+
+/// Parses a RON savestate and migrates it to [`SAVE_VERSION`]
+pub fn load_save(string: &str, errors: &mut ErrorList) -> Option<SaveStateData> {
+    let mut state = ron::from_str::<SaveStateData>(string).report_error(errors)?;
+    migrate_boards(state.version, &mut state.boards, errors);
+    state.version = SAVE_VERSION;
+    Some(state)
+}
+
+/// Migrates boards saved with format `version` to [`SAVE_VERSION`]
+pub fn migrate_boards(version: u32, boards: &mut [CircuitBoardData], errors: &mut ErrorList) {
+    if version > SAVE_VERSION {
+        errors.push_error(format!(
+            "Save format version {version} is newer than supported version {SAVE_VERSION}, some data may not load"
+        ));
+        return;
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let mut errors = errors.enter_context(|| format!("migrating save from version {from}"));
+        for board in boards.iter_mut() {
+            migration(board, &mut errors);
+        }
+    }
+}
+
+/// Pins used to be saved without data and got their ids from their position
+fn v0_pin_ids(board: &mut CircuitBoardData, errors: &mut ErrorList) {
+    #[derive(Serialize)]
+    struct PinIdModel {
+        id: Option<String>,
+    }
+
+    for circuit in board.circuits.iter_mut().flatten() {
+        if &*circuit.ty != crate::circuits::pin::TYPEID
+            || !matches!(circuit.imp, Intermediate::Unit)
+        {
+            continue;
+        }
+
+        let model = PinIdModel {
+            id: Some(back_compat_pin_id(circuit.pos)),
+        };
+        if let Some(imp) = serde_intermediate::to_intermediate(&model).report_error(errors) {
+            circuit.imp = imp;
+        }
+    }
+}
+
+fn back_compat_pin_id(pos: Vec2i) -> String {
+    let hex_x = format!("{}{:x}", if pos.x < 0 { "-" } else { "" }, pos.x.abs());
+    let hex_y = format!("{}{:x}", if pos.y < 0 { "-" } else { "" }, pos.y.abs());
+
+    format!("{:x}:{:x}:{}{}", hex_x.len(), hex_y.len(), hex_x, hex_y)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_intermediate::Intermediate;
+
+    use crate::{error::ErrorList, io::SaveStateData};
+
+    use super::SAVE_VERSION;
+
+    /// Saves written by older versions, all of them must keep loading
+    const CORPUS: &[(&str, &str)] = &[
+        ("v0_gates", include_str!("corpus/v0_gates.ron")),
+        ("v0_pin_design", include_str!("corpus/v0_pin_design.ron")),
+        ("v1_nested", include_str!("corpus/v1_nested.ron")),
+    ];
+
+    fn load(name: &str, string: &str) -> SaveStateData {
+        let mut errors = ErrorList::new();
+        let state = super::load_save(string, &mut errors);
+        assert!(errors.is_empty(), "{name} loaded with errors");
+        state.unwrap_or_else(|| panic!("{name} failed to load"))
+    }
+
+    fn pin_ids(state: &SaveStateData) -> Vec<Option<String>> {
+        #[derive(serde::Deserialize)]
+        struct PinIdModel {
+            id: Option<String>,
+        }
+
+        state
+            .boards
+            .iter()
+            .flat_map(|b| b.circuits.iter().flatten())
+            .filter(|c| &*c.ty == crate::circuits::pin::TYPEID)
+            .map(|c| {
+                serde_intermediate::from_intermediate::<PinIdModel>(&c.imp)
+                    .ok()
+                    .and_then(|m| m.id)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn corpus_loads() {
+        for (name, string) in CORPUS {
+            let state = load(name, string);
+            assert_eq!(state.version, SAVE_VERSION, "{name} was not migrated");
+            assert!(
+                pin_ids(&state).iter().all(Option::is_some),
+                "{name} has pins without ids"
+            );
+        }
+    }
+
+    #[test]
+    fn v0_pin_ids_match_designs() {
+        let (name, string) = CORPUS[1];
+        let state = load(name, string);
+        let design_ids: Vec<_> = state.boards[0].designs.designs[0]
+            .as_ref()
+            .map(|d| d.pins.iter().map(|p| Some(String::from(&*p.id))).collect())
+            .unwrap_or_default();
+
+        assert_eq!(pin_ids(&state), design_ids);
+    }
+
+    #[test]
+    fn current_version_is_untouched() {
+        let (name, string) = CORPUS[2];
+        let state = load(name, string);
+        let pin = state.boards[1].circuits[0].as_ref().map(|c| &c.imp);
+
+        assert!(matches!(pin, Some(Intermediate::Map(_))));
+        assert_eq!(pin_ids(&state), vec![Some("in".to_owned())]);
+    }
+
+    #[test]
+    fn newer_version_reports_error() {
+        let mut errors = ErrorList::new();
+        let state = super::load_save("(version: 999, boards: [])", &mut errors);
+
+        assert!(state.is_some());
+        assert!(!errors.is_empty());
+    }
+}
@@ -10,6 +10,15 @@ use crate::{
     vector::{Vec2i, Vec2u}, DynStaticStr, Direction2, board::{Decoration, CircuitDesignPin, CircuitDesignStorage, CircuitDesign, CircuitDesignControl}, random_u128, wires::WireColors,
 };
 
+pub mod logisim;
+pub mod migration;
+pub mod netlist;
+pub mod render;
+pub mod verilog;
+
+#[cfg(test)]
+mod test_boards;
+
 #[cfg(all(not(web_sys_unstable_apis), feature = "wasm"))]
 pub static GLOBAL_CLIPBOARD: crate::Mutex<Option<crate::io::CopyPasteData>> = crate::Mutex::new(None); 
 
@@ -125,9 +134,20 @@ pub struct CircuitBoardData {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SaveStateData {
+    #[serde(default)]
+    pub version: u32,
     pub boards: Vec<CircuitBoardData>
 }
 
+impl SaveStateData {
+    pub fn new(boards: Vec<CircuitBoardData>) -> Self {
+        Self {
+            version: migration::SAVE_VERSION,
+            boards,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CircuitDesignControlCopy {
     pub rect: Rect,
@@ -151,6 +171,14 @@ pub struct CircuitCopyData {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     #[serde(default)]
     pub design_controls: Vec<Option<CircuitDesignControlCopy>>,
+    /// Preview of a circuit with unknown type, so it can be placed back unchanged.
+    /// Only kept in memory, as it can't be recovered without the rest of the board
+    #[serde(skip)]
+    pub placeholder: Option<crate::circuits::missing::MissingPreview>,
+    /// Internal state in every state of the board by state id, for putting a removed circuit back.
+    /// Must be cleared for pasting, as the original circuit may still be on the board
+    #[serde(skip)]
+    pub state_internals: Vec<(usize, Intermediate)>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -169,6 +197,16 @@ pub struct CopyPasteData {
     pub circuits: Vec<CircuitCopyData>
 }
 
+impl CopyPasteData {
+    /// Data without anything that only applies to circuits being put back after removal
+    pub fn for_clipboard(mut self) -> Self {
+        for circuit in self.circuits.iter_mut() {
+            circuit.state_internals.clear();
+        }
+        self
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct CircuitPropertyStoreData(pub HashMap<DynStaticStr, Intermediate>);
 
@@ -0,0 +1,347 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    ops::Deref,
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::CircuitBoard,
+    circuits::{board::Board, Circuit},
+    error::{ErrorList, ResultReport},
+    DynStaticStr,
+};
+
+// Netlists describe board topology only: which circuit pins are connected with each other.
+// Wire geometry, designs and simulation state are left out, see `SaveStateData` for those.
+//
+// Format, as JSON:
+// {
+//   "version": 1,
+//   "boards": [{
+//     "uid": "1f3a...",           board uid in hex
+//     "name": "main",
+//     "circuits": [{
+//       "id": 0,                  unique within the board, net pins refer to it
+//       "type": "and",            type name, same as in saves
+//       "pos": [8, 0],            top left corner, in tiles
+//       "props": { ... },         property values, same as in saves
+//       "board": "2b4c..."        only in `board` circuits, uid of the board inside
+//     }],
+//     "nets": [{
+//       "id": 0,                  unique within the board, nets are numbered in circuit order
+//       "tunnels": ["clk"],       names of tunnels joining the net, omitted if there are none
+//       "pins": [{ "circuit": 0, "pin": "out" }]
+//     }]
+//   }]
+// }
+//
+// The exported board comes first, boards it contains follow, each one listed once.
+// Pins are named as in saves: gates have `in_0`, `in_1`... and `out`, board pins and tunnels
+// have a single `pin`, and pins of `board` circuits are named by pin ids of the inner board.
+// Every circuit pin belongs to exactly one net, unconnected pins get nets of their own.
+// `version` is increased only on incompatible changes, so readers should reject versions newer
+// than they know. Adding fields isn't one of them, unknown fields should be ignored.
+
+/// Current netlist format version, increased on incompatible changes
+pub const NETLIST_VERSION: u32 = 1;
+
+/// Netlist of a board and every board it contains
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct NetlistData {
+    pub version: u32,
+    /// Exported board first, followed by inner boards
+    pub boards: Vec<BoardNetlistData>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct BoardNetlistData {
+    /// Board uid in hex, it doesn't fit into JSON numbers
+    pub uid: String,
+    pub name: String,
+    pub circuits: Vec<NetlistCircuitData>,
+    pub nets: Vec<NetData>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct NetlistCircuitData {
+    /// Circuit id, unique within its board
+    pub id: usize,
+    /// Circuit type name, same as in saves
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub pos: [i32; 2],
+    /// Property values, same as in saves
+    pub props: BTreeMap<String, serde_json::Value>,
+    /// Uid of the board inside a `board` circuit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub board: Option<String>,
+}
+
+/// Connected circuit pins. Pins not connected to anything are nets of their own
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct NetData {
+    pub id: usize,
+    /// Names of tunnels joining this net
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tunnels: Vec<String>,
+    pub pins: Vec<NetPinData>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct NetPinData {
+    pub circuit: usize,
+    pub pin: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Node {
+    Wire(usize),
+    /// Pin not connected to any wire
+    Pin(usize, usize),
+}
+
+#[derive(Default)]
+struct UnionFind {
+    parents: Vec<usize>,
+    nodes: HashMap<Node, usize>,
+}
+
+impl UnionFind {
+    fn node(&mut self, node: Node) -> usize {
+        if let Some(index) = self.nodes.get(&node) {
+            return *index;
+        }
+        let index = self.parents.len();
+        self.parents.push(index);
+        self.nodes.insert(node, index);
+        index
+    }
+
+    fn find(&mut self, mut index: usize) -> usize {
+        while self.parents[index] != index {
+            self.parents[index] = self.parents[self.parents[index]];
+            index = self.parents[index];
+        }
+        index
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let a = self.find(a);
+        let b = self.find(b);
+        self.parents[a] = b;
+    }
+}
+
+/// Nets of every circuit pin in a board
+pub(super) struct BoardNets {
+    /// Pin names and nets of every circuit by its id
+    pub pins: HashMap<usize, Vec<(DynStaticStr, usize)>>,
+    pub count: usize,
+}
+
+impl BoardNets {
+    /// Nets are numbered in order of circuits and their pins
+    pub fn new(circuits: &[Arc<Circuit>]) -> Self {
+        let mut uf = UnionFind::default();
+        let mut pins = HashMap::new();
+        let mut tunnels: HashMap<Arc<str>, usize> = HashMap::new();
+        for circuit in circuits {
+            let info = circuit.info.read();
+            let list: Vec<_> = info
+                .pins
+                .iter()
+                .enumerate()
+                .map(|(i, pin)| {
+                    let node = match pin.pin.read().connected_wire() {
+                        Some(wire) => Node::Wire(wire),
+                        None => Node::Pin(circuit.id, i),
+                    };
+                    (pin.name.clone(), uf.node(node))
+                })
+                .collect();
+
+            if circuit.ty.deref() == "tunnel" {
+                if let (Some(name), Some((_, node))) = (circuit.name(), list.first()) {
+                    match tunnels.get(&name) {
+                        Some(other) => uf.union(*node, *other),
+                        None => {
+                            tunnels.insert(name, *node);
+                        }
+                    }
+                }
+            }
+            pins.insert(circuit.id, list);
+        }
+
+        let mut nets = HashMap::new();
+        for circuit in circuits {
+            let list = crate::unwrap_option_or_continue!(pins.get_mut(&circuit.id));
+            for (_, node) in list.iter_mut() {
+                let root = uf.find(*node);
+                let next = nets.len();
+                *node = *nets.entry(root).or_insert(next);
+            }
+        }
+
+        Self {
+            pins,
+            count: nets.len(),
+        }
+    }
+
+    pub fn net(&self, circuit: usize, pin: &str) -> Option<usize> {
+        self.pins
+            .get(&circuit)?
+            .iter()
+            .find(|(name, _)| name.deref() == pin)
+            .map(|(_, net)| *net)
+    }
+
+    /// Nets of pins named `{prefix}{index}`, sorted by index
+    pub fn indexed(&self, circuit: usize, prefix: &str) -> Vec<(u32, usize)> {
+        let mut nets: Vec<_> = self
+            .pins
+            .get(&circuit)
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, net)| Some((name.strip_prefix(prefix)?.parse().ok()?, *net)))
+            .collect();
+        nets.sort_by_key(|(i, _)| *i);
+        nets
+    }
+}
+
+fn board_netlist(
+    board: &CircuitBoard,
+    circuits: &[Arc<Circuit>],
+    errors: &mut ErrorList,
+) -> BoardNetlistData {
+    let nets = BoardNets::new(circuits);
+    let mut net_data: Vec<_> = (0..nets.count)
+        .map(|id| NetData {
+            id,
+            tunnels: vec![],
+            pins: vec![],
+        })
+        .collect();
+
+    let mut circuit_data = vec![];
+    for circuit in circuits {
+        for (pin, net) in nets.pins.get(&circuit.id).into_iter().flatten() {
+            net_data[*net].pins.push(NetPinData {
+                circuit: circuit.id,
+                pin: pin.to_string(),
+            });
+        }
+        if circuit.ty.deref() == "tunnel" {
+            let net = nets.net(circuit.id, "pin");
+            if let Some((name, net)) = circuit.name().zip(net) {
+                net_data[net].tunnels.push(name.to_string());
+            }
+        }
+
+        let mut errors = errors.enter_context(|| format!("exporting circuit {}", circuit.id));
+        let props = circuit
+            .props
+            .save()
+            .0
+            .into_iter()
+            .filter_map(|(id, value)| {
+                let value = serde_json::to_value(&value).report_error(&mut errors)?;
+                Some((id.to_string(), value))
+            })
+            .collect();
+        let inner = circuit
+            .read_imp(|b: &Board| b.inner_board().map(|b| format!("{:x}", b.uid)))
+            .flatten();
+
+        circuit_data.push(NetlistCircuitData {
+            id: circuit.id,
+            ty: circuit.ty.to_string(),
+            pos: [circuit.pos.x, circuit.pos.y],
+            props,
+            board: inner,
+        });
+    }
+
+    for net in net_data.iter_mut() {
+        net.tunnels.sort();
+        net.tunnels.dedup();
+    }
+
+    BoardNetlistData {
+        uid: format!("{:x}", board.uid),
+        name: board.name.read().get_str().to_string(),
+        circuits: circuit_data,
+        nets: net_data,
+    }
+}
+
+/// Exports a board and every board it contains as a pretty-printed JSON [`NetlistData`]
+pub fn export(board: &Arc<CircuitBoard>, errors: &mut ErrorList) -> String {
+    let mut boards = vec![];
+    let mut visited = HashSet::from([board.uid]);
+    let mut queue = VecDeque::from([board.clone()]);
+    while let Some(board) = queue.pop_front() {
+        let circuits: Vec<_> = board.circuits.read().iter().cloned().collect();
+        for circuit in circuits.iter() {
+            let inner = circuit
+                .read_imp(|b: &Board| b.inner_board().cloned())
+                .flatten();
+            if let Some(inner) = inner.filter(|inner| visited.insert(inner.uid)) {
+                queue.push_back(inner);
+            }
+        }
+        boards.push(board_netlist(&board, &circuits, errors));
+    }
+
+    let data = NetlistData {
+        version: NETLIST_VERSION,
+        boards,
+    };
+    serde_json::to_string_pretty(&data)
+        .report_error(errors)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{NetlistData, NETLIST_VERSION};
+    use crate::{error::ErrorList, io::test_boards};
+
+    #[test]
+    fn netlist_round_trips() {
+        let board = test_boards::hierarchy();
+        let mut errors = ErrorList::new();
+        let json = super::export(&board, &mut errors);
+        assert!(errors.is_empty());
+
+        let netlist: NetlistData = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string_pretty(&netlist).unwrap(), json);
+
+        assert_eq!(netlist.version, NETLIST_VERSION);
+        let names: Vec<_> = netlist.boards.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, ["main", "inv"]);
+
+        let main = &netlist.boards[0];
+        let instance = main.circuits.iter().find(|c| c.ty == "board").unwrap();
+        assert_eq!(instance.board.as_ref(), Some(&netlist.boards[1].uid));
+
+        let pins: usize = main.nets.iter().map(|n| n.pins.len()).sum();
+        let ids: Vec<_> = main.nets.iter().map(|n| n.id).collect();
+        assert_eq!(ids, (0..main.nets.len()).collect::<Vec<_>>());
+        // a, b, and gate, inv, y, bundler and bus pins
+        assert_eq!(pins, 1 + 1 + 3 + 2 + 1 + 3 + 1);
+
+        let and = main.circuits.iter().find(|c| c.ty == "and").unwrap();
+        let out = main
+            .nets
+            .iter()
+            .find(|n| n.pins.iter().any(|p| p.circuit == and.id && p.pin == "out"))
+            .unwrap();
+        assert_eq!(out.pins.len(), 2);
+        assert!(out.pins.iter().any(|p| p.circuit == instance.id));
+    }
+}
@@ -0,0 +1,402 @@
+use std::fmt::Write;
+
+use eframe::{
+    egui::{self, CentralPanel, Context, Frame, RawInput, Ui},
+    epaint::{
+        ClippedPrimitive, ClippedShape, Color32, FontFamily, ImageData, Mesh, Primitive, Shape,
+        Stroke, TextShape, TextureId,
+    },
+};
+use emath::{Pos2, Rect, Vec2};
+
+// Drawing is done by a separate egui context which is never shown, so it doesn't need a GPU
+// and doesn't disturb the UI. Its output shapes are then written as SVG elements, or
+// tessellated into triangles and rasterized for PNG.
+
+/// Shapes drawn in a separate egui context, in points starting at zero
+pub struct CapturedImage {
+    ctx: Context,
+    shapes: Vec<ClippedShape>,
+    size: Vec2,
+    /// Font atlas, sRGBA premultiplied
+    font: Option<(usize, usize, Vec<Color32>)>,
+}
+
+impl CapturedImage {
+    /// Draws with an offscreen [`Ui`] of given size, styled the same way as the app
+    pub fn capture(style: &egui::Style, size: Vec2, draw: impl FnOnce(&mut Ui)) -> Self {
+        let ctx = Context::default();
+        ctx.set_style(style.clone());
+
+        let input = RawInput {
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, size)),
+            ..Default::default()
+        };
+        let output = ctx.run(input, |ctx| {
+            CentralPanel::default()
+                .frame(Frame::none())
+                .show(ctx, |ui| draw(ui));
+        });
+
+        let font =
+            output
+                .textures_delta
+                .set
+                .iter()
+                .find_map(|(id, delta)| match (id, &delta.image) {
+                    (TextureId::Managed(0), ImageData::Font(image)) if delta.pos.is_none() => {
+                        let pixels = image.srgba_pixels(None).collect();
+                        Some((image.width(), image.height(), pixels))
+                    }
+                    _ => None,
+                });
+
+        Self {
+            ctx,
+            shapes: output.shapes,
+            size,
+            font,
+        }
+    }
+
+    pub fn to_svg(&self) -> String {
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = self.size.x,
+            h = self.size.y
+        );
+        for shape in self.shapes.iter() {
+            write_shape(&mut svg, &shape.shape);
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Largest pixel count [`Self::to_png`] rasterizes, its float raster takes 256 MiB
+    pub const MAX_PNG_PIXELS: usize = 1 << 24;
+
+    /// Rasterizes shapes with one pixel per point and encodes them as PNG
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        let width = self.size.x.ceil().max(1.0) as usize;
+        let height = self.size.y.ceil().max(1.0) as usize;
+        if width.saturating_mul(height) > Self::MAX_PNG_PIXELS {
+            return Err(format!(
+                "{width}x{height} image is too large to rasterize, try lowering the scale"
+            ));
+        }
+        let mut raster = Raster {
+            width,
+            height,
+            pixels: vec![[0.0; 4]; width * height],
+            font: self.font.as_ref(),
+        };
+        for primitive in self.ctx.tessellate(self.shapes.clone(), 1.0) {
+            let ClippedPrimitive {
+                clip_rect,
+                primitive,
+            } = primitive;
+            if let Primitive::Mesh(mesh) = primitive {
+                raster.draw_mesh(&mesh, clip_rect);
+            }
+        }
+
+        let mut data = Vec::with_capacity(width * height * 4);
+        for [r, g, b, a] in raster.pixels {
+            // PNG colors aren't premultiplied
+            let unmultiply = if a > 0.0 { 1.0 / a } else { 0.0 };
+            for c in [r * unmultiply, g * unmultiply, b * unmultiply, a] {
+                data.push((c.clamp(0.0, 1.0) * 255.0).round() as u8);
+            }
+        }
+
+        encode_png(width as u32, height as u32, &data).map_err(|e| e.to_string())
+    }
+}
+
+/// Encodes 8-bit RGBA pixel rows
+fn encode_png(width: u32, height: u32, data: &[u8]) -> Result<Vec<u8>, png::EncodingError> {
+    let mut png = vec![];
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(data)?;
+    writer.finish()?;
+    Ok(png)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn paint(attr: &str, color: Color32) -> String {
+    if color.a() == 0 {
+        return format!(r#" {attr}="none""#);
+    }
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    let mut paint = format!(r##" {attr}="#{r:02x}{g:02x}{b:02x}""##);
+    if a < 255 {
+        let _ = write!(paint, r#" {attr}-opacity="{:.3}""#, a as f32 / 255.0);
+    }
+    paint
+}
+
+fn fill(color: Color32) -> String {
+    paint("fill", color)
+}
+
+fn stroke(stroke: Stroke) -> String {
+    if stroke.width <= 0.0 || stroke.color.a() == 0 {
+        return r#" stroke="none""#.into();
+    }
+    format!(
+        r#"{} stroke-width="{}""#,
+        paint("stroke", stroke.color),
+        stroke.width
+    )
+}
+
+fn path_data(points: &[Pos2], closed: bool) -> String {
+    let mut data = String::new();
+    for (i, point) in points.iter().enumerate() {
+        let cmd = if i == 0 { 'M' } else { 'L' };
+        let _ = write!(data, "{cmd}{} {} ", point.x, point.y);
+    }
+    if closed {
+        data.push('Z');
+    }
+    data
+}
+
+fn write_shape(svg: &mut String, shape: &Shape) {
+    match shape {
+        Shape::Noop | Shape::Callback(_) => {}
+        Shape::Vec(shapes) => {
+            for shape in shapes {
+                write_shape(svg, shape);
+            }
+        }
+        Shape::Circle(circle) => {
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{}" cy="{}" r="{}"{}{}/>"#,
+                circle.center.x,
+                circle.center.y,
+                circle.radius,
+                fill(circle.fill),
+                stroke(circle.stroke)
+            );
+        }
+        Shape::LineSegment { points, stroke: s } => {
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}"{} stroke-linecap="round"/>"#,
+                points[0].x,
+                points[0].y,
+                points[1].x,
+                points[1].y,
+                stroke(*s)
+            );
+        }
+        Shape::Path(path) => {
+            let _ = writeln!(
+                svg,
+                r#"<path d="{}"{}{}/>"#,
+                path_data(&path.points, path.closed),
+                fill(path.fill),
+                stroke(path.stroke)
+            );
+        }
+        Shape::Rect(rect) => {
+            let radius = rect.rounding.nw.max(rect.rounding.se);
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{radius}"{}{}/>"#,
+                rect.rect.min.x,
+                rect.rect.min.y,
+                rect.rect.width(),
+                rect.rect.height(),
+                fill(rect.fill),
+                stroke(rect.stroke)
+            );
+        }
+        Shape::Text(text) => write_text(svg, text),
+        Shape::Mesh(mesh) => write_mesh(svg, mesh),
+        Shape::QuadraticBezier(bezier) => {
+            let [a, b, c] = bezier.points;
+            let close = if bezier.closed { "Z" } else { "" };
+            let _ = writeln!(
+                svg,
+                r#"<path d="M{} {} Q{} {} {} {}{close}"{}{}/>"#,
+                a.x,
+                a.y,
+                b.x,
+                b.y,
+                c.x,
+                c.y,
+                fill(bezier.fill),
+                stroke(bezier.stroke)
+            );
+        }
+        Shape::CubicBezier(bezier) => {
+            let [a, b, c, d] = bezier.points;
+            let close = if bezier.closed { "Z" } else { "" };
+            let _ = writeln!(
+                svg,
+                r#"<path d="M{} {} C{} {} {} {} {} {}{close}"{}{}/>"#,
+                a.x,
+                a.y,
+                b.x,
+                b.y,
+                c.x,
+                c.y,
+                d.x,
+                d.y,
+                fill(bezier.fill),
+                stroke(bezier.stroke)
+            );
+        }
+    }
+}
+
+fn write_text(svg: &mut String, text: &TextShape) {
+    let _ = writeln!(
+        svg,
+        r#"<g transform="translate({} {}) rotate({})">"#,
+        text.pos.x,
+        text.pos.y,
+        text.angle.to_degrees()
+    );
+    for row in text.galley.rows.iter() {
+        let first = crate::unwrap_option_or_continue!(row.glyphs.first());
+        let section = &text.galley.job.sections[first.section_index as usize];
+        let format = &section.format;
+        let color = match text.override_text_color {
+            Some(color) => color,
+            None if format.color == Color32::PLACEHOLDER => text.fallback_color,
+            None => format.color,
+        };
+        let family = match format.font_id.family {
+            FontFamily::Monospace => "monospace",
+            _ => "sans-serif",
+        };
+        let content: String = row.glyphs.iter().map(|g| g.chr).collect();
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" font-family="{family}" font-size="{}" xml:space="preserve"{}>{}</text>"#,
+            first.pos.x,
+            first.pos.y,
+            format.font_id.size,
+            fill(color),
+            escape(&content)
+        );
+    }
+    svg.push_str("</g>\n");
+}
+
+/// Meshes become triangles of average vertex color, textures are ignored
+fn write_mesh(svg: &mut String, mesh: &Mesh) {
+    for triangle in mesh.indices.chunks_exact(3) {
+        let vertices = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize]);
+        let [r, g, b, a] = vertices
+            .iter()
+            .map(|v| v.color.to_array().map(|c| c as u32))
+            .fold([0; 4], |acc, c| [0, 1, 2, 3].map(|i| acc[i] + c[i]))
+            .map(|c| (c / 3) as u8);
+        let color = Color32::from_rgba_premultiplied(r, g, b, a);
+        let points: Vec<_> = vertices.iter().map(|v| v.pos).collect();
+        let _ = writeln!(
+            svg,
+            r#"<path d="{}"{}/>"#,
+            path_data(&points, true),
+            fill(color)
+        );
+    }
+}
+
+/// Software rasterizer for tessellated meshes, colors being premultiplied
+struct Raster<'a> {
+    width: usize,
+    height: usize,
+    pixels: Vec<[f32; 4]>,
+    font: Option<&'a (usize, usize, Vec<Color32>)>,
+}
+
+impl Raster<'_> {
+    fn sample(&self, texture: TextureId, uv: Pos2) -> [f32; 4] {
+        let font = match (texture, self.font) {
+            (TextureId::Managed(0), Some(font)) => font,
+            _ => return [1.0; 4],
+        };
+        let (width, height, pixels) = font;
+        let x = ((uv.x * *width as f32) as usize).min(width - 1);
+        let y = ((uv.y * *height as f32) as usize).min(height - 1);
+        pixels[y * width + x].to_array().map(|c| c as f32 / 255.0)
+    }
+
+    fn draw_mesh(&mut self, mesh: &Mesh, clip: Rect) {
+        let clip = clip.intersect(Rect::from_min_size(
+            Pos2::ZERO,
+            Vec2::new(self.width as f32, self.height as f32),
+        ));
+        if !clip.is_positive() {
+            return;
+        }
+
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize]);
+            let area =
+                (b.pos - a.pos).x * (c.pos - a.pos).y - (b.pos - a.pos).y * (c.pos - a.pos).x;
+            if area.abs() < f32::EPSILON {
+                continue;
+            }
+
+            let bounds = Rect::from_points(&[a.pos, b.pos, c.pos]).intersect(clip);
+            if !bounds.is_positive() {
+                continue;
+            }
+            let colors = [a, b, c].map(|v| v.color.to_array().map(|c| c as f32 / 255.0));
+
+            for y in bounds.min.y.floor() as usize..bounds.max.y.ceil() as usize {
+                for x in bounds.min.x.floor() as usize..bounds.max.x.ceil() as usize {
+                    let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+                    let edge = |from: Pos2, to: Pos2| {
+                        ((to - from).x * (p - from).y - (to - from).y * (p - from).x) / area
+                    };
+                    let weights = [edge(b.pos, c.pos), edge(c.pos, a.pos), edge(a.pos, b.pos)];
+                    if weights.iter().any(|w| *w < 0.0) {
+                        continue;
+                    }
+
+                    let uv = a.uv.to_vec2() * weights[0]
+                        + b.uv.to_vec2() * weights[1]
+                        + c.uv.to_vec2() * weights[2];
+                    let texel = self.sample(mesh.texture_id, uv.to_pos2());
+                    let src: [f32; 4] = [0, 1, 2, 3].map(|i| {
+                        let color = colors[0][i] * weights[0]
+                            + colors[1][i] * weights[1]
+                            + colors[2][i] * weights[2];
+                        color * texel[i]
+                    });
+
+                    let dst = &mut self.pixels[y * self.width + x];
+                    for i in 0..4 {
+                        dst[i] = src[i] + dst[i] * (1.0 - src[3]);
+                    }
+                }
+            }
+        }
+    }
+}
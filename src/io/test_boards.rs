@@ -0,0 +1,153 @@
+use std::{ops::Deref, sync::Arc};
+
+use crate::{
+    app::SimulationContext,
+    board::{CircuitBoard, CircuitDesignPin, EditableCircuitBoard, StoredCircuitBoard},
+    circuits::{
+        board::BoardPreview, pin::PinType, props::CircuitPropertyStore, Circuit, CircuitPreview,
+        InternalPinDirection,
+    },
+    routing::{self, RoutingOptions},
+    vector::Vec2i,
+    wires::WireColors,
+    ArcString, Direction4,
+};
+
+// Small board hierarchy shared by exporter tests
+
+fn new_board(ctx: &Arc<SimulationContext>, name: &str) -> EditableCircuitBoard {
+    let board = Arc::new(CircuitBoard::new(ctx.clone(), name));
+    ctx.boards
+        .write()
+        .insert(board.uid, StoredCircuitBoard::new(board.clone()));
+    EditableCircuitBoard::new_main(board)
+}
+
+fn place(
+    editor: &mut EditableCircuitBoard,
+    preview: &CircuitPreview,
+    pos: Vec2i,
+    props: impl FnOnce(&CircuitPropertyStore),
+) -> Arc<Circuit> {
+    let store = preview.props.clone();
+    props(&store);
+    let id = editor
+        .place_circuit(pos, true, preview, Some(store), false, None, &mut |_, _| {})
+        .expect("test circuit should fit");
+    editor.board.circuits.read().get(id).cloned().unwrap()
+}
+
+fn place_builtin(
+    editor: &mut EditableCircuitBoard,
+    ty: &str,
+    pos: Vec2i,
+    props: impl FnOnce(&CircuitPropertyStore),
+) -> Arc<Circuit> {
+    let preview = editor.board.ctx.previews.get(ty).cloned().unwrap();
+    place(editor, &preview, pos, props)
+}
+
+fn place_pin(
+    editor: &mut EditableCircuitBoard,
+    name: &str,
+    ty: PinType,
+    pos: Vec2i,
+) -> Arc<Circuit> {
+    let dir = match ty {
+        PinType::Pico => Direction4::Right,
+        _ => Direction4::Left,
+    };
+    place_builtin(editor, "pin", pos, |props| {
+        props.write("name", |s: &mut ArcString| *s = name.into());
+        props.write("ty", |t: &mut PinType| *t = ty);
+        props.write("dir", |d: &mut Direction4| *d = dir);
+    })
+}
+
+fn pin_pos(circuit: &Circuit, pin: &str) -> Vec2i {
+    let info = circuit.info.read();
+    let pin = info.pins.iter().find(|p| p.name.deref() == pin).unwrap();
+    circuit.pos + pin.pos.convert(|v| v as i32)
+}
+
+fn connect(editor: &mut EditableCircuitBoard, from: (&Circuit, &str), to: (&Circuit, &str)) {
+    let from = pin_pos(from.0, from.1);
+    let to = pin_pos(to.0, to.1);
+    let route = routing::route(editor, from, to, RoutingOptions::default());
+    for part in route.expect("test pins should be routable") {
+        editor.place_wire_part(part, true, WireColors::default());
+    }
+}
+
+/// `inv` board: input pin `a` inverted into output pin `y`
+fn inverter(ctx: &Arc<SimulationContext>) -> Arc<CircuitBoard> {
+    let mut editor = new_board(ctx, "inv");
+    let a = place_pin(&mut editor, "a", PinType::Pico, [0, 0].into());
+    let not = place_builtin(&mut editor, "not", [6, 0].into(), |_| {});
+    let y = place_pin(&mut editor, "y", PinType::Cipo, [12, 0].into());
+    connect(&mut editor, (&a, "pin"), (&not, "in"));
+    connect(&mut editor, (&not, "out"), (&y, "pin"));
+
+    let board = editor.board.clone();
+    let mut designs = board.designs.write();
+    let design = designs.current_mut();
+    design.size = [2, 1].into();
+    for (pin, x, dir, display_dir) in [
+        (&a, 0, InternalPinDirection::Inside, Direction4::Left),
+        (&y, 1, InternalPinDirection::Outside, Direction4::Right),
+    ] {
+        let id = board.pins.read().get_by_right(&pin.id).cloned().unwrap();
+        design.pins.push(CircuitDesignPin {
+            id: id.into(),
+            pos: [x, 0].into(),
+            dir,
+            display_dir: Some(display_dir),
+            display_name: pin.name().unwrap().into(),
+        });
+    }
+    drop(designs);
+    board
+}
+
+/// `main` board: input pins `a` and `b` are ANDed and inverted by an `inv` board into output
+/// pin `y`, and bundled into two-bit output pin `bus`
+pub fn hierarchy() -> Arc<CircuitBoard> {
+    let ctx = SimulationContext::with_builtin_previews();
+    let inv = inverter(&ctx);
+    let inv_preview =
+        CircuitPreview::from_impl(Box::new(BoardPreview::new_from_board(inv.clone())));
+    let inv_pin = |name: &str| {
+        let circuit = inv
+            .circuits
+            .read()
+            .iter()
+            .find(|c| c.name().as_deref() == Some(name))
+            .cloned();
+        inv.pins
+            .read()
+            .get_by_right(&circuit.unwrap().id)
+            .cloned()
+            .unwrap()
+    };
+    let (inv_a, inv_y) = (inv_pin("a"), inv_pin("y"));
+
+    let mut editor = new_board(&ctx, "main");
+    let a = place_pin(&mut editor, "a", PinType::Pico, [0, 0].into());
+    let b = place_pin(&mut editor, "b", PinType::Pico, [0, 6].into());
+    let and = place_builtin(&mut editor, "and", [8, 0].into(), |_| {});
+    let instance = place(&mut editor, &inv_preview, [16, 0].into(), |_| {});
+    let y = place_pin(&mut editor, "y", PinType::Cipo, [24, 0].into());
+    let bundler = place_builtin(&mut editor, "bundler", [8, 12].into(), |props| {
+        props.write("io_count", |c: &mut u32| *c = 2);
+    });
+    let bus = place_pin(&mut editor, "bus", PinType::Cipo, [24, 12].into());
+
+    connect(&mut editor, (&bundler, "bundle"), (&bus, "pin"));
+    connect(&mut editor, (&a, "pin"), (&and, "in_0"));
+    connect(&mut editor, (&b, "pin"), (&and, "in_1"));
+    connect(&mut editor, (&and, "out"), (&instance, &inv_a));
+    connect(&mut editor, (&instance, &inv_y), (&y, "pin"));
+    connect(&mut editor, (&bundler, "io_0"), (&a, "pin"));
+    connect(&mut editor, (&bundler, "io_1"), (&b, "pin"));
+    editor.board.clone()
+}
@@ -0,0 +1,789 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    ops::Deref,
+    rc::Rc,
+    sync::Arc,
+};
+
+use crate::{
+    board::CircuitBoard,
+    circuits::{
+        board::Board,
+        bundler::BundlerDirecion,
+        pin::PinType,
+        splitter::{self, SplitterMode},
+        Circuit,
+    },
+    error::ErrorList,
+};
+
+use super::netlist::BoardNets;
+
+// Every board becomes a module, its pins being module ports. Wires, together with tunnels
+// sharing a name, become nets. Wires can carry any state, so net widths are inferred from
+// circuits connected to them, nets of unknown width being single bits.
+
+const KEYWORDS: &[&str] = &[
+    "always",
+    "and",
+    "assign",
+    "automatic",
+    "begin",
+    "buf",
+    "bufif0",
+    "bufif1",
+    "case",
+    "casex",
+    "casez",
+    "cell",
+    "cmos",
+    "config",
+    "deassign",
+    "default",
+    "defparam",
+    "design",
+    "disable",
+    "edge",
+    "else",
+    "end",
+    "endcase",
+    "endconfig",
+    "endfunction",
+    "endgenerate",
+    "endmodule",
+    "endprimitive",
+    "endspecify",
+    "endtable",
+    "endtask",
+    "event",
+    "for",
+    "force",
+    "forever",
+    "fork",
+    "function",
+    "generate",
+    "genvar",
+    "highz0",
+    "highz1",
+    "if",
+    "ifnone",
+    "incdir",
+    "include",
+    "initial",
+    "inout",
+    "input",
+    "instance",
+    "integer",
+    "join",
+    "large",
+    "liblist",
+    "library",
+    "localparam",
+    "macromodule",
+    "medium",
+    "module",
+    "nand",
+    "negedge",
+    "nmos",
+    "nor",
+    "noshowcancelled",
+    "not",
+    "notif0",
+    "notif1",
+    "or",
+    "output",
+    "parameter",
+    "pmos",
+    "posedge",
+    "primitive",
+    "pull0",
+    "pull1",
+    "pulldown",
+    "pullup",
+    "pulsestyle_ondetect",
+    "pulsestyle_onevent",
+    "rcmos",
+    "real",
+    "realtime",
+    "reg",
+    "release",
+    "repeat",
+    "rnmos",
+    "rpmos",
+    "rtran",
+    "rtranif0",
+    "rtranif1",
+    "scalared",
+    "showcancelled",
+    "signed",
+    "small",
+    "specify",
+    "specparam",
+    "strong0",
+    "strong1",
+    "supply0",
+    "supply1",
+    "table",
+    "task",
+    "time",
+    "tran",
+    "tranif0",
+    "tranif1",
+    "tri",
+    "tri0",
+    "tri1",
+    "triand",
+    "trior",
+    "trireg",
+    "unsigned",
+    "use",
+    "uwire",
+    "vectored",
+    "wait",
+    "wand",
+    "weak0",
+    "weak1",
+    "while",
+    "wire",
+    "wor",
+    "xnor",
+    "xor",
+];
+
+/// Turns any name into a Verilog identifier
+fn sanitize(name: &str) -> String {
+    let mut id: String = name
+        .trim()
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c,
+            false => '_',
+        })
+        .collect();
+    if id.is_empty() || id.starts_with(|c: char| c.is_ascii_digit()) {
+        id.insert(0, '_');
+    }
+    if KEYWORDS.contains(&id.as_str()) {
+        id.push('_');
+    }
+    id
+}
+
+fn range(width: u32) -> String {
+    match width {
+        0 | 1 => String::new(),
+        width => format!("[{}:0] ", width - 1),
+    }
+}
+
+fn concat(mut parts: Vec<String>) -> String {
+    match parts.len() {
+        1 => parts.remove(0),
+        _ => format!("{{{}}}", parts.join(", ")),
+    }
+}
+
+/// Identifiers used in a single scope
+#[derive(Default)]
+struct Names(HashSet<String>);
+
+impl Names {
+    fn unique(&mut self, base: String) -> String {
+        let mut name = base.clone();
+        let mut i = 1;
+        while self.0.contains(&name) {
+            name = format!("{base}_{i}");
+            i += 1;
+        }
+        self.0.insert(name.clone());
+        name
+    }
+}
+
+struct Net {
+    name: String,
+    width: u32,
+}
+
+impl Net {
+    fn bit(&self, bit: u32) -> String {
+        match self.width {
+            0 | 1 => self.name.clone(),
+            _ => format!("{}[{bit}]", self.name),
+        }
+    }
+
+    fn slice(&self, low: u32, len: u32) -> String {
+        if low == 0 && len >= self.width {
+            self.name.clone()
+        } else if len == 1 {
+            self.bit(low)
+        } else {
+            format!("{}[{}:{low}]", self.name, low + len - 1)
+        }
+    }
+
+    /// Bits in given order, lowest first
+    fn bits(&self, bits: &[u32]) -> String {
+        let contiguous = bits.windows(2).all(|w| w[1] == w[0] + 1);
+        match (contiguous, bits.first()) {
+            (true, Some(low)) => self.slice(*low, bits.len() as u32),
+            _ => concat(bits.iter().rev().map(|b| self.bit(*b)).collect()),
+        }
+    }
+}
+
+/// Exported module of a board
+struct Module {
+    name: String,
+    /// Board pin id to port name and width
+    ports: HashMap<Arc<str>, (String, u32)>,
+}
+
+struct Bundler {
+    dir: BundlerDirecion,
+    offset: u32,
+    bundle: Option<usize>,
+    pre: Option<usize>,
+    rem: Option<usize>,
+    io: Vec<(u32, usize)>,
+}
+
+impl Bundler {
+    fn new(circuit: &Circuit, nets: &BoardNets) -> Self {
+        Self {
+            dir: circuit
+                .props
+                .read_clone("bundle_dir")
+                .unwrap_or(BundlerDirecion::Input),
+            offset: circuit.props.read_clone("io_offset").unwrap_or(0),
+            bundle: nets.net(circuit.id, "bundle"),
+            pre: nets.net(circuit.id, "pre"),
+            rem: nets.net(circuit.id, "rem"),
+            io: nets.indexed(circuit.id, "io_"),
+        }
+    }
+
+    /// Bundle bits before the remaining ones
+    fn base(&self) -> u32 {
+        let pre = match (self.dir, self.pre) {
+            (BundlerDirecion::Input, None) => 0,
+            _ => self.offset,
+        };
+        pre + self.io.len() as u32
+    }
+}
+
+#[derive(Default)]
+struct Widths(Vec<Option<u32>>);
+
+impl Widths {
+    fn set(&mut self, net: Option<usize>, width: u32) -> bool {
+        match net.and_then(|net| self.0.get_mut(net)) {
+            Some(slot @ None) => {
+                *slot = Some(width);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn get(&self, net: Option<usize>) -> Option<u32> {
+        net.and_then(|net| self.0.get(net).copied().flatten())
+    }
+
+    /// Sets every unknown width in a group of nets which must be equal
+    fn equal(&mut self, nets: &[usize]) -> bool {
+        let known = nets.iter().find_map(|net| self.0[*net]);
+        let known = crate::unwrap_option_or_return!(known, false);
+        let mut changed = false;
+        for net in nets {
+            changed |= self.set(Some(*net), known);
+        }
+        changed
+    }
+}
+
+const GATES: &[&str] = &["and", "or", "nand", "nor", "xor", "xnor"];
+
+struct Exporter<'a> {
+    /// Exported modules by board uid, [`None`] while being exported
+    modules: HashMap<u128, Option<Rc<Module>>>,
+    module_names: Names,
+    out: String,
+    errors: &'a mut ErrorList,
+}
+
+impl Exporter<'_> {
+    fn module(&mut self, board: &Arc<CircuitBoard>) -> Option<Rc<Module>> {
+        match self.modules.get(&board.uid) {
+            Some(Some(module)) => return Some(module.clone()),
+            Some(None) => {
+                self.errors.push_error(format!(
+                    "Board \"{}\" contains itself and can't be exported",
+                    board.name.read().get_str().deref()
+                ));
+                return None;
+            }
+            None => {}
+        }
+
+        self.modules.insert(board.uid, None);
+        let module = Rc::new(self.export_board(board));
+        self.modules.insert(board.uid, Some(module.clone()));
+        Some(module)
+    }
+
+    fn export_board(&mut self, board: &Arc<CircuitBoard>) -> Module {
+        let board_name = board.name.read().get_str().to_string();
+        let circuits: Vec<_> = board.circuits.read().iter().cloned().collect();
+
+        // Inner modules are written first
+        let mut children = HashMap::new();
+        for circuit in circuits.iter().filter(|c| c.ty.deref() == "board") {
+            let inner = circuit
+                .read_imp(|b: &Board| b.inner_board().cloned())
+                .flatten();
+            let module = match inner {
+                Some(inner) => self.module(&inner),
+                None => {
+                    self.errors.push_error(format!(
+                        "Board \"{board_name}\": board circuit {} has no board to export",
+                        circuit.id
+                    ));
+                    None
+                }
+            };
+            children.insert(circuit.id, module);
+        }
+
+        let module_name = self.module_names.unique(sanitize(&board_name));
+        let pins = BoardNets::new(&circuits);
+        let widths = infer_widths(&circuits, &pins, &children);
+
+        let mut names = Names::default();
+        let mut net_names: Vec<Option<String>> = vec![None; pins.count];
+        let mut header = vec![];
+        let mut aliases = String::new();
+        let mut ports = HashMap::new();
+
+        // Inputs come first, so nets shared with outputs get names of inputs driving them
+        let mut board_pins: Vec<_> = circuits
+            .iter()
+            .filter(|c| c.ty.deref() == "pin")
+            .filter_map(|c| Some((c, pins.net(c.id, "pin")?)))
+            .map(|(c, net)| (c, net, c.props.read_clone("ty").unwrap_or(PinType::Pico)))
+            .collect();
+        board_pins.sort_by_key(|(c, _, ty)| (!ty.is_pico(), c.pos.x, c.pos.y));
+
+        for (circuit, net, ty) in board_pins {
+            let pin_id = board.pins.read().get_by_right(&circuit.id).cloned();
+            let base = circuit
+                .name()
+                .or_else(|| pin_id.clone())
+                .unwrap_or_else(|| "pin".into());
+            let name = names.unique(sanitize(&base));
+            let width = widths[net];
+            let dir = match ty {
+                PinType::Pico => "input",
+                PinType::Cipo => "output",
+                PinType::Controlled | PinType::Bidirectional => "inout",
+            };
+            header.push(format!("    {dir} wire {}{name}", range(width)));
+
+            match &net_names[net] {
+                None => net_names[net] = Some(name.clone()),
+                Some(other) if !ty.is_pico() => {
+                    let _ = writeln!(aliases, "    assign {name} = {other};");
+                }
+                Some(other) => self.errors.push_error(format!(
+                    "Board \"{board_name}\": input pins {other} and {name} are connected"
+                )),
+            }
+            if let Some(pin_id) = pin_id {
+                ports.insert(pin_id, (name, width));
+            }
+        }
+
+        let mut wires = String::new();
+        let nets: Vec<Net> = net_names
+            .into_iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(i, (name, width))| {
+                let name = name.unwrap_or_else(|| {
+                    let name = names.unique(format!("n{i}"));
+                    let _ = writeln!(wires, "    wire {}{name};", range(width));
+                    name
+                });
+                Net { name, width }
+            })
+            .collect();
+
+        let mut body = String::new();
+        for circuit in circuits.iter() {
+            let ctx = CircuitNets {
+                circuit,
+                pins: &pins,
+                nets: &nets,
+            };
+            if !ctx.export(&children, &mut names, &mut body) {
+                let _ = writeln!(
+                    body,
+                    "    // {} circuit {} isn't supported",
+                    circuit.ty.deref(),
+                    circuit.id
+                );
+                self.errors.push_error(format!(
+                    "Board \"{board_name}\": {} circuits can't be exported to Verilog",
+                    circuit.ty.deref()
+                ));
+            }
+        }
+
+        let _ = write!(self.out, "module {module_name}");
+        if header.is_empty() {
+            self.out.push_str(";\n");
+        } else {
+            let _ = write!(self.out, " (\n{}\n);\n", header.join(",\n"));
+        }
+        for part in [wires, aliases, body] {
+            if !part.is_empty() {
+                self.out.push('\n');
+                self.out.push_str(&part);
+            }
+        }
+        self.out.push_str("endmodule\n\n");
+
+        Module {
+            name: module_name,
+            ports,
+        }
+    }
+}
+
+fn infer_widths(
+    circuits: &[Arc<Circuit>],
+    pins: &BoardNets,
+    children: &HashMap<usize, Option<Rc<Module>>>,
+) -> Vec<u32> {
+    let mut widths = Widths(vec![None; pins.count]);
+    let mut groups = vec![];
+    let mut bundlers = vec![];
+
+    for circuit in circuits {
+        let nets = pins
+            .pins
+            .get(&circuit.id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        match circuit.ty.deref() {
+            ty if ty == "not" || GATES.contains(&ty) => {
+                groups.push(nets.iter().map(|(_, net)| *net).collect::<Vec<_>>());
+            }
+            "splitter" => {
+                let fields = splitter::field_bits(&circuit.props);
+                let width = fields.iter().flatten().max().map(|max| max + 1);
+                widths.set(pins.net(circuit.id, "bundle"), width.unwrap_or(0));
+                for (i, bits) in fields.iter().enumerate() {
+                    widths.set(
+                        pins.net(circuit.id, &format!("field_{i}")),
+                        bits.len() as u32,
+                    );
+                }
+            }
+            "bundler" => {
+                let bundler = Bundler::new(circuit, pins);
+                for (_, io) in bundler.io.iter() {
+                    widths.set(Some(*io), 1);
+                }
+                widths.set(bundler.pre, bundler.offset);
+                bundlers.push(bundler);
+            }
+            "board" => {
+                let module = children.get(&circuit.id).cloned().flatten();
+                let module = crate::unwrap_option_or_continue!(module);
+                for (name, net) in nets {
+                    if let Some((_, width)) = module.ports.get(name.deref()) {
+                        widths.set(Some(*net), *width);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let propagate = |widths: &mut Widths| loop {
+        let mut changed = false;
+        for group in groups.iter() {
+            changed |= widths.equal(group);
+        }
+        for bundler in bundlers.iter() {
+            let base = bundler.base();
+            changed |= match (widths.get(bundler.bundle), bundler.rem) {
+                (Some(width), Some(_)) => widths.set(bundler.rem, width.saturating_sub(base)),
+                (None, None) => widths.set(bundler.bundle, base),
+                (None, Some(_)) => match widths.get(bundler.rem) {
+                    Some(rem) => widths.set(bundler.bundle, base + rem),
+                    None => false,
+                },
+                (Some(_), None) => false,
+            };
+        }
+        if !changed {
+            break;
+        }
+    };
+
+    propagate(&mut widths);
+    for bundler in bundlers.iter() {
+        widths.set(bundler.rem, 1);
+    }
+    propagate(&mut widths);
+    widths.0.into_iter().map(|w| w.unwrap_or(1)).collect()
+}
+
+/// Nets of a single circuit
+struct CircuitNets<'a> {
+    circuit: &'a Circuit,
+    pins: &'a BoardNets,
+    nets: &'a [Net],
+}
+
+impl CircuitNets<'_> {
+    fn net(&self, pin: &str) -> Option<&Net> {
+        self.pins
+            .net(self.circuit.id, pin)
+            .map(|net| &self.nets[net])
+    }
+
+    /// Writes circuit contents, returns `false` if the circuit can't be exported
+    fn export(
+        &self,
+        children: &HashMap<usize, Option<Rc<Module>>>,
+        names: &mut Names,
+        out: &mut String,
+    ) -> bool {
+        let id = self.circuit.id;
+        match self.circuit.ty.deref() {
+            ty if GATES.contains(&ty) => self.export_gate(ty, names, out),
+            "not" => {
+                let (input, output) =
+                    crate::unwrap_option_or_return!(self.net("in").zip(self.net("out")), true);
+                if output.width <= 1 {
+                    let name = names.unique(format!("g{id}"));
+                    let _ = writeln!(out, "    not {name} ({}, {});", output.name, input.name);
+                } else {
+                    let _ = writeln!(out, "    assign {} = ~{};", output.name, input.name);
+                }
+            }
+            "splitter" => {
+                let bundle = crate::unwrap_option_or_return!(self.net("bundle"), true);
+                let mode = self
+                    .circuit
+                    .props
+                    .read_clone("mode")
+                    .unwrap_or(SplitterMode::Split);
+                let fields = splitter::field_bits(&self.circuit.props);
+                for (i, bits) in fields.iter().enumerate() {
+                    let field = crate::unwrap_option_or_continue!(self.net(&format!("field_{i}")));
+                    match mode {
+                        SplitterMode::Split => {
+                            let _ =
+                                writeln!(out, "    assign {} = {};", field.name, bundle.bits(bits));
+                        }
+                        SplitterMode::Merge => {
+                            for (i, bit) in bits.iter().enumerate() {
+                                let _ = writeln!(
+                                    out,
+                                    "    assign {} = {};",
+                                    bundle.bit(*bit),
+                                    field.bit(i as u32)
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+            "bundler" => self.export_bundler(out),
+            "pullup" => {
+                // Pulls its pin low, despite the name
+                let net = crate::unwrap_option_or_return!(self.net("pin"), true);
+                let name = names.unique(format!("p{id}"));
+                let _ = writeln!(
+                    out,
+                    "    pulldown {name} {}({});",
+                    range(net.width),
+                    net.name
+                );
+            }
+            "board" => {
+                let module = children.get(&id).cloned().flatten();
+                let module = match module {
+                    Some(module) => module,
+                    None => {
+                        let _ = writeln!(out, "    // board circuit {id} has no module");
+                        return true;
+                    }
+                };
+                let ports: Vec<_> = self
+                    .pins
+                    .pins
+                    .get(&id)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|(pin, net)| {
+                        let (port, _) = module.ports.get(pin.deref())?;
+                        Some(format!(".{port}({})", self.nets[*net].name))
+                    })
+                    .collect();
+                let name = names.unique(format!("u{id}"));
+                let _ = writeln!(out, "    {} {name} ({});", module.name, ports.join(", "));
+            }
+            // Pins are ports, tunnels only join nets and the rest are sinks with no logic
+            "pin" | "tunnel" | "probe" | "led" | "freq_meter" => {}
+            _ => return false,
+        }
+        true
+    }
+
+    fn export_gate(&self, ty: &str, names: &mut Names, out: &mut String) {
+        let output = crate::unwrap_option_or_return!(self.net("out"));
+        let inputs: Vec<_> = self
+            .pins
+            .indexed(self.circuit.id, "in_")
+            .into_iter()
+            .map(|(_, net)| self.nets[net].name.as_str())
+            .collect();
+        let parity = self.circuit.props.read_clone("extra").unwrap_or(false);
+        let inverted = matches!(ty, "nand" | "nor" | "xnor");
+
+        // Without parity mode, xor is True only when exactly one input is
+        let expr = if matches!(ty, "xor" | "xnor") && !parity && inputs.len() > 2 {
+            let terms: Vec<_> = (0..inputs.len())
+                .map(|i| {
+                    let factors: Vec<_> = inputs
+                        .iter()
+                        .enumerate()
+                        .map(|(j, input)| match i == j {
+                            true => input.to_string(),
+                            false => format!("~{input}"),
+                        })
+                        .collect();
+                    format!("({})", factors.join(" & "))
+                })
+                .collect();
+            terms.join(" | ")
+        } else if output.width <= 1 {
+            let name = names.unique(format!("g{}", self.circuit.id));
+            let _ = writeln!(
+                out,
+                "    {ty} {name} ({}, {});",
+                output.name,
+                inputs.join(", ")
+            );
+            return;
+        } else {
+            let op = match ty {
+                "and" | "nand" => " & ",
+                "or" | "nor" => " | ",
+                _ => " ^ ",
+            };
+            inputs.join(op)
+        };
+
+        let expr = match inverted {
+            true => format!("~({expr})"),
+            false => expr,
+        };
+        let _ = writeln!(out, "    assign {} = {expr};", output.name);
+    }
+
+    fn export_bundler(&self, out: &mut String) {
+        let bundler = Bundler::new(self.circuit, self.pins);
+        let bundle = crate::unwrap_option_or_return!(bundler.bundle.map(|net| &self.nets[net]));
+        let pre = bundler.pre.map(|net| &self.nets[net]);
+        let rem = bundler
+            .rem
+            .map(|net| &self.nets[net])
+            .filter(|net| net.width > 0);
+
+        match bundler.dir {
+            BundlerDirecion::Input => {
+                let mut parts: Vec<_> = rem.map(|rem| rem.name.clone()).into_iter().collect();
+                parts.extend(
+                    bundler
+                        .io
+                        .iter()
+                        .rev()
+                        .map(|(_, net)| self.nets[*net].name.clone()),
+                );
+                parts.extend(pre.map(|pre| pre.slice(0, bundler.offset)));
+                if !parts.is_empty() {
+                    let _ = writeln!(out, "    assign {} = {};", bundle.name, concat(parts));
+                }
+            }
+            BundlerDirecion::Output => {
+                if let Some(pre) = pre {
+                    let _ = writeln!(
+                        out,
+                        "    assign {} = {};",
+                        pre.name,
+                        bundle.slice(0, bundler.offset)
+                    );
+                }
+                if let Some(rem) = rem {
+                    let slice = bundle.slice(bundler.base(), rem.width);
+                    let _ = writeln!(out, "    assign {} = {slice};", rem.name);
+                }
+                for (bit, net) in bundler.io.iter() {
+                    let _ = writeln!(
+                        out,
+                        "    assign {} = {};",
+                        self.nets[*net].name,
+                        bundle.bit(*bit)
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Exports a board and every board it contains as structural Verilog, one module per board,
+/// inner modules first
+pub fn export(board: &Arc<CircuitBoard>, errors: &mut ErrorList) -> String {
+    let mut exporter = Exporter {
+        modules: HashMap::new(),
+        module_names: Names::default(),
+        out: String::new(),
+        errors,
+    };
+    exporter.module(board);
+    exporter.out
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{error::ErrorList, io::test_boards};
+
+    const HIERARCHY: &str = include_str!("corpus/verilog_hierarchy.v");
+
+    #[test]
+    fn hierarchy_matches_golden_output() {
+        let board = test_boards::hierarchy();
+        let mut errors = ErrorList::new();
+        let verilog = super::export(&board, &mut errors);
+
+        assert!(errors.is_empty());
+        assert_eq!(verilog, HIERARCHY);
+    }
+
+    #[test]
+    fn names_are_sanitized() {
+        assert_eq!(super::sanitize(" my pin "), "my_pin");
+        assert_eq!(super::sanitize("4bit"), "_4bit");
+        assert_eq!(super::sanitize(""), "_");
+        assert_eq!(super::sanitize("wire"), "wire_");
+    }
+}
@@ -15,7 +15,7 @@ use eframe::{
 };
 use emath::{vec2, Rect};
 
-use error::{ErrorList, OptionReport};
+use error::ErrorList;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_intermediate::Intermediate;
 
@@ -57,6 +57,7 @@ mod ext;
 mod graphics;
 mod io;
 mod path;
+mod routing;
 mod time;
 
 #[cfg(feature = "wasm")]
@@ -294,6 +295,22 @@ impl PanAndZoom {
         Self { center_pos, scale }
     }
 
+    /// Centers view on a world area, zooming so that it takes about half of the screen
+    pub fn focus(&mut self, screen_rect: Rect, pos: Vec2f, size: Vec2f) {
+        let size = Vec2f::from([size.x.max(1.0), size.y.max(1.0)]);
+        let scale = Vec2f::from(screen_rect.size()) / (size * 2.0);
+        self.center_pos = pos + size / 2.0;
+        self.scale = scale.x.min(scale.y).clamp(2.0, 64.0);
+    }
+
+    /// Centers view on a world area, zooming so that it fills the screen
+    pub fn fit(&mut self, screen_rect: Rect, area: Rect) {
+        let area_screen = self.to_screen(screen_rect).world_to_screen_rect(area);
+        let ratio = screen_rect.size() * 0.9 / area_screen.size().max(vec2(1.0, 1.0));
+        self.center_pos = area.center().into();
+        self.scale = (self.scale * ratio.min_elem()).clamp(0.25, 64.0);
+    }
+
     pub fn to_screen(self, screen_rect: Rect) -> Screen {
         let tl_pos = self.center_pos - (screen_rect.size() / 2.0 / self.scale);
         Screen {
@@ -871,14 +888,7 @@ impl PastePreview {
         let circuits: Vec<_> = data
             .circuits
             .into_iter()
-            .filter_map(|d| {
-                ctx.previews
-                    .get(&d.ty)
-                    .report_none(&mut errors, || {
-                        format!("circuit {} does not exist", d.ty.deref())
-                    })
-                    .and_then(|p| p.load_copy(&d, ctx, &mut errors).map(|b| (d, b)))
-            })
+            .filter_map(|d| CircuitPreview::from_copy(&d, ctx, &mut errors).map(|b| (d, b)))
             .collect();
 
         let size = {
@@ -936,10 +946,14 @@ impl PastePreview {
         }
     }
 
+    fn can_place(&self, board: &EditableCircuitBoard, pos: Vec2i) -> bool {
+        self.circuits.iter().all(|(c, p)| {
+            board.can_place_circuit_at(p.describe().size, pos + c.pos.convert(|v| v as i32), None)
+        })
+    }
+
     fn place(&self, board: &mut EditableCircuitBoard, pos: Vec2i, errors: &mut ErrorList) {
-        if self.circuits.iter().any(|(c, p)| {
-            !board.can_place_circuit_at(p.describe().size, pos + c.pos.convert(|v| v as i32), None)
-        }) {
+        if !self.can_place(board, pos) {
             return;
         }
 
@@ -972,30 +986,38 @@ impl PastePreview {
                 data,
                 &mut |board, id| {
                     if let Some(circuit) = board.board.circuits.read().get(id).cloned() {
-                        if !matches!(
-                            circuit_data.internal,
-                            serde_intermediate::Intermediate::Unit
-                        ) {
-                            for state in board.board.states.states.read().iter() {
-                                let ctx = CircuitStateContext::new(state.clone(), circuit.clone());
-                                let mut errors = errors.enter_context(|| {
-                                    format!(
-                                        "loading state for {} {} at {},{}",
-                                        circuit.ty.deref(),
-                                        circuit.id,
-                                        circuit.pos.x,
-                                        circuit.pos.y
-                                    )
-                                });
-                                state.write_circuit(id, |state| {
-                                    state.internal = circuit.imp.write().load_internal(
-                                        &ctx,
-                                        &circuit_data.internal,
-                                        true,
-                                        &mut errors,
-                                    );
-                                });
+                        for state in board.board.states.states.read().iter() {
+                            // Circuits put back after removal get their own state in every state
+                            let restored = circuit_data
+                                .state_internals
+                                .iter()
+                                .find(|(id, _)| *id == state.id);
+                            let (internal, paste) = match restored {
+                                Some((_, internal)) => (internal, false),
+                                None => (&circuit_data.internal, true),
+                            };
+                            if matches!(internal, serde_intermediate::Intermediate::Unit) {
+                                continue;
                             }
+
+                            let ctx = CircuitStateContext::new(state.clone(), circuit.clone());
+                            let mut errors = errors.enter_context(|| {
+                                format!(
+                                    "loading state for {} {} at {},{}",
+                                    circuit.ty.deref(),
+                                    circuit.id,
+                                    circuit.pos.x,
+                                    circuit.pos.y
+                                )
+                            });
+                            state.write_circuit(id, |state| {
+                                state.internal = circuit.imp.write().load_internal(
+                                    &ctx,
+                                    internal,
+                                    paste,
+                                    &mut errors,
+                                );
+                            });
                         }
                     }
                 },
@@ -1059,6 +1081,12 @@ impl Debug for ArcString {
     }
 }
 
+impl PartialEq for ArcString {
+    fn eq(&self, other: &Self) -> bool {
+        self.get_str().deref() == other.get_str().deref()
+    }
+}
+
 impl Serialize for ArcString {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -0,0 +1,181 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::{
+    board::EditableCircuitBoard,
+    vector::Vec2i,
+    wires::{TileWires, WirePart},
+    Direction4,
+};
+
+/// How far outside of the start and end bounding box routes may go, in tiles
+const SEARCH_MARGIN: i32 = 32;
+
+/// Upper limit of visited tiles, routing fails after reaching it
+const SEARCH_LIMIT: usize = 200_000;
+
+/// Extra cost of a bend when fewer bends are preferred
+const BEND_COST: u32 = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct RoutingOptions {
+    /// Don't cross existing wires, even without connecting to them
+    pub avoid_wires: bool,
+    pub prefer_fewer_bends: bool,
+}
+
+impl Default for RoutingOptions {
+    fn default() -> Self {
+        Self {
+            avoid_wires: true,
+            prefer_fewer_bends: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Free,
+    /// Existing wire can be crossed in a straight line without connecting to it
+    Crossing {
+        vertical: bool,
+    },
+    Blocked,
+}
+
+/// Finds an orthogonal path from `from` to `to` avoiding circuits and connections to unrelated wires.
+/// Uses A* over board tiles, returned parts go from `from` to `to`
+pub fn route(
+    board: &EditableCircuitBoard,
+    from: Vec2i,
+    to: Vec2i,
+    options: RoutingOptions,
+) -> Option<Vec<WirePart>> {
+    if from == to {
+        return None;
+    }
+
+    let min = Vec2i::from([from.x.min(to.x), from.y.min(to.y)]) - SEARCH_MARGIN;
+    let max = Vec2i::from([from.x.max(to.x), from.y.max(to.y)]) + SEARCH_MARGIN;
+    let in_bounds = |p: Vec2i| p.x >= min.x && p.y >= min.y && p.x <= max.x && p.y <= max.y;
+
+    let mut tiles = HashMap::new();
+    let mut tile = |pos: Vec2i| {
+        *tiles
+            .entry(pos)
+            .or_insert_with(|| classify_tile(board, pos, options))
+    };
+
+    let bend_cost = if options.prefer_fewer_bends {
+        BEND_COST
+    } else {
+        0
+    };
+    let heuristic = |p: Vec2i| p.x.abs_diff(to.x) + p.y.abs_diff(to.y);
+
+    type State = (Vec2i, Option<Direction4>);
+    // Queue entries point into `states`, as states themselves aren't ordered
+    let mut states: Vec<State> = vec![];
+    let mut queue = BinaryHeap::new();
+    let mut costs: HashMap<State, u32> = HashMap::new();
+    let mut came_from: HashMap<State, State> = HashMap::new();
+
+    let start: State = (from, None);
+    costs.insert(start, 0);
+    states.push(start);
+    queue.push(Reverse((heuristic(from), 0u32, 0usize)));
+
+    let mut end = None;
+    while let Some(Reverse((_, cost, index))) = queue.pop() {
+        if costs.len() > SEARCH_LIMIT {
+            return None;
+        }
+        let state = states[index];
+        let (pos, dir) = state;
+        if pos == to {
+            end = Some(state);
+            break;
+        }
+        if costs.get(&state).is_some_and(|c| *c < cost) {
+            continue;
+        }
+
+        let crossing = pos != from && matches!(tile(pos), Tile::Crossing { .. });
+        for next_dir in Direction4::iter_all() {
+            if dir.is_some_and(|d| d == next_dir.inverted()) {
+                continue;
+            }
+            // Bending on a crossing would connect to the crossed wire
+            if crossing && dir != Some(next_dir) {
+                continue;
+            }
+
+            let next = next_dir.move_vector(pos, 1);
+            if !in_bounds(next) {
+                continue;
+            }
+            if next != to {
+                match tile(next) {
+                    Tile::Free => {}
+                    Tile::Crossing { vertical } if vertical == next_dir.is_horizontal() => {}
+                    Tile::Crossing { .. } | Tile::Blocked => continue,
+                }
+            }
+
+            let bend = dir.is_some_and(|d| d != next_dir);
+            let next_cost = cost + 1 + if bend { bend_cost } else { 0 };
+            let next_state = (next, Some(next_dir));
+            if costs.get(&next_state).is_some_and(|c| *c <= next_cost) {
+                continue;
+            }
+            costs.insert(next_state, next_cost);
+            came_from.insert(next_state, state);
+            states.push(next_state);
+            queue.push(Reverse((
+                next_cost + heuristic(next),
+                next_cost,
+                states.len() - 1,
+            )));
+        }
+    }
+
+    let mut path = vec![];
+    let mut state = end?;
+    loop {
+        path.push(state);
+        state = crate::unwrap_option_or_break!(came_from.get(&state).copied());
+    }
+    path.reverse();
+
+    let mut parts = vec![];
+    let mut run_start = from;
+    for (i, (pos, dir)) in path.iter().enumerate() {
+        let next_dir = path.get(i + 1).and_then(|(_, d)| *d);
+        if i > 0 && next_dir != *dir {
+            parts.extend(EditableCircuitBoard::calc_wire_part(
+                Some(run_start),
+                Some(*pos),
+            ));
+            run_start = *pos;
+        }
+    }
+    Some(parts)
+}
+
+fn classify_tile(board: &EditableCircuitBoard, pos: Vec2i, options: RoutingOptions) -> Tile {
+    let circuit = board
+        .circuit_nodes
+        .get(pos.convert(|v| v as isize))
+        .is_some_and(|n| n.circuit.is_some());
+    if circuit {
+        return Tile::Blocked;
+    }
+
+    match board.wires_at(pos) {
+        TileWires::None => Tile::Free,
+        TileWires::One { vertical, .. } if !options.avoid_wires => Tile::Crossing { vertical },
+        TileWires::One { .. } | TileWires::Two { .. } | TileWires::Point { .. } => Tile::Blocked,
+    }
+}
@@ -92,6 +92,42 @@ impl WireState {
         }
     }
 
+    /// Creates a state from lowest `bits` bits of `value`<br>
+    /// Single bit values create single states, larger ones create bundles with first state being the lowest bit
+    pub fn from_bits(value: u64, bits: u32) -> WireState {
+        if bits <= 1 {
+            return (value & 1 != 0).into();
+        }
+
+        let mut bundle = PooledStateVec::new();
+        bundle.extend((0..bits.min(64)).map(|i| WireState::from(value & (1 << i) != 0)));
+        WireState::Bundle(Arc::new(bundle))
+    }
+
+    /// Reads state as an integer with first state being the lowest bit<br>
+    /// Returns `None` if any of the states isn't True or False, or if bundle is longer than 64 bits
+    pub fn to_bits(&self) -> Option<u64> {
+        match self {
+            WireState::True => Some(1),
+            WireState::False => Some(0),
+            WireState::None | WireState::Error => None,
+            WireState::Bundle(bundle) => {
+                if bundle.len() > 64 {
+                    return None;
+                }
+                let mut value = 0;
+                for (i, state) in bundle.iter().enumerate() {
+                    match state {
+                        WireState::True => value |= 1 << i,
+                        WireState::False => {}
+                        _ => return None,
+                    }
+                }
+                Some(value)
+            }
+        }
+    }
+
     pub fn as_single(&self) -> Result<SingleWireState, &Arc<PooledStateVec>> {
         match self {
             Self::None => Ok(SingleWireState::None),
@@ -339,6 +375,9 @@ impl CircuitState {
     }
 
     pub fn save(&self) -> crate::io::CircuitStateData {
+        if let Some(data) = missing::MissingCircuit::saved_state(self) {
+            return data;
+        }
         crate::io::CircuitStateData {
             pins: self.pins.inner.clone(),
             pin_dirs: self.pin_dirs.inner.clone(),
@@ -355,10 +394,15 @@ impl CircuitState {
         circ: &CircuitStateContext,
         errors: &mut ErrorList,
     ) -> Self {
+        let missing = circ
+            .circuit
+            .read_imp(|_: &missing::MissingCircuit| ())
+            .is_some();
         Self {
             pins: FixedVec::from_option_vec(data.pins.clone()),
             pin_dirs: FixedVec::from_option_vec(data.pin_dirs.clone()),
             internal: match &data.internal {
+                _ if missing => Some(missing::MissingCircuit::load_state(data)),
                 serde_intermediate::Intermediate::Unit => None,
                 data => circ
                     .circuit
@@ -28,6 +28,8 @@ use crate::{
 use super::{
     custom_rect_editor,
     drawing::{self, align_rect_scaled},
+    history::{History, HistoryAction, HistoryEntry},
+    keybinds::{EditorAction, Keybinds},
     rect_editor, rect_properties_editor,
     selection::{
         Selection, SelectionImpl,
@@ -201,6 +203,18 @@ impl SelectionImpl for DesignerSelectionImpl {
     }
 }
 
+/// Design contents before and after an edit
+struct DesignEdit {
+    before: CircuitDesign,
+    after: CircuitDesign,
+}
+
+impl HistoryEntry for DesignEdit {
+    fn memory_size(&self) -> usize {
+        self.before.memory_size() + self.after.memory_size()
+    }
+}
+
 pub struct Designer {
     storage: Arc<RwLock<CircuitDesignStorage>>,
     provider: Box<dyn DesignProvider>,
@@ -213,6 +227,10 @@ pub struct Designer {
     selected_pin_dir: Option<Direction4>,
     default_rect_visuals: Arc<RwLock<RectVisuals>>,
     rect_drag_start: Option<Vec2f>,
+
+    history: History<DesignEdit>,
+    /// Design contents after the last recorded edit
+    edit_start: Option<CircuitDesign>,
 }
 
 impl Designer {
@@ -260,21 +278,45 @@ impl Designer {
             selected_pin_dir: None,
             default_rect_visuals: rect_visuals,
             rect_drag_start: None,
+            history: History::default(),
+            edit_start: None,
         }
     }
 
-    pub fn background_update(&mut self, style: &Style, ui: &mut Ui) {
+    pub fn background_update(&mut self, style: &Style, keybinds: &Keybinds, ui: &mut Ui) {
         let rect = ui.max_rect();
 
         self.pan_zoom.update(ui, rect, self.selected_id.is_none());
 
+        let actions = ui.input(|input| keybinds.pressed_actions(input));
+        let pressed = |action| actions.contains(&action);
+
         let designs = self.storage.clone();
         let mut designs = designs.write();
         let design = designs.current_mut();
 
         #[allow(clippy::collapsible_if)]
         if !ui.ctx().wants_keyboard_input() {
-            if ui.input(|input| input.key_pressed(Key::R)) {
+            let history_action = if pressed(EditorAction::Undo) {
+                Some(HistoryAction::Undo)
+            } else if pressed(EditorAction::Redo) {
+                Some(HistoryAction::Redo)
+            } else {
+                None
+            };
+            if let Some(action) = history_action {
+                let contents = match action {
+                    HistoryAction::Undo => self.history.undo().map(|e| &e.before),
+                    HistoryAction::Redo => self.history.redo().map(|e| &e.after),
+                };
+                if let Some(contents) = contents {
+                    *design = contents.clone().with_id(design.id());
+                    self.edit_start = Some(design.clone());
+                    self.selection.clear();
+                }
+            }
+
+            if pressed(EditorAction::Rotate) {
                 if self.selection.selection.is_empty() {
                     if let Some(dir) = &mut self.selected_pin_dir {
                         *dir = dir.rotate_clockwise();
@@ -290,7 +332,7 @@ impl Designer {
                 }
             }
 
-            if ui.input(|input| input.key_pressed(Key::Delete)) {
+            if pressed(EditorAction::Delete) {
                 for i in (0..design.pins.len()).rev() {
                     if self.selection.contains(&SelectedDesignObject::Pin(i)) {
                         design.pins.remove(i);
@@ -714,6 +756,7 @@ impl Designer {
 
         self.components_ui(style, ui, design);
         self.properties_ui(ui, design);
+        self.record_edits(ui, design);
 
         drop(designs);
 
@@ -756,6 +799,30 @@ impl Designer {
         DesignerResponse { close }
     }
 
+    /// Records design changes once user stops interacting with it
+    fn record_edits(&mut self, ui: &Ui, design: &CircuitDesign) {
+        let before = match self.edit_start.take() {
+            Some(before) => before,
+            None => {
+                self.edit_start = Some(design.clone());
+                return;
+            }
+        };
+
+        let interacting =
+            ui.input(|input| input.pointer.any_down()) || ui.ctx().wants_keyboard_input();
+        if interacting || before.same_contents(design) {
+            self.edit_start = Some(before);
+            return;
+        }
+
+        self.history.push(DesignEdit {
+            before,
+            after: design.clone(),
+        });
+        self.edit_start = Some(design.clone());
+    }
+
     fn draw_pin(
         &self,
         dir: InternalPinDirection,
@@ -789,7 +856,7 @@ impl Designer {
             };
 
             crate::graphics::outside_pin(
-                ctx.style.wire_colors.false_color(),
+                ctx.style.pin_color(),
                 display_dir.is_some(),
                 pico,
                 angle,
@@ -854,7 +921,7 @@ impl Designer {
                                             let angle =
                                                 if pico == Some(true) { TAU * 0.5 } else { 0.0 };
                                             crate::graphics::outside_pin(
-                                                paint_ctx.style.wire_colors.false_color(),
+                                                paint_ctx.style.pin_color(),
                                                 true,
                                                 pico,
                                                 angle,
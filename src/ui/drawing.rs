@@ -45,17 +45,8 @@ pub fn draw_grid(
     let start = (pos / cell_size).convert(|v| v as i32);
     let off = pos % cell_size;
 
-    let dim_color = match style.egui_style.visuals.dark_mode {
-        true => Color32::from_gray(64),
-        false => Color32::from_gray(192),
-    };
-    let highlight_color = match style.egui_style.visuals.dark_mode {
-        true => Color32::from_gray(96),
-        false => Color32::from_gray(160),
-    };
-
-    let dim_stroke = Stroke::new(1.0, dim_color);
-    let highlight_stroke = Stroke::new(1.5, highlight_color);
+    let dim_stroke = Stroke::new(1.0, style.grid_color());
+    let highlight_stroke = Stroke::new(1.5, style.grid_highlight_color());
 
     for i in 0..visible_cells.x {
         let x = i + start.x;
@@ -132,12 +123,7 @@ pub fn draw_cross(screen: &Screen, style: &Style, bounds: Rect, paint: &egui::Pa
     cross_pos.y = cross_pos.y.clamp(bounds.top(), bounds.bottom());
 
     let unit = Vec2f::single_value(screen.scale);
-    let cross_color = match style.egui_style.visuals.dark_mode {
-        true => Color32::WHITE,
-        false => Color32::from_gray(32),
-    };
-
-    let cross_stroke = Stroke::new(2.0, cross_color);
+    let cross_stroke = Stroke::new(2.0, style.origin_cross_color());
 
     paint.line_segment(
         [
@@ -239,7 +225,7 @@ pub fn draw_pin_names<'a>(
             visual.window_fill.linear_multiply(0.6),
             visual.window_stroke,
         );
-        let color = visual.text_color();
+        let color = ctx.style.pin_label_color();
         ctx.paint.add(TextShape {
             pos: textpos,
             galley,
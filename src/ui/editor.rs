@@ -17,30 +17,39 @@ use crate::{
         SelectedBoardObject, SelectedItem, StoredCircuitBoard,
     },
     circuits::{
+        board::{Board, BoardPreview, BoardState},
         props::{CircuitPropertyImpl, CircuitPropertyStore},
-        CircuitNode, CircuitPreview, CircuitStateContext,
+        probe::{format_state, ValueFormat},
+        CircuitNode, CircuitPin, CircuitPreview, CircuitStateContext, PinDirection,
     },
     containers::ChunksLookaround,
-    error::{ErrorList, ResultReport},
+    error::{ErrorList, OptionReport, ResultReport},
+    io::render::CapturedImage,
     ext::IteratorEqExt,
     string::StringFormatterState,
-    vector::{Vec2f, Vec2i},
-    wires::{WireNode, WirePart, WireColors},
+    vector::{Vec2f, Vec2i, Vec2u},
+    wires::{TileWires, Wire, WireNode, WirePart, WireColors},
+    routing::{self, RoutingOptions},
     ArcString, Direction2, Direction4, DynStaticStr, PaintContext, PanAndZoom, PastePreview,
-    Screen,
+    Screen, State,
 };
 
 use super::{
     designer::Designer,
     drawing,
+    history::{self, BoardEdit, BoardObjects, History, HistoryAction},
+    keybinds::{EditorAction, Keybinds, KeybindsWindow},
+    search::{SearchPalette, SearchTarget},
     selection::{Selection, SelectionInventoryItem},
     side_panel::{PanelSide, SidePanel},
+    transform::GroupTransform,
     DoubleSelectableLabel, Inventory, InventoryItem, InventoryItemGroup, PropertyEditor,
 };
 
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub enum SelectedItemId {
     Wires,
+    Router,
     Selection,
     Paste,
     Circuit(DynStaticStr),
@@ -91,6 +100,70 @@ struct InventoryItemDrawData {
     false_color_override: Option<Color32>
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ImageExportTarget {
+    Board,
+    Selection,
+    /// Outer view of the board, as seen from boards containing it
+    Design,
+}
+
+struct ImageExportOptions {
+    grid: bool,
+    pin_names: bool,
+    /// Image points per tile
+    scale: f32,
+}
+
+impl Default for ImageExportOptions {
+    fn default() -> Self {
+        Self {
+            grid: false,
+            pin_names: true,
+            scale: 16.0,
+        }
+    }
+}
+
+/// View and edit state of an open board tab.
+/// State of the active tab is kept in the editor itself
+struct BoardTab {
+    board: EditableCircuitBoard,
+    pan_zoom: PanAndZoom,
+    history: History<BoardEdit>,
+    selection: Selection<BoardObjectSelectionImpl>,
+}
+
+impl BoardTab {
+    fn new(board: EditableCircuitBoard) -> Self {
+        Self {
+            board,
+            pan_zoom: PanAndZoom::default(),
+            history: History::default(),
+            selection: Selection::default(),
+        }
+    }
+}
+
+/// Router start, end and options
+type RouteKey = (Vec2i, Vec2i, RoutingOptions);
+
+/// Selected objects being dragged to a new position
+struct SelectionDrag {
+    start: Vec2i,
+    origin: Vec2i,
+    paste: PastePreview,
+    /// Points where selected objects are connected to wires outside of the selection
+    anchors: Vec<(Vec2i, WireColors)>,
+}
+
+/// Selected circuits and tiles of selected wires
+#[derive(Default)]
+struct ImageExportFilter {
+    circuits: HashSet<usize>,
+    wire_tiles: HashSet<Vec2i>,
+}
+
 pub struct CircuitBoardEditor {
     pan_zoom: PanAndZoom,
     pub board: EditableCircuitBoard,
@@ -107,7 +180,22 @@ pub struct CircuitBoardEditor {
 
     wire_drag_pos: Option<Vec2i>,
     wire_colors: WireColors,
+    /// Pin where the route being drawn starts
+    route_start: Option<Vec2i>,
+    routing: RoutingOptions,
+    /// Last route by its start, end and options, as routing is too slow to run every frame
+    route_cache: Option<(RouteKey, Option<Vec<WirePart>>)>,
     pub selection: Selection<BoardObjectSelectionImpl>,
+    selection_drag: Option<SelectionDrag>,
+    image_export: ImageExportOptions,
+    history: History<BoardEdit>,
+    search: SearchPalette,
+    show_minimap: bool,
+    pub keybinds: Keybinds,
+    keybinds_window: KeybindsWindow,
+    /// Inactive tabs, active tab's slot is always empty
+    tabs: Vec<Option<BoardTab>>,
+    active_tab: usize,
 }
 
 static INVENTORY_CIRCUIT_ORDER: &[&str] = &["or", "nor", "and", "nand", "xor", "xnor", "not"];
@@ -168,6 +256,27 @@ impl InventoryItem<SelectedItemId, InventoryItemDrawData> for WireInventoryItem
     }
 }
 
+struct RouterInventoryItem {}
+impl InventoryItem<SelectedItemId, InventoryItemDrawData> for RouterInventoryItem {
+    fn id(&self) -> SelectedItemId {
+        SelectedItemId::Router
+    }
+
+    fn draw(&self, pass: &InventoryItemDrawData, ctx: &PaintContext) {
+        let color = pass.false_color_override.unwrap_or_else(|| ctx.style.wire_colors.false_color());
+
+        let points = [[0.2, 0.2], [0.5, 0.2], [0.5, 0.8], [0.8, 0.8]]
+            .map(|p| ctx.rect.lerp_inside(p.into()));
+        ctx.paint
+            .add(Shape::line(points.to_vec(), Stroke::new(2.5, color)));
+
+        for point in [points[0], points[3]] {
+            ctx.paint
+                .circle_filled(point, ctx.rect.width() * 0.08, color);
+        }
+    }
+}
+
 struct CircuitInventoryItem {
     preview: Arc<CircuitPreview>,
     id: DynStaticStr,
@@ -245,6 +354,7 @@ impl CircuitBoardEditor {
                     SelectedItemId::Selection,
                 ))),
                 InventoryItemGroup::SingleItem(Box::new(WireInventoryItem {})),
+                InventoryItemGroup::SingleItem(Box::new(RouterInventoryItem {})),
                 InventoryItemGroup::Group(inventory_group),
             ]
             .into(),
@@ -252,8 +362,20 @@ impl CircuitBoardEditor {
             props_ui: PropertyEditor::new(),
             sim: ctx.clone(),
             wire_drag_pos: None,
+            route_start: None,
+            routing: Default::default(),
+            route_cache: None,
             wire_colors: Default::default(),
             selection: Selection::default(),
+            selection_drag: None,
+            image_export: Default::default(),
+            history: History::default(),
+            search: SearchPalette::default(),
+            show_minimap: true,
+            keybinds: Keybinds::default(),
+            keybinds_window: KeybindsWindow::default(),
+            tabs: vec![None],
+            active_tab: 0,
         }
     }
 
@@ -261,7 +383,35 @@ impl CircuitBoardEditor {
         let rect = ui.max_rect();
         self.pan_zoom.update(ui, rect, self.selected_id.is_none());
 
-        if !ui.ctx().wants_keyboard_input() {
+        let keyboard = !ui.ctx().wants_keyboard_input() && !self.keybinds_window.is_capturing();
+        let actions = match keyboard {
+            true => ui.input(|input| self.keybinds.pressed_actions(input)),
+            false => vec![],
+        };
+        let pressed = |action| actions.contains(&action);
+
+        if keyboard {
+            if pressed(EditorAction::Undo) {
+                self.apply_history(HistoryAction::Undo);
+            } else if pressed(EditorAction::Redo) {
+                self.apply_history(HistoryAction::Redo);
+            }
+
+            if pressed(EditorAction::Search) {
+                self.search.open();
+            }
+            if pressed(EditorAction::ShowKeybinds) {
+                self.keybinds_window.toggle();
+            }
+
+            if pressed(EditorAction::ZoomToFit) {
+                self.zoom_to_fit(rect);
+            } else if pressed(EditorAction::ZoomToSelection) {
+                self.zoom_to_selection(rect);
+            } else if pressed(EditorAction::ToggleMinimap) {
+                self.show_minimap = !self.show_minimap;
+            }
+
             cfg_if::cfg_if! {
                 if #[cfg(all(not(web_sys_unstable_apis), feature = "wasm"))] {
                     let paste = ui
@@ -312,67 +462,21 @@ impl CircuitBoardEditor {
                         });
                     }
                 }
-                if copy || cut {
-                    let min_pos = {
-                        let mut min_pos = None;
-                        for obj in self.selection.selection.iter() {
-                            let pos = match obj {
-                                SelectedBoardObject::WirePart { pos, dir } => {
-                                    let node = self.board.find_wire_node(*pos, (*dir).into());
-                                    let node = unwrap_option_or_continue!(node);
-                                    node.pos
-                                }
-                                SelectedBoardObject::Circuit { id } => {
-                                    let pos =
-                                        self.board.board.circuits.read().get(*id).map(|c| c.pos);
-                                    unwrap_option_or_continue!(pos)
-                                }
-                            };
-                            min_pos = match min_pos {
-                                None => Some(pos),
-                                Some(mp) => Some([mp.x.min(pos.x), mp.y.min(pos.y)].into()),
-                            }
-                        }
-                        min_pos
-                    };
-                    if let Some(min_pos) = min_pos {
-                        let mut copy = crate::io::CopyPasteData::default();
-                        for obj in self.selection.selection.iter() {
-                            match obj {
-                                SelectedBoardObject::WirePart { pos, dir } => {
-                                    if let Some(w) = self.board.find_wire_node(*pos, (*dir).into())
-                                    {
-                                        let colors = self.board.board.wires.read().get(w.wire).map(|w| w.colors).unwrap_or_default();
-                                        copy.wires.push(crate::io::WirePartCopyData {
-                                            pos: (*pos - min_pos).convert(|v| v as u32),
-                                            length: w.distance.get(),
-                                            colors,
-                                            dir: *dir,
-                                        })
-                                    }
-                                }
-                                SelectedBoardObject::Circuit { id } => {
-                                    let circuits = self.board.board.circuits.read();
-                                    if let Some(circuit) = circuits.get(*id) {
-                                        let pos = (circuit.pos - min_pos).convert(|v| v as u32);
-                                        copy.circuits
-                                            .push(circuit.copy(pos, self.board.state.as_ref()))
-                                    }
-                                }
-                            }
-                        }
-                        cfg_if::cfg_if! {
-                            if #[cfg(all(not(web_sys_unstable_apis), feature = "wasm"))] {
-                                *crate::io::GLOBAL_CLIPBOARD.lock() = Some(copy);
-                            } else {
-                                let copy_text = ron::to_string(&copy).unwrap();
-                                ui.output_mut(|output| output.copied_text = copy_text);
-                            }
+                let delete = cut || pressed(EditorAction::Delete);
+                let objects = (copy || delete).then(|| self.selection_objects()).flatten();
+                if let (true, Some(objects)) = (copy || cut, &objects) {
+                    let copy = objects.data.clone().for_clipboard();
+                    cfg_if::cfg_if! {
+                        if #[cfg(all(not(web_sys_unstable_apis), feature = "wasm"))] {
+                            *crate::io::GLOBAL_CLIPBOARD.lock() = Some(copy);
+                        } else {
+                            let copy_text = ron::to_string(&copy).unwrap();
+                            ui.output_mut(|output| output.copied_text = copy_text);
                         }
                     }
                 }
 
-                if cut || ui.input(|input| input.key_pressed(egui::Key::Delete)) {
+                if delete {
                     let mut affected_wires = HashSet::new();
                     let drain = self.selection.selection.drain().collect::<Vec<_>>();
 
@@ -396,45 +500,47 @@ impl CircuitBoardEditor {
                     for wire in affected_wires {
                         self.board.board.states.update_wire(wire, true);
                     }
-                    drop(sim_lock)
+                    drop(sim_lock);
+
+                    if let Some(objects) = objects {
+                        self.history.push(BoardEdit::Removed(objects));
+                    }
                 }
             }
 
-            if ui.input(|input| input.key_pressed(Key::F9)) {
+            if pressed(EditorAction::ToggleDebug) {
                 self.debug = !self.debug;
-            } else if ui.input(|input| input.key_pressed(Key::F8)) {
+            } else if pressed(EditorAction::ReloadBoard) {
                 let state = self.board.state.clone();
                 self.board = EditableCircuitBoard::new(state);
-            } else if ui.input(|input| input.key_pressed(Key::F2)) {
-                if ui.input(|input| input.modifiers.shift) {
-                    for board in self.board.board.ctx.boards.read().values() {
-                        for state in board.board.states.states.read().iter() {
-                            state.reset();
-                        }
-                    }
-                    for board in self.board.board.ctx.boards.read().values() {
-                        for state in board.board.states.states.read().iter() {
-                            state.update_everything();
-                        }
-                    }
-                } else if ui.input(|input| input.modifiers.command) {
-                    for state in self.board.board.states.states.read().iter() {
+            } else if pressed(EditorAction::ResetAllStates) {
+                for board in self.board.board.ctx.boards.read().values() {
+                    for state in board.board.states.states.read().iter() {
                         state.reset();
+                    }
+                }
+                for board in self.board.board.ctx.boards.read().values() {
+                    for state in board.board.states.states.read().iter() {
                         state.update_everything();
                     }
-                } else {
-                    let state = &self.board.state;
+                }
+            } else if pressed(EditorAction::ResetBoardStates) {
+                for state in self.board.board.states.states.read().iter() {
                     state.reset();
                     state.update_everything();
                 }
-            } else if ui.input(|input| input.key_pressed(Key::Q)) {
+            } else if pressed(EditorAction::ResetState) {
+                let state = &self.board.state;
+                state.reset();
+                state.update_everything();
+            } else if pressed(EditorAction::ToggleOrderedQueue) {
                 let sim_lock = self.board.board.sim_lock.clone();
                 let sim_lock = sim_lock.write();
 
                 let ordered = self.board.board.is_ordered_queue();
                 self.board.board.set_ordered_queue(!ordered, false);
                 drop(sim_lock);
-            } else if ui.input(|input| input.key_pressed(Key::P)) {
+            } else if pressed(EditorAction::ToggleFrozen) {
                 self.board.state.set_frozen(!self.board.state.is_frozen());
             }
 
@@ -471,9 +577,38 @@ impl CircuitBoardEditor {
         }
         let selected_item = self.selected_item();
 
-        if !ui.ctx().wants_keyboard_input() {
-            if let Some(selected) = &selected_item {
-                if ui.input(|input| input.key_pressed(Key::R)) {
+        if keyboard {
+            let selection_transform = matches!(selected_item, Some(SelectedItem::Selection))
+                && !self.selection.selection.is_empty();
+            if selection_transform {
+                let transform = [
+                    (EditorAction::Rotate, GroupTransform::RotateClockwise),
+                    (
+                        EditorAction::RotateCounterclockwise,
+                        GroupTransform::RotateCounterclockwise,
+                    ),
+                    (EditorAction::Flip, GroupTransform::MirrorHorizontal),
+                    (EditorAction::FlipVertical, GroupTransform::MirrorVertical),
+                ]
+                .into_iter()
+                .find_map(|(action, transform)| pressed(action).then_some(transform));
+                if let Some(transform) = transform {
+                    self.transform_selection(transform);
+                }
+
+                let nudge = [
+                    (EditorAction::NudgeUp, [0, -1]),
+                    (EditorAction::NudgeLeft, [-1, 0]),
+                    (EditorAction::NudgeDown, [0, 1]),
+                    (EditorAction::NudgeRight, [1, 0]),
+                ]
+                .into_iter()
+                .find_map(|(action, offset)| pressed(action).then_some(offset));
+                if let Some(offset) = nudge {
+                    self.move_selection(offset.into());
+                }
+            } else if let Some(selected) = &selected_item {
+                if pressed(EditorAction::Rotate) {
                     self.change_selected_props(selected, "dir", |d: &mut Direction4| {
                         *d = d.rotate_clockwise()
                     });
@@ -483,7 +618,7 @@ impl CircuitBoardEditor {
                     self.swap_selected_size(selected);
                 }
 
-                if ui.input(|input| input.key_pressed(Key::F)) {
+                if pressed(EditorAction::Flip) {
                     self.change_selected_props(selected, "flip", |f: &mut bool| *f = !*f);
                 }
             }
@@ -504,10 +639,12 @@ impl CircuitBoardEditor {
 
         let tile_bounds = self.calc_draw_bounds(&screen);
 
+        let selecting = matches!(&selected_item, Some(SelectedItem::Selection));
+        self.update_selection_drag(&ctx, selecting);
         self.selection.pre_update_selection(
             &self.board,
             &ctx,
-            matches!(&selected_item, Some(SelectedItem::Selection)),
+            selecting && self.selection_drag.is_none(),
         );
 
         ctx.draw_chunks(
@@ -541,8 +678,13 @@ impl CircuitBoardEditor {
         );
 
         self.update_wires(&ctx, matches!(&selected_item, Some(SelectedItem::Wire)));
+        self.update_router(&ctx, matches!(&selected_item, Some(SelectedItem::Router)));
 
         self.draw_hovered_circuit_pin_names(&ctx);
+        self.update_net_highlight(
+            &ctx,
+            matches!(&selected_item, None | Some(SelectedItem::Selection)),
+        );
 
         self.update_previews(&ctx);
         self.selection.update_selection(&self.board, &ctx);
@@ -561,6 +703,8 @@ impl CircuitBoardEditor {
     }
 
     pub fn ui_update(&mut self, style: &Style, ui: &mut Ui) -> EditorResponse {
+        // Same rect board is drawn in by `background_update`
+        let screen_rect = ui.max_rect();
         let components_response = self.components_ui(style, ui);
         self.properties_ui(style, ui);
 
@@ -568,6 +712,8 @@ impl CircuitBoardEditor {
             let rect = crate::ui::side_panel::remaining_rect(ui).shrink(10.0);
             let mut ui = ui.child_ui(rect, *ui.layout());
 
+            self.tabs_ui(&mut ui);
+
             if ui.input(|input| input.key_pressed(Key::Escape))
                 || ui.input(|input| {
                     input.pointer.button_released(PointerButton::Secondary)
@@ -588,6 +734,7 @@ impl CircuitBoardEditor {
                 style,
                 |id| match id {
                     SelectedItemId::Wires => Some("Wires".into()),
+                    SelectedItemId::Router => Some("Wire router".into()),
                     SelectedItemId::Selection => Some("Selection".into()),
                     SelectedItemId::Paste => Some("Paste".into()),
                     SelectedItemId::Circuit(cid) => {
@@ -636,8 +783,36 @@ impl CircuitBoardEditor {
             let text = format!("This board has {states} state(s), {frozen_states} are frozen, {unused_states} will be removed\nCurrent queue length: {queue_len}");
 
             ui.monospace(text);
+
+            ui.horizontal(|ui| {
+                let zoom_fit = ui
+                    .button("Zoom to fit")
+                    .on_hover_text(self.keybinds.format(ui.ctx(), EditorAction::ZoomToFit))
+                    .clicked();
+                if zoom_fit {
+                    self.zoom_to_fit(screen_rect);
+                }
+                let selection = !self.selection.selection.is_empty();
+                let zoom_selection = ui
+                    .add_enabled(selection, egui::Button::new("Zoom to selection"))
+                    .on_hover_text(self.keybinds.format(ui.ctx(), EditorAction::ZoomToSelection))
+                    .clicked();
+                if zoom_selection {
+                    self.zoom_to_selection(screen_rect);
+                }
+                ui.checkbox(&mut self.show_minimap, "Minimap");
+            });
+
+            if self.show_minimap {
+                self.minimap_ui(&ui, rect, screen_rect);
+            }
         }
 
+        if let Some(target) = self.search.ui(ui, &self.sim, &self.board) {
+            self.jump_to(target, ui.max_rect());
+        }
+        self.keybinds_window.ui(ui.ctx(), &mut self.keybinds);
+
         if !self.errors.is_empty() {
             let mut open = true;
             egui::Window::new("Errors")
@@ -692,18 +867,92 @@ impl CircuitBoardEditor {
             self.wire_drag_pos = None;
 
             if let Some(part) = drawing_wire {
-                self.board.place_wire_part(part, true, self.wire_colors);
+                let parts = history::uncovered_wire_parts(&self.board, &part);
+                if let Some(wire) = self.board.place_wire_part(part, true, self.wire_colors) {
+                    let colors = self.board.board.wires.read().get(wire).map(|w| w.colors);
+                    let colors = colors.unwrap_or(self.wire_colors);
+                    if let Some(edit) = BoardEdit::wires_placed(&self.board, parts, colors) {
+                        self.history.push(edit);
+                    }
+                }
             }
         }
 
         if let Some(mouse_pos) = mouse_tile_pos_i {
-            if interaction.clicked_by(egui::PointerButton::Primary) && self.wire_drag_pos.is_none()
+            if interaction.clicked_by(egui::PointerButton::Primary)
+                && self.wire_drag_pos.is_none()
+                && self.board.try_toggle_node_intersection(mouse_pos)
             {
-                self.board.try_toggle_node_intersection(mouse_pos);
+                self.history.push(BoardEdit::IntersectionToggled(mouse_pos));
             }
         }
     }
 
+    fn update_router(&mut self, ctx: &PaintContext, selected: bool) {
+        if !selected {
+            self.route_start = None;
+            return;
+        }
+
+        let mouse_tile_pos = ctx
+            .ui
+            .input(|input| input.pointer.interact_pos())
+            .map(|p| ctx.screen.screen_to_world(Vec2f::from(p)));
+        let mouse_tile_pos = mouse_tile_pos.map(|p| p.convert(|v| v.floor() as i32));
+        let hovered_pin = mouse_tile_pos.filter(|p| self.board.pin_at(*p).is_some());
+
+        let key = self
+            .route_start
+            .zip(hovered_pin)
+            .map(|(start, end)| (start, end, self.routing));
+        if self.route_cache.as_ref().map(|(k, _)| *k) != key {
+            self.route_cache = key.map(|(start, end, options)| {
+                let route = routing::route(&self.board, start, end, options);
+                ((start, end, options), route)
+            });
+        }
+        for pos in self.route_start.iter().chain(hovered_pin.iter()) {
+            let rect = Rect::from_min_size(
+                ctx.screen.world_to_screen_tile(*pos).into(),
+                vec2(ctx.screen.scale, ctx.screen.scale),
+            );
+            ctx.paint.rect_stroke(
+                rect,
+                Rounding::ZERO,
+                Stroke::new(2.0, ctx.style.selection_border_color()),
+            );
+        }
+        let route = self.route_cache.as_ref().and_then(|(_, r)| r.as_ref());
+        for part in route.into_iter().flatten() {
+            self.draw_wire_part(ctx, part, Color32::GRAY);
+        }
+
+        let interaction = ctx.ui.interact(ctx.rect, ctx.ui.id(), Sense::click());
+        if !interaction.clicked_by(PointerButton::Primary) {
+            return;
+        }
+
+        if self.route_start.is_none() {
+            self.route_start = hovered_pin;
+            return;
+        }
+        self.route_start = None;
+
+        let route = unwrap_option_or_return!(self.route_cache.take().and_then(|(_, r)| r));
+        let mut edits = vec![];
+        for part in route {
+            let parts = history::uncovered_wire_parts(&self.board, &part);
+            if let Some(wire) = self.board.place_wire_part(part, true, self.wire_colors) {
+                let colors = self.board.board.wires.read().get(wire).map(|w| w.colors);
+                let colors = colors.unwrap_or(self.wire_colors);
+                edits.extend(BoardEdit::wires_placed(&self.board, parts, colors));
+            }
+        }
+        if let Some(edit) = BoardEdit::group(edits) {
+            self.history.push(edit);
+        }
+    }
+
     fn update_previews(&mut self, ctx: &PaintContext) {
         let selected = self.selected_item();
 
@@ -750,7 +999,7 @@ impl CircuitBoardEditor {
                 ctx.paint.circle_filled(
                     pos.into(),
                     Self::WIRE_THICKNESS * 0.5 * ctx.screen.scale,
-                    ctx.style.wire_colors.false_color(),
+                    ctx.style.pin_color(),
                 );
             }
 
@@ -770,7 +1019,7 @@ impl CircuitBoardEditor {
 
             if interaction.clicked_by(eframe::egui::PointerButton::Primary) {
                 fn empty_handler(_: &mut EditableCircuitBoard, _: usize) {}
-                self.board.place_circuit(
+                let id = self.board.place_circuit(
                     place_pos,
                     true,
                     p.as_ref(),
@@ -779,6 +1028,9 @@ impl CircuitBoardEditor {
                     None,
                     &mut empty_handler,
                 );
+                if let Some(objects) = id.and_then(|id| BoardObjects::new(&self.board, [id], [])) {
+                    self.history.push(BoardEdit::Placed(objects));
+                }
             }
         } else if let SelectedItem::Paste(p) = selected {
             let size = p.size;
@@ -793,7 +1045,9 @@ impl CircuitBoardEditor {
             p.draw(self, place_pos, &ctx.with_rect(rect));
             let interaction = ctx.ui.interact(ctx.rect, ctx.ui.id(), Sense::click());
             if interaction.clicked_by(eframe::egui::PointerButton::Primary) {
-                p.place(&mut self.board, place_pos, &mut self.errors);
+                if let Some(objects) = self.place_paste(&p, place_pos) {
+                    self.history.push(BoardEdit::Placed(objects));
+                }
             }
         }
     }
@@ -842,6 +1096,439 @@ impl CircuitBoardEditor {
         }
     }
 
+    /// Wire under the pointer, either directly or through a hovered circuit pin
+    fn hovered_wire(&self, ctx: &PaintContext) -> Option<usize> {
+        let pos = ctx.ui.input(|input| input.pointer.hover_pos())?;
+        if !ctx.rect.contains(pos) {
+            return None;
+        }
+        let pos = ctx.screen.screen_to_world(Vec2f::from(pos));
+        let tile = pos.convert(|v| v.floor() as i32);
+
+        if let Some(pin) = self.board.pin_at(tile) {
+            return pin.read().wire;
+        }
+        match self.board.wires_at(tile) {
+            TileWires::None => None,
+            TileWires::One { wire, .. } | TileWires::Point { wire, .. } => Some(wire),
+            TileWires::Two {
+                horizontal,
+                vertical,
+            } => {
+                let offset = pos - tile.convert(|v| v as f32) - 0.5;
+                match offset.x.abs() < offset.y.abs() {
+                    true => Some(vertical),
+                    false => Some(horizontal),
+                }
+            }
+        }
+    }
+
+    /// Highlights the whole net under the pointer and shows its state.
+    /// Alt+click selects all of its wire parts
+    fn update_net_highlight(&mut self, ctx: &PaintContext, enabled: bool) {
+        if !enabled {
+            return;
+        }
+        let wire = unwrap_option_or_return!(self.hovered_wire(ctx));
+        let wires = self.board.board.wires.read();
+        let wire = unwrap_option_or_return!(wires.get(wire));
+
+        let parts = self.wire_parts(wire);
+        let pins: Vec<_> = wire
+            .points
+            .iter()
+            .filter_map(|(pos, point)| point.pin.clone().map(|pin| (*pos, pin)))
+            .collect();
+        let id = wire.id;
+        drop(wires);
+
+        let stroke = Stroke::new(2.0, ctx.style.net_highlight_color());
+        for part in parts.iter() {
+            let rect = Self::calc_wire_part_rect(&ctx.screen, part).expand(2.0);
+            ctx.paint.rect_stroke(rect, Rounding::ZERO, stroke);
+        }
+        for (pos, _) in pins.iter() {
+            let rect = Rect::from_min_size(
+                ctx.screen.world_to_screen_tile(*pos).into(),
+                vec2(ctx.screen.scale, ctx.screen.scale),
+            );
+            ctx.paint.rect_stroke(rect, Rounding::ZERO, stroke);
+        }
+
+        let state = self.board.state.get_wire(id);
+        let drivers: Vec<_> = pins
+            .iter()
+            .map(|(_, pin)| pin.read())
+            .filter(|pin| matches!(pin.direction(&self.board.state), PinDirection::Outside))
+            .map(|pin| self.pin_label(&pin))
+            .collect();
+        egui::show_tooltip_at_pointer(ctx.ui.ctx(), Id::new("net_tooltip"), |ui| {
+            ui.label(format!("Net {id}"));
+            ui.label(format!("State: {}", format_state(&state, ValueFormat::Hex)));
+            ui.label(format!("Connected pins: {}", pins.len()));
+            if drivers.is_empty() {
+                ui.label("No drivers");
+            } else {
+                ui.label("Drivers:");
+                for driver in drivers {
+                    ui.label(format!("  {driver}"));
+                }
+            }
+        });
+
+        let select = ctx
+            .ui
+            .input(|input| input.modifiers.alt && input.pointer.primary_clicked());
+        if select {
+            self.selection.clear();
+            self.selection.selection.extend(
+                parts
+                    .iter()
+                    .map(|p| SelectedBoardObject::WirePart { pos: p.pos, dir: p.dir }),
+            );
+            self.selected_id = Some(SelectedItemId::Selection);
+        }
+    }
+
+    /// All parts of a wire, going between its points
+    fn wire_parts(&self, wire: &Wire) -> Vec<WirePart> {
+        let mut parts = vec![];
+        for (pos, point) in wire.points.iter() {
+            for dir in Direction2::iter_all().filter(|d| point.get_dir(*d)) {
+                let length = self
+                    .board
+                    .wire_nodes
+                    .get(pos.convert(|v| v as isize))
+                    .and_then(|n| n.get_dir(dir.into()).get())
+                    .and_then(NonZeroU32::new);
+                let length = unwrap_option_or_continue!(length);
+                parts.push(WirePart {
+                    pos: *pos,
+                    length,
+                    dir,
+                });
+            }
+        }
+        parts
+    }
+
+    /// Switches to a tab with main state of the board, opening a new one if needed
+    pub fn open_board(&mut self, board: Arc<CircuitBoard>) {
+        self.open_state(board.clone().states.get_or_create_main(board));
+    }
+
+    /// Switches to a tab with this state, opening a new one if needed
+    pub fn open_state(&mut self, state: Arc<State>) {
+        let index = (0..self.tabs.len()).find(|i| Arc::ptr_eq(self.tab_state(*i), &state));
+        match index {
+            Some(index) => self.switch_tab(index),
+            None => {
+                let tab = BoardTab::new(EditableCircuitBoard::new(state));
+                self.tabs.push(Some(tab));
+                self.switch_tab(self.tabs.len() - 1);
+            }
+        }
+    }
+
+    /// Closes all tabs and opens main state of the board
+    pub fn reset_tabs(&mut self, board: Arc<CircuitBoard>) {
+        self.tabs = vec![None];
+        self.active_tab = 0;
+        self.swap_tab(BoardTab::new(EditableCircuitBoard::new_main(board)));
+    }
+
+    fn tab_state(&self, index: usize) -> &Arc<State> {
+        match self.tabs.get(index).and_then(|t| t.as_ref()) {
+            Some(tab) => &tab.board.state,
+            None => &self.board.state,
+        }
+    }
+
+    /// Replaces active tab's state, returning the previous one
+    fn swap_tab(&mut self, tab: BoardTab) -> BoardTab {
+        self.selection_drag = None;
+        self.route_start = None;
+        self.route_cache = None;
+        self.wire_drag_pos = None;
+        BoardTab {
+            board: std::mem::replace(&mut self.board, tab.board),
+            pan_zoom: std::mem::replace(&mut self.pan_zoom, tab.pan_zoom),
+            history: std::mem::replace(&mut self.history, tab.history),
+            selection: std::mem::replace(&mut self.selection, tab.selection),
+        }
+    }
+
+    fn switch_tab(&mut self, index: usize) {
+        if index == self.active_tab {
+            return;
+        }
+        let tab = self.tabs.get_mut(index).and_then(|t| t.take());
+        let mut tab = unwrap_option_or_return!(tab);
+
+        // Node caches and history are per tab, so they're stale if the board was edited in
+        // another tab
+        if tab.board.is_outdated() {
+            let editable = EditableCircuitBoard::new(tab.board.state.clone());
+            tab = BoardTab {
+                pan_zoom: tab.pan_zoom,
+                ..BoardTab::new(editable)
+            };
+        }
+        let previous = self.swap_tab(tab);
+        self.tabs[self.active_tab] = Some(previous);
+        self.active_tab = index;
+    }
+
+    /// Last tab can't be closed
+    fn close_tab(&mut self, index: usize) {
+        if self.tabs.len() <= 1 || index >= self.tabs.len() {
+            return;
+        }
+        if index == self.active_tab {
+            self.switch_tab(if index > 0 { index - 1 } else { index + 1 });
+        }
+        self.tabs.remove(index);
+        if index < self.active_tab {
+            self.active_tab -= 1;
+        }
+    }
+
+    /// Closes tabs of a removed board, opening `fallback` if no other tabs remain
+    fn close_board_tabs(&mut self, uid: u128, fallback: Arc<CircuitBoard>) {
+        for index in (0..self.tabs.len()).rev() {
+            if self.tab_state(index).board.uid == uid {
+                self.close_tab(index);
+            }
+        }
+        if self.board.board.uid == uid {
+            self.reset_tabs(fallback);
+        }
+    }
+
+    /// Board name, with instance name for states of nested boards
+    fn state_label(state: &State) -> String {
+        let name = state.board.name.read().get_arc();
+        match state.get_parent() {
+            None => name.to_string(),
+            Some(parent) => match parent.circuit.name() {
+                Some(instance) => format!("{name} ({instance})"),
+                None => format!("{name} (at {}, {})", parent.circuit.pos.x, parent.circuit.pos.y),
+            },
+        }
+    }
+
+    fn tabs_ui(&mut self, ui: &mut Ui) {
+        let mut switch = None;
+        let mut close = None;
+        ui.horizontal_wrapped(|ui| {
+            for index in 0..self.tabs.len() {
+                let label = Self::state_label(self.tab_state(index));
+                if ui.selectable_label(index == self.active_tab, label).clicked() {
+                    switch = Some(index);
+                }
+                if self.tabs.len() > 1
+                    && ui.small_button("×").on_hover_text("Close tab").clicked()
+                {
+                    close = Some(index);
+                }
+                ui.separator();
+            }
+        });
+
+        // States of boards this one is nested in, outermost first
+        let mut path = vec![];
+        let mut parent = self.board.state.get_parent();
+        while let Some(p) = parent {
+            parent = p.state.get_parent();
+            path.push(p.state);
+        }
+        path.reverse();
+
+        let mut open = None;
+        if !path.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                for state in path {
+                    if ui.link(Self::state_label(&state)).clicked() {
+                        open = Some(state);
+                    }
+                    ui.label("›");
+                }
+                ui.strong(Self::state_label(&self.board.state));
+            });
+        }
+
+        if let Some(index) = switch {
+            self.switch_tab(index);
+        }
+        if let Some(index) = close {
+            self.close_tab(index);
+        }
+        if let Some(state) = open {
+            self.open_state(state);
+        }
+    }
+
+    pub fn show_keybinds(&mut self, editing: bool) {
+        self.keybinds_window.open(editing);
+    }
+
+    fn zoom_to_fit(&mut self, screen_rect: Rect) {
+        if let Some(bounds) = self.board.bounds() {
+            self.pan_zoom.fit(screen_rect, bounds.expand(1.0));
+        }
+    }
+
+    fn zoom_to_selection(&mut self, screen_rect: Rect) {
+        if let Some(bounds) = self.selection_bounds() {
+            self.pan_zoom.fit(screen_rect, bounds.expand(1.0));
+        }
+    }
+
+    /// Area of selected objects in tiles
+    fn selection_bounds(&self) -> Option<Rect> {
+        let circuits = self.board.board.circuits.read();
+        let mut bounds: Option<Rect> = None;
+        for object in self.selection.selection.iter() {
+            let (tl, br) = match object {
+                SelectedBoardObject::WirePart { pos, dir } => {
+                    let node = self.board.find_wire_node(*pos, (*dir).into());
+                    let end = node.map_or(*pos, |n| n.pos);
+                    (
+                        Vec2i::from([pos.x.min(end.x), pos.y.min(end.y)]),
+                        Vec2i::from([pos.x.max(end.x), pos.y.max(end.y)]),
+                    )
+                }
+                SelectedBoardObject::Circuit { id } => {
+                    let circuit = unwrap_option_or_continue!(circuits.get(*id));
+                    let size = circuit.info.read().size.convert(|v| v as i32);
+                    (circuit.pos, circuit.pos + size - 1)
+                }
+            };
+            let rect = Rect::from_min_max(
+                pos2(tl.x as f32, tl.y as f32),
+                pos2(br.x as f32 + 1.0, br.y as f32 + 1.0),
+            );
+            bounds = Some(bounds.map_or(rect, |b| b.union(rect)));
+        }
+        bounds
+    }
+
+    /// Overlay in the bottom-right corner of `area` with board contents and current view.
+    /// Clicking or dragging on it moves the view
+    fn minimap_ui(&mut self, ui: &Ui, area: Rect, screen_rect: Rect) {
+        const MINIMAP_SIZE: Vec2 = vec2(200.0, 150.0);
+
+        let view = self.pan_zoom.to_screen(screen_rect).screen_to_world_rect(screen_rect);
+        let bounds = self.board.bounds();
+        let world = bounds.map_or(view, |b| b.union(view)).expand(4.0);
+
+        let scale = (MINIMAP_SIZE / world.size()).min_elem();
+        let size = world.size() * scale;
+        let rect = Rect::from_min_size(area.right_bottom() - size, size);
+        let minimap = PanAndZoom::new(world.center().into(), scale).to_screen(rect);
+
+        egui::Area::new(ui.id().with("minimap"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(rect.left_top())
+            .show(ui.ctx(), |ui| {
+                let (response, painter) = ui.allocate_painter(size, Sense::click_and_drag());
+                if response.clicked() || response.dragged() {
+                    if let Some(pos) = response.interact_pointer_pos() {
+                        self.pan_zoom.center_pos = minimap.screen_to_world(pos.into());
+                    }
+                }
+
+                let visuals = ui.visuals();
+                painter.rect(
+                    rect,
+                    Rounding::same(3.0),
+                    visuals.extreme_bg_color.gamma_multiply(0.85),
+                    visuals.window_stroke(),
+                );
+                if let Some(bounds) = bounds {
+                    painter.rect_filled(
+                        minimap.world_to_screen_rect(bounds),
+                        Rounding::ZERO,
+                        visuals.widgets.inactive.bg_fill,
+                    );
+                }
+                for circuit in self.board.board.circuits.read().iter() {
+                    let pos = circuit.pos.convert(|v| v as f32);
+                    let size = circuit.info.read().size.convert(|v| v as f32);
+                    let circuit_rect = Rect::from_min_size(pos2(pos.x, pos.y), size.into());
+                    painter.rect_filled(
+                        minimap.world_to_screen_rect(circuit_rect),
+                        Rounding::ZERO,
+                        visuals.widgets.active.bg_fill,
+                    );
+                }
+                painter.rect_stroke(
+                    minimap.world_to_screen_rect(view).intersect(rect),
+                    Rounding::ZERO,
+                    visuals.selection.stroke,
+                );
+            });
+    }
+
+    /// Opens board containing search target, selects the target and moves view to it
+    fn jump_to(&mut self, target: SearchTarget, screen_rect: Rect) {
+        if target.board() != self.board.board.uid {
+            let board = self.sim.boards.read().get(&target.board()).map(|b| b.board.clone());
+            let board = unwrap_option_or_return!(board);
+            self.open_board(board);
+        }
+        self.selection.clear();
+
+        let (pos, size) = match target {
+            SearchTarget::Board(_) => return,
+            SearchTarget::Circuit { circuit, .. } => {
+                let circuit = self.board.board.circuits.read().get(circuit).cloned();
+                let circuit = unwrap_option_or_return!(circuit);
+                self.selection
+                    .selection
+                    .insert(SelectedBoardObject::Circuit { id: circuit.id });
+                let size = circuit.info.read().size;
+                (circuit.pos, size.convert(|v| v as i32))
+            }
+            SearchTarget::Wire { wire, .. } => {
+                let wires = self.board.board.wires.read();
+                let wire = unwrap_option_or_return!(wires.get(wire));
+                let parts = self.wire_parts(wire);
+                let points = wire.points.keys().copied();
+                let min = points.clone().reduce(|a, b| [a.x.min(b.x), a.y.min(b.y)].into());
+                let max = points.reduce(|a, b| [a.x.max(b.x), a.y.max(b.y)].into());
+                drop(wires);
+
+                self.selection.selection.extend(
+                    parts
+                        .iter()
+                        .map(|p| SelectedBoardObject::WirePart { pos: p.pos, dir: p.dir }),
+                );
+                let (min, max) = unwrap_option_or_return!(min.zip(max));
+                (min, max - min + 1)
+            }
+        };
+        self.selected_id = Some(SelectedItemId::Selection);
+        self.pan_zoom.focus(
+            screen_rect,
+            pos.convert(|v| v as f32),
+            size.convert(|v| v as f32),
+        );
+    }
+
+    /// Circuit name or type with pin name
+    fn pin_label(&self, pin: &CircuitPin) -> String {
+        let circuits = self.board.board.circuits.read();
+        let circuit = circuits.get(pin.id.circuit_id);
+        let name = circuit.and_then(|c| {
+            c.name().map(|n| n.to_string()).or_else(|| {
+                self.sim.previews.get(&c.ty).map(|p| p.imp.display_name().to_string())
+            })
+        });
+        format!("{}: {}", name.as_deref().unwrap_or("?"), pin.name().deref())
+    }
+
     fn draw_hovered_circuit_pin_names(&self, ctx: &PaintContext) {
         let mouse_tile_pos = ctx
             .ui
@@ -1237,6 +1924,204 @@ impl CircuitBoardEditor {
         }
     }
 
+    fn image_export_filter(&self) -> ImageExportFilter {
+        let mut filter = ImageExportFilter::default();
+        for object in self.selection.selection.iter() {
+            match object {
+                SelectedBoardObject::Circuit { id } => {
+                    filter.circuits.insert(*id);
+                }
+                SelectedBoardObject::WirePart { pos, dir } => {
+                    let node = self.board.find_wire_node(*pos, (*dir).into());
+                    let node = unwrap_option_or_continue!(node);
+                    for x in pos.x.min(node.pos.x)..=pos.x.max(node.pos.x) {
+                        for y in pos.y.min(node.pos.y)..=pos.y.max(node.pos.y) {
+                            filter.wire_tiles.insert([x, y].into());
+                        }
+                    }
+                }
+            }
+        }
+        filter
+    }
+
+    /// World bounds of everything drawn, [`None`] if there's nothing
+    fn image_export_bounds(&self, filter: Option<&ImageExportFilter>) -> Option<Rect> {
+        let mut bounds: Option<Rect> = None;
+        let mut add = |pos: Vec2i, size: Vec2u| {
+            let rect = Rect::from_min_size(
+                Vec2f::from(pos.convert(|v| v as f32)).into(),
+                Vec2f::from(size.convert(|v| v as f32)).into(),
+            );
+            bounds = Some(bounds.map_or(rect, |bounds| bounds.union(rect)));
+        };
+
+        for circuit in self.board.board.circuits.read().iter() {
+            if filter.is_some_and(|f| !f.circuits.contains(&circuit.id)) {
+                continue;
+            }
+            add(circuit.pos, circuit.info.read().size);
+        }
+        match filter {
+            Some(filter) => filter.wire_tiles.iter().for_each(|pos| add(*pos, 1.into())),
+            None => {
+                for wire in self.board.board.wires.read().iter() {
+                    wire.points.keys().for_each(|pos| add(*pos, 1.into()));
+                }
+            }
+        }
+        bounds
+    }
+
+    /// Draws given world bounds with an offscreen [`Ui`]
+    fn capture_image(
+        options: &ImageExportOptions,
+        style: &Style,
+        bounds: Rect,
+        draw: impl FnOnce(&PaintContext),
+    ) -> CapturedImage {
+        let bounds = bounds.expand(1.0);
+        let size = bounds.size() * options.scale;
+        CapturedImage::capture(&style.egui_style, size, |ui| {
+            let rect = Rect::from_min_size(Pos2::ZERO, size);
+            let screen = Screen {
+                scr_rect: rect,
+                wld_pos: bounds.min.into(),
+                scale: options.scale,
+            };
+            let paint = ui.painter_at(rect);
+            paint.rect_filled(rect, Rounding::ZERO, ui.visuals().panel_fill);
+            if options.grid {
+                drawing::draw_dynamic_grid(&screen, style, 16.0, 16.into(), &paint);
+            }
+
+            let ctx = PaintContext {
+                screen,
+                style,
+                paint: &paint,
+                rect,
+                ui,
+            };
+            draw(&ctx);
+        })
+    }
+
+    fn draw_board_image(&self, ctx: &PaintContext, filter: Option<&ImageExportFilter>) {
+        let bounds = self.calc_draw_bounds(&ctx.screen);
+        ctx.draw_chunks(
+            bounds,
+            &self.board.wire_nodes,
+            &self,
+            |node| !node.is_empty(),
+            |node, pos, ctx, this, lookaround| {
+                if filter.is_some_and(|f| !f.wire_tiles.contains(&pos)) {
+                    return;
+                }
+                this.draw_wire_node(bounds, ctx, node, pos, lookaround);
+            },
+        );
+        ctx.draw_chunks(
+            bounds,
+            &self.board.circuit_nodes,
+            self,
+            |n| n.circuit.is_some(),
+            |node, pos, ctx, this, _| {
+                let id = node.circuit.get();
+                if filter.is_some_and(|f| id.is_some_and(|id| !f.circuits.contains(&id))) {
+                    return;
+                }
+                this.draw_circuit_node(bounds, node, pos, ctx)
+            },
+        );
+
+        if !self.image_export.pin_names {
+            return;
+        }
+        for circuit in self.board.board.circuits.read().iter() {
+            if filter.is_some_and(|f| !f.circuits.contains(&circuit.id)) {
+                continue;
+            }
+            let info = circuit.info.read();
+            drawing::draw_pin_names(
+                circuit.pos.convert(|v| v as isize),
+                info.pins
+                    .iter()
+                    .map(|pin| (pin.pos, pin.display_name.deref(), pin.display_dir)),
+                0.5,
+                0.5,
+                ctx,
+            );
+        }
+    }
+
+    fn capture_design_image(
+        board: &Arc<CircuitBoard>,
+        options: &ImageExportOptions,
+        style: &Style,
+    ) -> CapturedImage {
+        let preview = BoardPreview::new_from_board(board.clone());
+        let preview = CircuitPreview::new(Box::new(preview), CircuitPropertyStore::default());
+        let description = preview.describe();
+        let size = Vec2f::from(description.size.convert(|v| v as f32));
+        let rect = Rect::from_min_size(Pos2::ZERO, size.into());
+
+        // Extra space for pin names
+        Self::capture_image(options, style, rect.expand(1.0), |ctx| {
+            preview.draw(&ctx.with_rect(ctx.screen.world_to_screen_rect(rect)), false);
+            if options.pin_names {
+                drawing::draw_pin_names(
+                    0.into(),
+                    description
+                        .pins
+                        .iter()
+                        .map(|pin| (pin.pos, pin.display_name.deref(), pin.display_dir)),
+                    0.5,
+                    0.5,
+                    ctx,
+                );
+            }
+        })
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    fn export_image(&mut self, board: &Arc<CircuitBoard>, target: ImageExportTarget, style: &Style) {
+        let name = board.name.read().get_str().to_string();
+        let fd = rfd::FileDialog::new()
+            .set_title("Export image")
+            .set_file_name(format!("{name}.svg"))
+            .add_filter("SVG image", &["svg"])
+            .add_filter("PNG image", &["png"]);
+        let path = unwrap_option_or_return!(fd.save_file());
+
+        let image = match target {
+            ImageExportTarget::Design => {
+                Some(Self::capture_design_image(board, &self.image_export, style))
+            }
+            ImageExportTarget::Board | ImageExportTarget::Selection => {
+                let filter = (target == ImageExportTarget::Selection)
+                    .then(|| self.image_export_filter());
+                self.image_export_bounds(filter.as_ref()).map(|bounds| {
+                    Self::capture_image(&self.image_export, style, bounds, |ctx| {
+                        self.draw_board_image(ctx, filter.as_ref())
+                    })
+                })
+            }
+        };
+
+        let mut errors = self.errors.enter_context(|| "exporting image");
+        let image = unwrap_option_or_return!(image.report_none(&mut errors, || "Nothing to export"));
+        let png = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+        let data = match png {
+            true => image.to_png().report_error(&mut errors),
+            false => Some(image.to_svg().into_bytes()),
+        };
+        if let Some(data) = data {
+            std::fs::write(path, data).report_error(&mut errors);
+        }
+    }
+
     fn calc_draw_bounds(&self, screen: &Screen) -> TileDrawBounds {
         let chunk_size: Vec2f = (screen.scale * 16.0).into();
 
@@ -1289,9 +2174,18 @@ impl CircuitBoardEditor {
                 vec.push((circuit_id, old))
             }
             drop(circuits);
+            let mut edits = vec![];
             for (circuit, old) in vec {
-                self.board
-                    .circuit_property_changed(circuit, id, old.as_ref());
+                if self
+                    .board
+                    .circuit_property_changed(circuit, id, old.as_ref())
+                {
+                    let edit = BoardEdit::property_changed(&self.board, circuit, id, old.as_ref());
+                    edits.extend(edit);
+                }
+            }
+            if let Some(edit) = BoardEdit::group(edits) {
+                self.history.push(edit);
             }
         }
     }
@@ -1335,6 +2229,7 @@ impl CircuitBoardEditor {
                 vec.push((circuit.clone(), old))
             }
             drop(circuits);
+            let mut edits = vec![];
             for (circuit, [oldw, oldh]) in vec {
                 let width = self
                     .board
@@ -1357,6 +2252,356 @@ impl CircuitBoardEditor {
                         .circuit_property_changed(circuit.id, "width", oldh.as_ref()); // width was successfully set to height before
                     continue;
                 }
+
+                for (property, old) in [("width", oldw), ("height", oldh)] {
+                    let edit =
+                        BoardEdit::property_changed(&self.board, circuit.id, property, old.as_ref());
+                    edits.extend(edit);
+                }
+            }
+            if let Some(edit) = BoardEdit::group(edits) {
+                self.history.push(edit);
+            }
+        }
+    }
+
+    fn apply_history(&mut self, action: HistoryAction) {
+        let mut errors = self.errors.enter_context(|| "applying edit history");
+        match action {
+            HistoryAction::Undo => {
+                if let Some(edit) = self.history.undo() {
+                    edit.undo(&mut self.board, &self.sim, &mut errors);
+                }
+            }
+            HistoryAction::Redo => {
+                if let Some(edit) = self.history.redo() {
+                    edit.redo(&mut self.board, &self.sim, &mut errors);
+                }
+            }
+        }
+        drop(errors);
+
+        // Circuit ids may have changed
+        self.selection.clear();
+    }
+
+    /// Selected circuits and wire parts
+    fn selection_objects(&self) -> Option<BoardObjects> {
+        let mut circuits = vec![];
+        let mut wires = vec![];
+        for obj in self.selection.selection.iter() {
+            match obj {
+                SelectedBoardObject::WirePart { pos, dir } => {
+                    let node = self.board.find_wire_node(*pos, (*dir).into());
+                    let node = unwrap_option_or_continue!(node);
+                    let colors = self.board.board.wires.read().get(node.wire).map(|w| w.colors);
+                    let part = WirePart {
+                        pos: *pos,
+                        length: node.distance,
+                        dir: *dir,
+                    };
+                    wires.push((part, colors.unwrap_or_default()));
+                }
+                SelectedBoardObject::Circuit { id } => circuits.push(*id),
+            }
+        }
+        BoardObjects::new(&self.board, circuits, wires)
+    }
+
+    /// Returns objects that were placed
+    fn place_paste(&mut self, paste: &PastePreview, pos: Vec2i) -> Option<BoardObjects> {
+        if !paste.can_place(&self.board, pos) {
+            return None;
+        }
+
+        let wires: Vec<_> = paste
+            .wires
+            .iter()
+            .filter_map(|w| {
+                NonZeroU32::new(w.length).map(|length| {
+                    let part = WirePart {
+                        pos: pos + w.pos.convert(|v| v as i32),
+                        length,
+                        dir: w.dir,
+                    };
+                    (part, w.colors)
+                })
+            })
+            .flat_map(|(part, colors)| {
+                history::uncovered_wire_parts(&self.board, &part)
+                    .into_iter()
+                    .map(move |p| (p, colors))
+            })
+            .collect();
+
+        paste.place(&mut self.board, pos, &mut self.errors);
+
+        let circuits: Vec<_> = paste
+            .circuits
+            .iter()
+            .filter_map(|(c, _)| self.board.circuit_at(pos + c.pos.convert(|v| v as i32)))
+            .collect();
+        BoardObjects::new(&self.board, circuits, wires)
+    }
+
+    /// Replaces selected objects with their transformed copies.
+    /// Wires connecting them to the rest of the board are routed to their new position.
+    /// Selection is left as is if transformed objects don't fit
+    fn transform_selection(&mut self, transform: GroupTransform) {
+        let objects = unwrap_option_or_return!(self.selection_objects());
+        let anchors = self.selection_anchors(&objects);
+        let mut points: Vec<_> = anchors.iter().map(|(pos, _)| *pos).collect();
+        let mut errors = self.errors.enter_context(|| "transforming selection");
+        let transformed = objects.transformed(transform, &mut points, &self.sim, &mut errors);
+        drop(errors);
+        let transformed = unwrap_option_or_return!(transformed);
+        let links = anchors
+            .into_iter()
+            .zip(points)
+            .map(|((from, colors), to)| (from, to, colors))
+            .collect();
+        self.replace_selection(objects, transformed, links, true);
+    }
+
+    /// Moves selected objects by `offset` tiles.
+    /// Wires connecting them to the rest of the board are stretched to their new position
+    fn move_selection(&mut self, offset: Vec2i) {
+        let objects = unwrap_option_or_return!(self.selection_objects());
+        let links = self
+            .selection_anchors(&objects)
+            .into_iter()
+            .map(|(pos, colors)| (pos, pos + offset, colors))
+            .collect();
+        let moved = BoardObjects {
+            origin: objects.origin + offset,
+            data: objects.data.clone(),
+        };
+        self.replace_selection(objects, moved, links, false);
+    }
+
+    /// Replaces selected `objects` with `new` ones and connects `links` after them,
+    /// with routed wires if `route` is set and routing succeeds, with rubber bands otherwise.
+    /// Nothing is changed if new objects don't fit
+    fn replace_selection(
+        &mut self,
+        objects: BoardObjects,
+        new: BoardObjects,
+        links: Vec<(Vec2i, Vec2i, WireColors)>,
+        route: bool,
+    ) {
+        let mut errors = self.errors.enter_context(|| "replacing selection");
+        let paste = PastePreview::new(new.data.clone(), &self.sim, &mut errors);
+        drop(errors);
+
+        let removed = BoardEdit::Removed(objects);
+        removed.redo(&mut self.board, &self.sim, &mut self.errors);
+
+        if !paste.can_place(&self.board, new.origin) {
+            removed.undo(&mut self.board, &self.sim, &mut self.errors);
+            return;
+        }
+        let placed = self.place_paste(&paste, new.origin);
+
+        let mut edits = vec![removed];
+        edits.extend(placed.map(BoardEdit::Placed));
+        for (from, to, colors) in links {
+            let routed = route
+                .then(|| routing::route(&self.board, from, to, self.routing))
+                .flatten();
+            let wires = routed.unwrap_or_else(|| {
+                Self::rubber_band_parts(from, to)
+                    .into_iter()
+                    .flatten()
+                    .collect()
+            });
+            for part in wires {
+                let parts = history::uncovered_wire_parts(&self.board, &part);
+                self.board.place_wire_part(part, true, colors);
+                edits.extend(BoardEdit::wires_placed(&self.board, parts, colors));
+            }
+        }
+
+        if let Some(edit) = BoardEdit::group(edits) {
+            self.history.push(edit);
+        }
+        self.select_objects(&new);
+    }
+
+    /// Points of `objects` connected to wires or circuit pins that aren't in `objects`
+    fn selection_anchors(&self, objects: &BoardObjects) -> Vec<(Vec2i, WireColors)> {
+        let mut covered = HashSet::new();
+        let mut points = vec![];
+        for wire in objects.data.wires.iter() {
+            let dir = Direction4::from(wire.dir);
+            let pos = objects.origin + wire.pos.convert(|v| v as i32);
+            let end = dir.move_vector(pos, wire.length as i32);
+            covered.insert((pos, dir));
+            covered.insert((end, dir.inverted()));
+            points.extend([pos, end]);
+        }
+
+        let mut circuits = HashSet::new();
+        let board_circuits = self.board.board.circuits.read();
+        for circuit in objects.data.circuits.iter() {
+            let pos = objects.origin + circuit.pos.convert(|v| v as i32);
+            let id = self.board.circuit_at(pos);
+            let circuit = id.and_then(|id| board_circuits.get(id));
+            let circuit = unwrap_option_or_continue!(circuit);
+            circuits.insert(circuit.id);
+            let info = circuit.info.read();
+            points.extend(info.pins.iter().map(|p| pos + p.pos.convert(|v| v as i32)));
+        }
+        drop(board_circuits);
+
+        let wires = self.board.board.wires.read();
+        let mut anchors = vec![];
+        for pos in points {
+            let node = self.board.wire_nodes.get(pos.convert(|v| v as isize));
+            let node = unwrap_option_or_continue!(node);
+            let wire = node.wire.get().and_then(|w| wires.get(w));
+            let wire = unwrap_option_or_continue!(wire);
+
+            let outside_wire = Direction4::iter_all()
+                .any(|dir| node.get_dir(dir).is_some() && !covered.contains(&(pos, dir)));
+            let outside_circuit = self
+                .board
+                .circuit_nodes
+                .get(pos.convert(|v| v as isize))
+                .and_then(|n| n.circuit.get())
+                .is_some_and(|c| !circuits.contains(&c));
+
+            if (outside_wire || outside_circuit) && !anchors.iter().any(|(p, _)| *p == pos) {
+                anchors.push((pos, wire.colors));
+            }
+        }
+        anchors
+    }
+
+    /// Orthogonal path between two points, horizontal part first
+    fn rubber_band_parts(from: Vec2i, to: Vec2i) -> [Option<WirePart>; 2] {
+        let corner = [to.x, from.y].into();
+        [
+            EditableCircuitBoard::calc_wire_part(Some(from), Some(corner)),
+            EditableCircuitBoard::calc_wire_part(Some(corner), Some(to)),
+        ]
+    }
+
+    /// Whether tile is covered by a selected circuit or wire part
+    fn selection_contains_tile(&self, tile: Vec2i) -> bool {
+        let circuit = self
+            .board
+            .circuit_nodes
+            .get(tile.convert(|v| v as isize))
+            .and_then(|n| n.circuit.get());
+        if let Some(id) = circuit {
+            return self
+                .selection
+                .selection
+                .contains(&SelectedBoardObject::Circuit { id });
+        }
+
+        self.selection.selection.iter().any(|obj| match obj {
+            SelectedBoardObject::WirePart { pos, dir } => self
+                .board
+                .find_wire_node(*pos, (*dir).into())
+                .is_some_and(|node| {
+                    let part = WirePart {
+                        pos: *pos,
+                        length: node.distance,
+                        dir: *dir,
+                    };
+                    part.iter_pos(true).any(|p| p == tile)
+                }),
+            SelectedBoardObject::Circuit { .. } => false,
+        })
+    }
+
+    fn update_selection_drag(&mut self, ctx: &PaintContext, selected: bool) {
+        if !selected {
+            self.selection_drag = None;
+            return;
+        }
+
+        let tile_pos = |pos: Option<Pos2>| {
+            pos.map(|p| {
+                let pos = ctx.screen.screen_to_world(Vec2f::from(p));
+                pos.convert(|v| v.floor() as i32)
+            })
+        };
+        let (press_tile, mouse_tile, modifiers) = ctx.ui.input(|input| {
+            (
+                tile_pos(input.pointer.press_origin()),
+                tile_pos(input.pointer.interact_pos()),
+                input.modifiers.shift || input.modifiers.command,
+            )
+        });
+
+        let interaction = ctx
+            .ui
+            .interact(ctx.rect, ctx.ui.id(), Sense::click_and_drag());
+
+        if self.selection_drag.is_none() && interaction.drag_started_by(PointerButton::Primary) {
+            let start = press_tile.filter(|t| !modifiers && self.selection_contains_tile(*t));
+            let start = unwrap_option_or_return!(start);
+            let objects = unwrap_option_or_return!(self.selection_objects());
+
+            let anchors = self.selection_anchors(&objects);
+            let mut errors = self.errors.enter_context(|| "moving selection");
+            self.selection_drag = Some(SelectionDrag {
+                start,
+                origin: objects.origin,
+                anchors,
+                paste: PastePreview::new(objects.data, &self.sim, &mut errors),
+            });
+            return;
+        }
+
+        let drag = unwrap_option_or_return!(&self.selection_drag);
+        let offset = mouse_tile.map_or(Vec2i::default(), |t| t - drag.start);
+
+        if interaction.drag_released_by(PointerButton::Primary) {
+            self.selection_drag = None;
+            if offset != Vec2i::default() {
+                self.move_selection(offset);
+            }
+            return;
+        }
+
+        let pos = drag.origin + offset;
+        let rect = Rect::from_min_size(
+            ctx.screen.world_to_screen_tile(pos).into(),
+            (drag.paste.size.convert(|v| v as f32) * ctx.screen.scale).into(),
+        );
+        drag.paste.draw(self, pos, &ctx.with_rect(rect));
+        for (anchor, _) in drag.anchors.iter() {
+            for part in Self::rubber_band_parts(*anchor, *anchor + offset).iter().flatten() {
+                self.draw_wire_part(ctx, part, Color32::GRAY);
+            }
+        }
+    }
+
+    /// Selects objects that were placed on the board
+    fn select_objects(&mut self, objects: &BoardObjects) {
+        self.selection.clear();
+        for circuit in objects.data.circuits.iter() {
+            let id = self.board.circuit_at(objects.origin + circuit.pos.convert(|v| v as i32));
+            let id = unwrap_option_or_continue!(id);
+            self.selection.selection.insert(SelectedBoardObject::Circuit { id });
+        }
+
+        for wire in objects.data.wires.iter() {
+            let mut pos = objects.origin + wire.pos.convert(|v| v as i32);
+            let mut remaining = wire.length;
+            while remaining > 0 {
+                let node = self.board.find_wire_node(pos, wire.dir.into());
+                let node = unwrap_option_or_break!(node);
+                if node.distance.get() > remaining {
+                    break;
+                }
+                let part = SelectedBoardObject::WirePart { pos, dir: wire.dir };
+                self.selection.selection.insert(part);
+                pos = node.pos;
+                remaining -= node.distance.get();
             }
         }
     }
@@ -1366,6 +2611,7 @@ impl CircuitBoardEditor {
             SelectedItemId::Paste => self.paste.as_ref().map(|p| SelectedItem::Paste(p.clone())),
             SelectedItemId::Selection => Some(SelectedItem::Selection),
             SelectedItemId::Wires => Some(SelectedItem::Wire),
+            SelectedItemId::Router => Some(SelectedItem::Router),
             SelectedItemId::Circuit(circ) => self
                 .sim
                 .previews
@@ -1430,7 +2676,37 @@ impl CircuitBoardEditor {
             }
         }
 
-        fn boards_ui(this: &mut CircuitBoardEditor, ui: &mut Ui) -> Option<Designer> {
+        #[cfg(not(feature = "wasm"))]
+        fn export_verilog(board: &Arc<CircuitBoard>, errors: &mut ErrorList) {
+            let name = board.name.read().get_str().to_string();
+            let fd = rfd::FileDialog::new()
+                .set_title("Export Verilog")
+                .set_file_name(format!("{name}.v"))
+                .add_filter("Verilog", &["v"]);
+            let path = crate::unwrap_option_or_return!(fd.save_file());
+            let mut errors = errors.enter_context(|| "exporting Verilog");
+            let verilog = crate::io::verilog::export(board, &mut errors);
+            std::fs::write(path, verilog).report_error(&mut errors);
+        }
+
+        #[cfg(not(feature = "wasm"))]
+        fn export_netlist(board: &Arc<CircuitBoard>, errors: &mut ErrorList) {
+            let name = board.name.read().get_str().to_string();
+            let fd = rfd::FileDialog::new()
+                .set_title("Export netlist")
+                .set_file_name(format!("{name}.json"))
+                .add_filter("JSON netlist", &["json"]);
+            let path = crate::unwrap_option_or_return!(fd.save_file());
+            let mut errors = errors.enter_context(|| "exporting netlist");
+            let netlist = crate::io::netlist::export(board, &mut errors);
+            std::fs::write(path, netlist).report_error(&mut errors);
+        }
+
+        fn boards_ui(
+            this: &mut CircuitBoardEditor,
+            style: &Style,
+            ui: &mut Ui,
+        ) -> Option<Designer> {
             let renamer_memory_id = ui.id().with("__renamer_memory");
             let renamer_id = ui.id().with("__renamer_input");
             let rename = ui
@@ -1438,8 +2714,11 @@ impl CircuitBoardEditor {
                 .flatten();
 
             let mut queued_deletion = None;
+            let mut open_request = None;
             let mut drawn_renamer = false;
             let mut designer_request = None;
+            #[cfg(not(feature = "wasm"))]
+            let mut image_request = None;
             let no_delete = this.sim.boards.read().len() <= 1;
             for board in this.sim.boards.read().values() {
                 if Some(board.board.uid) == rename && !drawn_renamer {
@@ -1475,7 +2754,7 @@ impl CircuitBoardEditor {
                     }
 
                     if resp.double_clicked_by(egui::PointerButton::Primary) && !active {
-                        this.board = EditableCircuitBoard::new_main(board.board.clone());
+                        open_request = Some(board.board.clone());
                         if selected {
                             this.selected_id = None;
                         }
@@ -1483,7 +2762,7 @@ impl CircuitBoardEditor {
 
                     resp.context_menu(|ui| {
                         if ui.button("Edit").clicked() {
-                            this.board = EditableCircuitBoard::new_main(board.board.clone());
+                            open_request = Some(board.board.clone());
                             if selected {
                                 this.selected_id = None;
                             }
@@ -1497,6 +2776,44 @@ impl CircuitBoardEditor {
                             ui.close_menu();
                         }
 
+                        #[cfg(not(feature = "wasm"))]
+                        if ui.button("Export Verilog").clicked() {
+                            export_verilog(&board.board, &mut this.errors);
+                            ui.close_menu();
+                        }
+
+                        #[cfg(not(feature = "wasm"))]
+                        if ui.button("Export netlist").clicked() {
+                            export_netlist(&board.board, &mut this.errors);
+                            ui.close_menu();
+                        }
+
+                        #[cfg(not(feature = "wasm"))]
+                        ui.menu_button("Export image", |ui| {
+                            let options = &mut this.image_export;
+                            ui.checkbox(&mut options.grid, "Grid");
+                            ui.checkbox(&mut options.pin_names, "Pin names");
+                            ui.add(
+                                egui::DragValue::new(&mut options.scale)
+                                    .clamp_range(4.0..=64.0)
+                                    .suffix(" px per tile"),
+                            );
+                            ui.separator();
+
+                            let has_selection = !this.selection.selection.is_empty();
+                            let targets = [
+                                ("Board", ImageExportTarget::Board, active),
+                                ("Selection", ImageExportTarget::Selection, active && has_selection),
+                                ("Design", ImageExportTarget::Design, true),
+                            ];
+                            for (name, target, enabled) in targets {
+                                if ui.add_enabled(enabled, egui::Button::new(name)).clicked() {
+                                    image_request = Some((board.board.clone(), target));
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+
                         if ui.button("Rename").clicked() {
                             // same hack as below
                             if !drawn_renamer {
@@ -1538,7 +2855,7 @@ impl CircuitBoardEditor {
                     .boards
                     .write()
                     .insert(uid, StoredCircuitBoard::new(board.clone()));
-                this.board = EditableCircuitBoard::new_main(board);
+                this.open_board(board);
 
                 // HACK: widget must exist before `request_focus` can be called on its id, panics otherwise
                 if !drawn_renamer {
@@ -1551,15 +2868,25 @@ impl CircuitBoardEditor {
                 });
             }
 
+            #[cfg(not(feature = "wasm"))]
+            if let Some((board, target)) = image_request {
+                this.export_image(&board, target, style);
+            }
+            #[cfg(feature = "wasm")]
+            let _ = style;
+
             if let Some(uid) = queued_deletion {
                 let mut boards = this.sim.boards.write();
                 if let Some(board) = boards.remove(&uid) {
                     board.board.destroy();
                 };
-                if this.board.board.uid == uid {
-                    let board = boards.values().next().expect("Boards must exist!");
-                    this.board = EditableCircuitBoard::new_main(board.board.clone());
-                }
+                let fallback = boards.values().next().expect("Boards must exist!").board.clone();
+                drop(boards);
+                this.close_board_tabs(uid, fallback);
+            }
+
+            if let Some(board) = open_request {
+                this.open_board(board);
             }
 
             designer_request
@@ -1578,7 +2905,7 @@ impl CircuitBoardEditor {
 
                     let boards_resp = CollapsingHeader::new("Circuit boards")
                         .default_open(true)
-                        .show(ui, |ui| boards_ui(this, ui));
+                        .show(ui, |ui| boards_ui(this, style, ui));
 
                     ComponentsUiResponse {
                         designer_request: boards_resp.body_returned.flatten(),
@@ -1617,7 +2944,9 @@ impl CircuitBoardEditor {
         let selected_item = self.selected_item();
         let selected_item = if selected_item
             .as_ref()
-            .is_some_and(|s| matches!(s, SelectedItem::Circuit(_) | SelectedItem::Wire))
+            .is_some_and(|s| {
+                matches!(s, SelectedItem::Circuit(_) | SelectedItem::Wire | SelectedItem::Router)
+            })
         {
             selected_item
         } else {
@@ -1675,10 +3004,20 @@ impl CircuitBoardEditor {
                                 }
                             }
                             SelectedItem::Wire => Self::wire_color_properties(style, ui, [&mut self.wire_colors]),
+                            SelectedItem::Router => {
+                                ui.checkbox(&mut self.routing.avoid_wires, "Avoid crossing wires");
+                                ui.checkbox(&mut self.routing.prefer_fewer_bends, "Prefer fewer bends");
+                                ui.separator();
+                                Self::wire_color_properties(style, ui, [&mut self.wire_colors]);
+                            }
                         }
                     } else if let Some(obj) = selected_object {
                         match obj {
-                            SelectedObjectId::Circuit => self.selected_circuit_properties(ui),
+                            SelectedObjectId::Circuit => {
+                                self.selected_circuit_properties(ui);
+                                self.expression_actions_ui(ui);
+                                self.board_actions_ui(ui);
+                            }
                             SelectedObjectId::Wire => {
                                 let mut wire_ids = BTreeSet::new();
                                 for object in self.selection.selection.iter() {
@@ -1730,6 +3069,74 @@ impl CircuitBoardEditor {
         }
     }
 
+    fn expression_actions_ui(&mut self, ui: &mut Ui) {
+        if self.selection.selection.len() != 1 {
+            return;
+        }
+        let id = match self.selection.selection.iter().next() {
+            Some(SelectedBoardObject::Circuit { id }) => *id,
+            _ => return,
+        };
+        let circuit = self.board.board.circuits.read().get(id).cloned();
+        let circuit = unwrap_option_or_return!(circuit);
+        if circuit.ty.deref() != "expression" {
+            return;
+        }
+
+        ui.separator();
+        let expand = ui
+            .button("Expand into gates")
+            .on_hover_text("Places gates computing this expression below it")
+            .clicked();
+        if expand {
+            let result =
+                crate::circuits::expression::expand_into_gates(&mut self.board, &self.sim, &circuit);
+            match result {
+                Ok((circuits, wires)) => {
+                    let wires = wires.into_iter().map(|p| (p, WireColors::default()));
+                    if let Some(objects) = BoardObjects::new(&self.board, circuits, wires) {
+                        self.history.push(BoardEdit::Placed(objects));
+                    }
+                }
+                Err(e) => self
+                    .errors
+                    .enter_context(|| "expanding expression")
+                    .push_error(e),
+            }
+        }
+    }
+
+    fn board_actions_ui(&mut self, ui: &mut Ui) {
+        if self.selection.selection.len() != 1 {
+            return;
+        }
+        let id = match self.selection.selection.iter().next() {
+            Some(SelectedBoardObject::Circuit { id }) => *id,
+            _ => return,
+        };
+        let circuit = self.board.board.circuits.read().get(id).cloned();
+        let circuit = unwrap_option_or_return!(circuit);
+        let definition = circuit.read_imp(|b: &Board| b.inner_board().cloned()).flatten();
+        let definition = unwrap_option_or_return!(definition);
+
+        ui.separator();
+        if ui.button("Open definition").clicked() {
+            self.open_board(definition);
+        }
+        let open_state = ui
+            .button("Open instance state")
+            .on_hover_text("Opens inner board with signals of this instance")
+            .clicked();
+        if open_state {
+            let state = CircuitStateContext::new(self.board.state.clone(), circuit)
+                .read_circuit_internal_state(|s: &BoardState| s.inner_state().cloned())
+                .flatten();
+            if let Some(state) = state {
+                self.open_state(state);
+            }
+        }
+    }
+
     fn selected_circuit_properties(&mut self, ui: &mut Ui) {
         let selected_circuit_props = self.selection.selection.iter().filter_map(|o| match o {
             SelectedBoardObject::Circuit { id } => Some(*id),
@@ -1743,6 +3150,7 @@ impl CircuitBoardEditor {
 
         let response = self.props_ui.ui(ui, stores);
 
+        let mut edits = vec![];
         for property in response.changes {
             let str_arc = property
                 .new
@@ -1776,9 +3184,22 @@ impl CircuitBoardEditor {
                 }
             }
             for (circuit, old) in property.affected_values {
-                self.board
-                    .circuit_property_changed(circuit, &property.id, old.as_ref());
+                if self
+                    .board
+                    .circuit_property_changed(circuit, &property.id, old.as_ref())
+                {
+                    edits.extend(BoardEdit::property_changed(
+                        &self.board,
+                        circuit,
+                        &property.id,
+                        old.as_ref(),
+                    ));
+                }
             }
         }
+        drop(circuits);
+        if let Some(edit) = BoardEdit::group(edits) {
+            self.history.push(edit);
+        }
     }
 }
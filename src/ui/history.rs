@@ -0,0 +1,359 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    num::NonZeroU32,
+    sync::Arc,
+};
+
+use serde_intermediate::Intermediate;
+
+use crate::{
+    app::SimulationContext,
+    board::EditableCircuitBoard,
+    circuits::props::CircuitPropertyImpl,
+    error::ErrorList,
+    io::{CopyPasteData, WirePartCopyData},
+    vector::Vec2i,
+    wires::{WireColors, WirePart},
+    Direction4, DynStaticStr, PastePreview,
+};
+
+/// Default memory budget of a single history, in bytes
+pub const DEFAULT_MEMORY_BUDGET: usize = 16 * 1024 * 1024;
+
+pub trait HistoryEntry {
+    /// Approximate memory used by this entry, in bytes
+    fn memory_size(&self) -> usize;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HistoryAction {
+    Undo,
+    Redo,
+}
+
+/// Undo/redo stacks. Oldest entries are dropped when their total size exceeds the memory budget
+pub struct History<T> {
+    undo: VecDeque<(T, usize)>,
+    redo: Vec<(T, usize)>,
+    memory: usize,
+    budget: usize,
+}
+
+impl<T: HistoryEntry> History<T> {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: vec![],
+            memory: 0,
+            budget,
+        }
+    }
+
+    /// Records a new entry, discarding everything that could be redone
+    pub fn push(&mut self, entry: T) {
+        for (_, size) in self.redo.drain(..) {
+            self.memory -= size;
+        }
+
+        let size = entry.memory_size();
+        self.memory += size;
+        self.undo.push_back((entry, size));
+
+        while self.memory > self.budget && self.undo.len() > 1 {
+            if let Some((_, size)) = self.undo.pop_front() {
+                self.memory -= size;
+            }
+        }
+    }
+
+    /// Returns entry that should be undone
+    pub fn undo(&mut self) -> Option<&T> {
+        let entry = self.undo.pop_back()?;
+        self.redo.push(entry);
+        self.redo.last().map(|(e, _)| e)
+    }
+
+    /// Returns entry that should be redone
+    pub fn redo(&mut self) -> Option<&T> {
+        let entry = self.redo.pop()?;
+        self.undo.push_back(entry);
+        self.undo.back().map(|(e, _)| e)
+    }
+}
+
+impl<T: HistoryEntry> Default for History<T> {
+    fn default() -> Self {
+        Self::new(DEFAULT_MEMORY_BUDGET)
+    }
+}
+
+/// Circuits and wire parts, stored relative to `origin`
+pub struct BoardObjects {
+    pub origin: Vec2i,
+    pub data: CopyPasteData,
+}
+
+impl BoardObjects {
+    pub fn new(
+        board: &EditableCircuitBoard,
+        circuits: impl IntoIterator<Item = usize>,
+        wires: impl IntoIterator<Item = (WirePart, WireColors)>,
+    ) -> Option<Self> {
+        let board_circuits = board.board.circuits.read();
+        let circuits: Vec<_> = circuits
+            .into_iter()
+            .filter_map(|id| board_circuits.get(id).cloned())
+            .collect();
+        drop(board_circuits);
+        let wires: Vec<_> = wires.into_iter().collect();
+
+        let origin = circuits
+            .iter()
+            .map(|c| c.pos)
+            .chain(
+                wires
+                    .iter()
+                    .map(|(p, _)| p.dir.move_vector(p.pos, p.length.get() as i32, true)),
+            )
+            .reduce(|a, b| [a.x.min(b.x), a.y.min(b.y)].into())?;
+
+        let data = CopyPasteData {
+            wires: wires
+                .into_iter()
+                .map(|(part, colors)| WirePartCopyData {
+                    pos: (part.pos - origin).convert(|v| v as u32),
+                    length: part.length.get(),
+                    colors,
+                    dir: part.dir,
+                })
+                .collect(),
+            circuits: circuits
+                .iter()
+                .map(|c| c.copy((c.pos - origin).convert(|v| v as u32), &board.state))
+                .collect(),
+        };
+        Some(Self { origin, data })
+    }
+
+    fn place(
+        &self,
+        board: &mut EditableCircuitBoard,
+        ctx: &Arc<SimulationContext>,
+        errors: &mut ErrorList,
+    ) {
+        PastePreview::new(self.data.clone(), ctx, errors).place(board, self.origin, errors);
+    }
+
+    fn remove(&self, board: &mut EditableCircuitBoard) {
+        let sim_lock = { board.board.sim_lock.clone() };
+        let sim_lock = sim_lock.write();
+
+        let mut affected_wires = HashSet::new();
+        for circuit in self.data.circuits.iter() {
+            let pos = self.origin + circuit.pos.convert(|v| v as i32);
+            let id = board.circuit_at(pos);
+            let id = crate::unwrap_option_or_continue!(id);
+            board.remove_circuit(id, &mut affected_wires);
+        }
+
+        for wire in self.data.wires.iter() {
+            let dir = Direction4::from(wire.dir);
+            let mut pos = self.origin + wire.pos.convert(|v| v as i32);
+            let mut remaining = wire.length;
+            while remaining > 0 {
+                let node = board.find_wire_node(pos, dir);
+                let node = crate::unwrap_option_or_break!(node);
+                if node.distance.get() > remaining {
+                    break;
+                }
+                if let Some(wire) = board.remove_wire_part(pos, dir, true, false) {
+                    affected_wires.insert(wire);
+                }
+                pos = node.pos;
+                remaining -= node.distance.get();
+            }
+        }
+
+        for wire in affected_wires {
+            board.board.states.update_wire(wire, true);
+        }
+        drop(sim_lock);
+    }
+
+    fn memory_size(&self) -> usize {
+        std::mem::size_of::<Self>() + ron::to_string(&self.data).map_or(0, |s| s.len())
+    }
+}
+
+pub enum BoardEdit {
+    /// Objects were placed on the board
+    Placed(BoardObjects),
+    /// Objects were removed from the board
+    Removed(BoardObjects),
+    PropertyChanged {
+        circuit: Vec2i,
+        property: DynStaticStr,
+        old: Intermediate,
+        new: Intermediate,
+    },
+    /// Toggling is its own inverse
+    IntersectionToggled(Vec2i),
+    /// Edits done by a single action
+    Group(Vec<BoardEdit>),
+}
+
+impl BoardEdit {
+    /// Wire placement, only parts that weren't already on the board are recorded
+    pub fn wires_placed(
+        board: &EditableCircuitBoard,
+        parts: impl IntoIterator<Item = WirePart>,
+        colors: WireColors,
+    ) -> Option<Self> {
+        let wires = parts.into_iter().map(|p| (p, colors));
+        BoardObjects::new(board, [], wires).map(Self::Placed)
+    }
+
+    /// Must be called after property was successfully changed
+    pub fn property_changed(
+        board: &EditableCircuitBoard,
+        circuit: usize,
+        property: &str,
+        old: &dyn CircuitPropertyImpl,
+    ) -> Option<Self> {
+        let circuit = board.board.circuits.read().get(circuit).cloned()?;
+        let new = circuit.props.read_dyn(property, |p| p.imp().save())?;
+        Some(Self::PropertyChanged {
+            circuit: circuit.pos,
+            property: property.to_owned().into(),
+            old: old.save(),
+            new,
+        })
+    }
+
+    /// Combines edits into one, None if there are no edits
+    pub fn group(mut edits: Vec<BoardEdit>) -> Option<Self> {
+        match edits.len() {
+            0 => None,
+            1 => edits.pop(),
+            _ => Some(Self::Group(edits)),
+        }
+    }
+
+    pub fn undo(
+        &self,
+        board: &mut EditableCircuitBoard,
+        ctx: &Arc<SimulationContext>,
+        errors: &mut ErrorList,
+    ) {
+        match self {
+            BoardEdit::Placed(objects) => objects.remove(board),
+            BoardEdit::Removed(objects) => objects.place(board, ctx, errors),
+            BoardEdit::PropertyChanged {
+                circuit,
+                property,
+                old,
+                ..
+            } => Self::set_property(board, *circuit, property, old),
+            BoardEdit::IntersectionToggled(pos) => {
+                board.try_toggle_node_intersection(*pos);
+            }
+            BoardEdit::Group(edits) => {
+                for edit in edits.iter().rev() {
+                    edit.undo(board, ctx, errors);
+                }
+            }
+        }
+    }
+
+    pub fn redo(
+        &self,
+        board: &mut EditableCircuitBoard,
+        ctx: &Arc<SimulationContext>,
+        errors: &mut ErrorList,
+    ) {
+        match self {
+            BoardEdit::Placed(objects) => objects.place(board, ctx, errors),
+            BoardEdit::Removed(objects) => objects.remove(board),
+            BoardEdit::PropertyChanged {
+                circuit,
+                property,
+                new,
+                ..
+            } => Self::set_property(board, *circuit, property, new),
+            BoardEdit::IntersectionToggled(pos) => {
+                board.try_toggle_node_intersection(*pos);
+            }
+            BoardEdit::Group(edits) => {
+                for edit in edits.iter() {
+                    edit.redo(board, ctx, errors);
+                }
+            }
+        }
+    }
+
+    fn set_property(
+        board: &mut EditableCircuitBoard,
+        pos: Vec2i,
+        property: &str,
+        value: &Intermediate,
+    ) {
+        let id = board.circuit_at(pos);
+        let id = crate::unwrap_option_or_return!(id);
+        let circuit = board.board.circuits.read().get(id).cloned();
+        let circuit = crate::unwrap_option_or_return!(circuit);
+
+        let old = circuit.props.write_dyn(property, |p| {
+            let old = p.imp().clone();
+            p.imp_mut().load(value);
+            old
+        });
+        let old = crate::unwrap_option_or_return!(old);
+        board.circuit_property_changed(id, property, old.as_ref());
+    }
+}
+
+impl HistoryEntry for BoardEdit {
+    fn memory_size(&self) -> usize {
+        let inner = match self {
+            BoardEdit::Placed(objects) | BoardEdit::Removed(objects) => objects.memory_size(),
+            BoardEdit::PropertyChanged { old, new, .. } => {
+                ron::to_string(old).map_or(0, |s| s.len())
+                    + ron::to_string(new).map_or(0, |s| s.len())
+            }
+            BoardEdit::IntersectionToggled(_) => 0,
+            BoardEdit::Group(edits) => edits.iter().map(|e| e.memory_size()).sum(),
+        };
+        std::mem::size_of::<Self>() + inner
+    }
+}
+
+/// Parts of `part` that aren't covered by wires on the board
+pub fn uncovered_wire_parts(board: &EditableCircuitBoard, part: &WirePart) -> Vec<WirePart> {
+    let dir = Direction4::from(part.dir);
+    let mut parts = vec![];
+    let mut run: Option<(Vec2i, u32)> = None;
+
+    for pos in part.iter_pos(true).take(part.length.get() as usize) {
+        let covered = board
+            .wire_nodes
+            .get(pos.convert(|v| v as isize))
+            .is_some_and(|n| n.get_dir(dir).is_some());
+
+        match (&mut run, covered) {
+            (Some((_, length)), false) => *length += 1,
+            (None, false) => run = Some((pos, 1)),
+            (Some(_), true) => parts.extend(run.take().map(|(pos, length)| WirePart {
+                pos,
+                length: NonZeroU32::new(length).unwrap(),
+                dir: part.dir,
+            })),
+            (None, true) => {}
+        }
+    }
+    parts.extend(run.map(|(pos, length)| WirePart {
+        pos,
+        length: NonZeroU32::new(length).unwrap(),
+        dir: part.dir,
+    }));
+    parts
+}
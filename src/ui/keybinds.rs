@@ -0,0 +1,338 @@
+use std::collections::BTreeMap;
+
+use eframe::egui::{self, Event, Grid, InputState, Key, KeyboardShortcut, Modifiers, Ui};
+use emath::Align2;
+use serde::{Deserialize, Serialize};
+
+/// Editor actions that can be bound to keyboard shortcuts
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum EditorAction {
+    Undo,
+    Redo,
+    Search,
+    Delete,
+    Rotate,
+    RotateCounterclockwise,
+    Flip,
+    FlipVertical,
+    NudgeUp,
+    NudgeLeft,
+    NudgeDown,
+    NudgeRight,
+    ZoomToFit,
+    ZoomToSelection,
+    ToggleMinimap,
+    ToggleFrozen,
+    ToggleOrderedQueue,
+    ResetState,
+    ResetBoardStates,
+    ResetAllStates,
+    ReloadBoard,
+    ToggleDebug,
+    ShowKeybinds,
+}
+
+impl EditorAction {
+    pub const ALL: [EditorAction; 23] = [
+        EditorAction::Undo,
+        EditorAction::Redo,
+        EditorAction::Search,
+        EditorAction::Delete,
+        EditorAction::Rotate,
+        EditorAction::RotateCounterclockwise,
+        EditorAction::Flip,
+        EditorAction::FlipVertical,
+        EditorAction::NudgeUp,
+        EditorAction::NudgeLeft,
+        EditorAction::NudgeDown,
+        EditorAction::NudgeRight,
+        EditorAction::ZoomToFit,
+        EditorAction::ZoomToSelection,
+        EditorAction::ToggleMinimap,
+        EditorAction::ToggleFrozen,
+        EditorAction::ToggleOrderedQueue,
+        EditorAction::ResetState,
+        EditorAction::ResetBoardStates,
+        EditorAction::ResetAllStates,
+        EditorAction::ReloadBoard,
+        EditorAction::ToggleDebug,
+        EditorAction::ShowKeybinds,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EditorAction::Undo => "Undo",
+            EditorAction::Redo => "Redo",
+            EditorAction::Search => "Search",
+            EditorAction::Delete => "Delete selection",
+            EditorAction::Rotate => "Rotate",
+            EditorAction::RotateCounterclockwise => "Rotate selection counterclockwise",
+            EditorAction::Flip => "Flip / mirror selection horizontally",
+            EditorAction::FlipVertical => "Mirror selection vertically",
+            EditorAction::NudgeUp => "Move selection up",
+            EditorAction::NudgeLeft => "Move selection left",
+            EditorAction::NudgeDown => "Move selection down",
+            EditorAction::NudgeRight => "Move selection right",
+            EditorAction::ZoomToFit => "Zoom to fit board",
+            EditorAction::ZoomToSelection => "Zoom to selection",
+            EditorAction::ToggleMinimap => "Toggle minimap",
+            EditorAction::ToggleFrozen => "Freeze or unfreeze current state",
+            EditorAction::ToggleOrderedQueue => "Toggle ordered queue",
+            EditorAction::ResetState => "Reset current state",
+            EditorAction::ResetBoardStates => "Reset all states of the current board",
+            EditorAction::ResetAllStates => "Reset all states of all boards",
+            EditorAction::ReloadBoard => "Reload board",
+            EditorAction::ToggleDebug => "Toggle debug view",
+            EditorAction::ShowKeybinds => "Show keyboard shortcuts",
+        }
+    }
+
+    pub fn default_shortcut(self) -> Option<KeyboardShortcut> {
+        let (modifiers, key) = match self {
+            EditorAction::Undo => (Modifiers::COMMAND, Key::Z),
+            EditorAction::Redo => (Modifiers::COMMAND | Modifiers::SHIFT, Key::Z),
+            EditorAction::Search => (Modifiers::COMMAND, Key::F),
+            EditorAction::Delete => (Modifiers::NONE, Key::Delete),
+            EditorAction::Rotate => (Modifiers::NONE, Key::R),
+            EditorAction::RotateCounterclockwise => (Modifiers::SHIFT, Key::R),
+            EditorAction::Flip => (Modifiers::NONE, Key::F),
+            EditorAction::FlipVertical => (Modifiers::SHIFT, Key::F),
+            EditorAction::NudgeUp => (Modifiers::NONE, Key::ArrowUp),
+            EditorAction::NudgeLeft => (Modifiers::NONE, Key::ArrowLeft),
+            EditorAction::NudgeDown => (Modifiers::NONE, Key::ArrowDown),
+            EditorAction::NudgeRight => (Modifiers::NONE, Key::ArrowRight),
+            EditorAction::ZoomToFit => (Modifiers::NONE, Key::Home),
+            EditorAction::ZoomToSelection => (Modifiers::SHIFT, Key::Home),
+            EditorAction::ToggleMinimap => (Modifiers::NONE, Key::M),
+            EditorAction::ToggleFrozen => (Modifiers::NONE, Key::P),
+            EditorAction::ToggleOrderedQueue => (Modifiers::NONE, Key::Q),
+            EditorAction::ResetState => (Modifiers::NONE, Key::F2),
+            EditorAction::ResetBoardStates => (Modifiers::COMMAND, Key::F2),
+            EditorAction::ResetAllStates => (Modifiers::SHIFT, Key::F2),
+            EditorAction::ReloadBoard => (Modifiers::NONE, Key::F8),
+            EditorAction::ToggleDebug => (Modifiers::NONE, Key::F9),
+            EditorAction::ShowKeybinds => (Modifiers::NONE, Key::F1),
+        };
+        Some(KeyboardShortcut::new(modifiers, key))
+    }
+}
+
+/// Shortcuts handled by the platform, these can't be rebound
+const FIXED_SHORTCUTS: [(&str, KeyboardShortcut); 3] = [
+    ("Copy", KeyboardShortcut::new(Modifiers::COMMAND, Key::C)),
+    ("Cut", KeyboardShortcut::new(Modifiers::COMMAND, Key::X)),
+    ("Paste", KeyboardShortcut::new(Modifiers::COMMAND, Key::V)),
+];
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Keybinds {
+    /// Unbound actions are kept as `None`, so they don't get their defaults back on load
+    binds: BTreeMap<EditorAction, Option<KeyboardShortcut>>,
+}
+
+impl Default for Keybinds {
+    fn default() -> Self {
+        Self {
+            binds: BTreeMap::new(),
+        }
+        .with_defaults()
+    }
+}
+
+impl Keybinds {
+    /// Fills in default shortcuts of actions missing from saved keybinds
+    pub fn with_defaults(mut self) -> Self {
+        for action in EditorAction::ALL {
+            self.binds
+                .entry(action)
+                .or_insert_with(|| action.default_shortcut());
+        }
+        self
+    }
+
+    pub fn get(&self, action: EditorAction) -> Option<KeyboardShortcut> {
+        self.binds.get(&action).copied().flatten()
+    }
+
+    /// Modifiers must match exactly, so Shift+R doesn't also trigger R
+    fn pressed(&self, input: &InputState, action: EditorAction) -> bool {
+        self.get(action).is_some_and(|shortcut| {
+            input.modifiers.matches_exact(shortcut.modifiers)
+                && input.key_pressed(shortcut.logical_key)
+        })
+    }
+
+    pub fn pressed_actions(&self, input: &InputState) -> Vec<EditorAction> {
+        EditorAction::ALL
+            .into_iter()
+            .filter(|a| self.pressed(input, *a))
+            .collect()
+    }
+
+    pub fn format(&self, ctx: &egui::Context, action: EditorAction) -> String {
+        self.get(action)
+            .map_or_else(|| "Unbound".into(), |s| ctx.format_shortcut(&s))
+    }
+
+    /// Names of other actions and fixed shortcuts using the same shortcut as `action`
+    pub fn conflicts(&self, action: EditorAction) -> Vec<&'static str> {
+        let shortcut = match self.get(action) {
+            Some(shortcut) => shortcut,
+            None => return vec![],
+        };
+        let actions = EditorAction::ALL
+            .into_iter()
+            .filter(|a| *a != action && self.get(*a) == Some(shortcut))
+            .map(EditorAction::name);
+        let fixed = FIXED_SHORTCUTS
+            .into_iter()
+            .filter(|(_, s)| *s == shortcut)
+            .map(|(name, _)| name);
+        actions.chain(fixed).collect()
+    }
+
+    pub fn has_conflicts(&self) -> bool {
+        EditorAction::ALL
+            .into_iter()
+            .any(|a| !self.conflicts(a).is_empty())
+    }
+}
+
+/// Cheat-sheet of all shortcuts, which can also be used to rebind them
+#[derive(Default)]
+pub struct KeybindsWindow {
+    open: bool,
+    editing: bool,
+    /// Action waiting for a key press to be bound to
+    capturing: Option<EditorAction>,
+}
+
+impl KeybindsWindow {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.capturing = None;
+    }
+
+    pub fn open(&mut self, editing: bool) {
+        self.open = true;
+        self.editing = editing;
+    }
+
+    /// Whether keyboard input should go to this window instead of the editor
+    pub fn is_capturing(&self) -> bool {
+        self.open && self.capturing.is_some()
+    }
+
+    pub fn ui(&mut self, ctx: &egui::Context, keybinds: &mut Keybinds) {
+        if !self.open {
+            return;
+        }
+
+        if let Some(action) = self.capturing {
+            let pressed = ctx.input(|input| {
+                input.events.iter().find_map(|e| match e {
+                    Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => Some(KeyboardShortcut::new(*modifiers, *key)),
+                    _ => None,
+                })
+            });
+            if let Some(shortcut) = pressed {
+                if shortcut.logical_key != Key::Escape {
+                    keybinds.binds.insert(action, Some(shortcut));
+                }
+                self.capturing = None;
+            }
+        }
+
+        let mut open = self.open;
+        egui::Window::new("Keyboard shortcuts")
+            .anchor(Align2::CENTER_CENTER, emath::vec2(0.0, 0.0))
+            .collapsible(false)
+            .resizable(false)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.editing, "Edit");
+                    if self.editing && ui.button("Reset all").clicked() {
+                        *keybinds = Keybinds::default();
+                        self.capturing = None;
+                    }
+                });
+                if keybinds.has_conflicts() {
+                    ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        "Some actions share the same shortcut",
+                    );
+                }
+                ui.separator();
+                self.keybinds_grid(ui, keybinds);
+            });
+        self.open = open;
+        if !open {
+            self.capturing = None;
+        }
+    }
+
+    fn keybinds_grid(&mut self, ui: &mut Ui, keybinds: &mut Keybinds) {
+        Grid::new("keybinds").striped(true).show(ui, |ui| {
+            for action in EditorAction::ALL {
+                ui.label(action.name());
+
+                let text = match self.capturing == Some(action) {
+                    true => "Press a key…".into(),
+                    false => keybinds.format(ui.ctx(), action),
+                };
+                let conflicts = keybinds.conflicts(action);
+                let text = match conflicts.is_empty() {
+                    true => egui::RichText::new(text),
+                    false => egui::RichText::new(text).color(ui.visuals().error_fg_color),
+                };
+
+                if self.editing {
+                    let response = ui.selectable_label(self.capturing == Some(action), text);
+                    if response.clicked() {
+                        self.capturing = Some(action);
+                    }
+                    if self.capturing == Some(action) {
+                        response.request_focus();
+                    }
+                    let response = match conflicts.is_empty() {
+                        true => response,
+                        false => response
+                            .on_hover_text(format!("Also used by: {}", conflicts.join(", "))),
+                    };
+                    response.on_hover_text("Click and press a new shortcut, Escape to cancel");
+
+                    ui.horizontal(|ui| {
+                        if ui.small_button("Unbind").clicked() {
+                            keybinds.binds.insert(action, None);
+                        }
+                        let default = keybinds.get(action) == action.default_shortcut();
+                        if ui
+                            .add_enabled(!default, egui::Button::new("Reset").small())
+                            .clicked()
+                        {
+                            keybinds.binds.insert(action, action.default_shortcut());
+                        }
+                    });
+                } else {
+                    let response = ui.label(text);
+                    if !conflicts.is_empty() {
+                        response.on_hover_text(format!("Also used by: {}", conflicts.join(", ")));
+                    }
+                }
+                ui.end_row();
+            }
+
+            for (name, shortcut) in FIXED_SHORTCUTS {
+                ui.label(name);
+                ui.weak(ui.ctx().format_shortcut(&shortcut));
+                ui.end_row();
+            }
+        });
+    }
+}
@@ -13,8 +13,13 @@ use serde::{Deserialize, Serialize};
 pub mod designer;
 pub mod drawing;
 pub mod editor;
+pub mod history;
+pub mod keybinds;
+pub mod search;
 pub mod selection;
 pub mod side_panel;
+pub mod theme;
+pub mod transform;
 
 use crate::{
     app::Style,
@@ -775,7 +780,7 @@ impl Sides {
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct RectVisuals {
     pub rounding: Rounding,
     pub fill: Color32,
@@ -0,0 +1,209 @@
+use std::ops::Deref;
+
+use eframe::egui::{self, Key, ScrollArea, TextEdit, Ui};
+use emath::{vec2, Align2};
+
+use crate::{app::SimulationContext, board::EditableCircuitBoard, state::WireState, ArcString};
+
+/// Maximum number of shown results
+const RESULT_LIMIT: usize = 100;
+
+#[derive(Clone, Copy)]
+pub enum SearchTarget {
+    Board(u128),
+    Circuit { board: u128, circuit: usize },
+    Wire { board: u128, wire: usize },
+}
+
+impl SearchTarget {
+    pub fn board(self) -> u128 {
+        match self {
+            SearchTarget::Board(board)
+            | SearchTarget::Circuit { board, .. }
+            | SearchTarget::Wire { board, .. } => board,
+        }
+    }
+}
+
+struct SearchResult {
+    text: String,
+    detail: String,
+    target: SearchTarget,
+}
+
+/// Ctrl+F palette searching circuits, boards and erroring wires
+#[derive(Default)]
+pub struct SearchPalette {
+    open: bool,
+    focus: bool,
+    query: String,
+    error_wires: bool,
+    selected: usize,
+}
+
+impl SearchPalette {
+    pub fn open(&mut self) {
+        self.open = true;
+        self.focus = true;
+        self.selected = 0;
+    }
+
+    /// Returns picked search result
+    pub fn ui(
+        &mut self,
+        ui: &Ui,
+        sim: &SimulationContext,
+        current: &EditableCircuitBoard,
+    ) -> Option<SearchTarget> {
+        if !self.open {
+            return None;
+        }
+
+        let results = self.search(sim, current);
+        if !results.is_empty() {
+            self.selected = self.selected.min(results.len() - 1);
+        }
+
+        let (up, down, enter, escape) = ui.input(|input| {
+            (
+                input.key_pressed(Key::ArrowUp),
+                input.key_pressed(Key::ArrowDown),
+                input.key_pressed(Key::Enter),
+                input.key_pressed(Key::Escape),
+            )
+        });
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down && self.selected + 1 < results.len() {
+            self.selected += 1;
+        }
+
+        let mut picked = enter.then_some(self.selected);
+        let mut open = !escape;
+        egui::Window::new("Search")
+            .anchor(Align2::CENTER_TOP, vec2(0.0, 40.0))
+            .collapsible(false)
+            .resizable(false)
+            .open(&mut open)
+            .show(ui.ctx(), |ui| {
+                let edit = TextEdit::singleline(&mut self.query)
+                    .hint_text("Circuit type, name, label or board")
+                    .show(ui);
+                if self.focus {
+                    edit.response.request_focus();
+                    self.focus = false;
+                }
+                if edit.response.changed() {
+                    self.selected = 0;
+                }
+                ui.checkbox(&mut self.error_wires, "Wires in error state");
+                ui.separator();
+
+                ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for (i, result) in results.iter().enumerate() {
+                        let text = format!("{} — {}", result.text, result.detail);
+                        let response = ui.selectable_label(i == self.selected, text);
+                        if i == self.selected && (up || down) {
+                            response.scroll_to_me(None);
+                        }
+                        if response.clicked() {
+                            picked = Some(i);
+                        }
+                    }
+                    if results.is_empty() {
+                        ui.weak("Nothing found");
+                    }
+                });
+            });
+
+        let target = picked.and_then(|i| results.get(i)).map(|r| r.target);
+        self.open = open && target.is_none();
+        target
+    }
+
+    fn search(&self, sim: &SimulationContext, current: &EditableCircuitBoard) -> Vec<SearchResult> {
+        let mut results = vec![];
+        let query = self.query.trim().to_lowercase();
+        let matches = |str: &str| str.to_lowercase().contains(&query);
+
+        if self.error_wires {
+            for wire in current.board.wires.read().iter() {
+                if !matches!(current.state.get_wire(wire.id), WireState::Error) {
+                    continue;
+                }
+                let pos = wire.points.keys().min_by_key(|p| (p.y, p.x));
+                let detail = match pos {
+                    Some(pos) => format!("error at {}, {}", pos.x, pos.y),
+                    None => "error".into(),
+                };
+                results.push(SearchResult {
+                    text: format!("Wire {}", wire.id),
+                    detail,
+                    target: SearchTarget::Wire {
+                        board: current.board.uid,
+                        wire: wire.id,
+                    },
+                });
+            }
+        }
+
+        if query.is_empty() {
+            return results;
+        }
+
+        let boards = sim.boards.read();
+        let mut boards: Vec<_> = boards.values().map(|b| b.board.clone()).collect();
+        // Current board first
+        boards.sort_by_key(|b| b.uid != current.board.uid);
+
+        for board in boards {
+            let board_name = board.name.read().get_arc();
+            if matches(&board_name) {
+                results.push(SearchResult {
+                    text: board_name.to_string(),
+                    detail: "board".into(),
+                    target: SearchTarget::Board(board.uid),
+                });
+            }
+
+            for circuit in board.circuits.read().iter() {
+                let display_name = sim
+                    .previews
+                    .get(&circuit.ty)
+                    .map(|p| p.imp.display_name().deref().to_owned());
+                let name = circuit.name();
+                let text = circuit
+                    .props
+                    .read("text", |t: &ArcString| t.get_arc())
+                    .filter(|t| !t.is_empty());
+
+                let found = matches(&circuit.ty)
+                    || display_name.as_deref().is_some_and(matches)
+                    || name.as_deref().is_some_and(matches)
+                    || text.as_deref().is_some_and(matches);
+                if !found {
+                    continue;
+                }
+
+                let display_name = display_name.unwrap_or_else(|| circuit.ty.deref().to_owned());
+                results.push(SearchResult {
+                    text: name
+                        .or(text)
+                        .map_or_else(|| display_name.clone(), |n| n.to_string()),
+                    detail: format!(
+                        "{display_name} at {}, {} in {board_name}",
+                        circuit.pos.x, circuit.pos.y
+                    ),
+                    target: SearchTarget::Circuit {
+                        board: board.uid,
+                        circuit: circuit.id,
+                    },
+                });
+            }
+        }
+
+        results.truncate(RESULT_LIMIT);
+        results
+    }
+}
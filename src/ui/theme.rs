@@ -0,0 +1,205 @@
+use std::sync::Arc;
+
+use eframe::{
+    egui::{Button, Grid, Ui, Visuals},
+    epaint::{Color32, Stroke},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{app::Style, wires::WireColors};
+
+/// Board canvas colors. Unset ones are derived from egui visuals
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct CanvasColors {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub background: Option<Color32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub grid: Option<Color32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub grid_highlight: Option<Color32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub origin_cross: Option<Color32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub component_fill: Option<Color32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub component_border: Option<Color32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub pin: Option<Color32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub pin_label: Option<Color32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub net_highlight: Option<Color32>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ThemePreset {
+    Light,
+    Dark,
+    HighContrast,
+}
+
+impl ThemePreset {
+    pub const ALL: [ThemePreset; 3] = [
+        ThemePreset::Light,
+        ThemePreset::Dark,
+        ThemePreset::HighContrast,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ThemePreset::Light => "Light",
+            ThemePreset::Dark => "Dark",
+            ThemePreset::HighContrast => "High contrast",
+        }
+    }
+
+    /// Preset visuals on top of `base`, which keeps its fonts and spacing
+    pub fn style(self, base: &eframe::egui::Style) -> Style {
+        let (visuals, wire_colors, canvas_colors) = match self {
+            ThemePreset::Light => (Visuals::light(), Default::default(), Default::default()),
+            ThemePreset::Dark => (Visuals::dark(), Default::default(), Default::default()),
+            ThemePreset::HighContrast => (
+                Self::high_contrast_visuals(),
+                WireColors {
+                    none: Some(Color32::from_rgb(80, 150, 255)),
+                    r#false: Some(Color32::from_rgb(120, 255, 120)),
+                    r#true: Some(Color32::from_rgb(0, 170, 0)),
+                    error: Some(Color32::from_rgb(255, 60, 60)),
+                    bundle: Some(Color32::WHITE),
+                },
+                CanvasColors {
+                    background: Some(Color32::BLACK),
+                    grid: Some(Color32::from_gray(70)),
+                    grid_highlight: Some(Color32::from_gray(130)),
+                    origin_cross: Some(Color32::WHITE),
+                    component_fill: Some(Color32::WHITE),
+                    component_border: None,
+                    pin: Some(Color32::YELLOW),
+                    pin_label: Some(Color32::WHITE),
+                    net_highlight: Some(Color32::from_rgb(0, 255, 255)),
+                },
+            ),
+        };
+
+        let mut egui_style = base.clone();
+        egui_style.visuals = visuals;
+        Style {
+            egui_style: Arc::new(egui_style),
+            wire_colors,
+            canvas_colors,
+        }
+    }
+
+    fn high_contrast_visuals() -> Visuals {
+        let mut visuals = Visuals::dark();
+        visuals.override_text_color = Some(Color32::WHITE);
+        visuals.panel_fill = Color32::BLACK;
+        visuals.window_fill = Color32::BLACK;
+        visuals.extreme_bg_color = Color32::BLACK;
+        visuals.faint_bg_color = Color32::from_gray(24);
+        visuals.window_stroke = Stroke::new(1.0, Color32::WHITE);
+        visuals.widgets.noninteractive.bg_stroke = Stroke::new(1.0, Color32::WHITE);
+        visuals.widgets.inactive.bg_stroke = Stroke::new(1.0, Color32::from_gray(200));
+        visuals.selection.bg_fill = Color32::from_rgb(0, 92, 230);
+        visuals.selection.stroke = Stroke::new(2.0, Color32::YELLOW);
+        visuals
+    }
+}
+
+impl Style {
+    /// Editor for canvas and selection colors.
+    /// Changed egui visuals have to be applied to the egui context by the caller
+    pub fn theme_ui(&mut self, ui: &mut Ui) -> bool {
+        let defaults = Style {
+            canvas_colors: Default::default(),
+            ..self.clone()
+        };
+        let mut egui_style = (*self.egui_style).clone();
+        let visuals = &mut egui_style.visuals;
+
+        let changed = Grid::new("theme_ui")
+            .num_columns(3)
+            .show(ui, |ui| {
+                let colors = &mut self.canvas_colors;
+                let mut changed = false;
+                changed |= color_row(
+                    ui,
+                    "Canvas background",
+                    &mut colors.background,
+                    defaults.canvas_background_color(),
+                );
+                changed |= color_row(ui, "Grid", &mut colors.grid, defaults.grid_color());
+                changed |= color_row(
+                    ui,
+                    "Grid highlight",
+                    &mut colors.grid_highlight,
+                    defaults.grid_highlight_color(),
+                );
+                changed |= color_row(
+                    ui,
+                    "Origin cross",
+                    &mut colors.origin_cross,
+                    defaults.origin_cross_color(),
+                );
+                changed |= color_row(
+                    ui,
+                    "Component fill",
+                    &mut colors.component_fill,
+                    defaults.component_fill_color(),
+                );
+                changed |= color_row(
+                    ui,
+                    "Component border",
+                    &mut colors.component_border,
+                    defaults.component_border_color(),
+                );
+                changed |= color_row(ui, "Pins", &mut colors.pin, defaults.pin_color());
+                changed |= color_row(
+                    ui,
+                    "Pin labels",
+                    &mut colors.pin_label,
+                    defaults.pin_label_color(),
+                );
+                changed |= color_row(
+                    ui,
+                    "Net highlight",
+                    &mut colors.net_highlight,
+                    defaults.net_highlight_color(),
+                );
+
+                ui.label("Selection fill");
+                changed |= ui
+                    .color_edit_button_srgba(&mut visuals.selection.bg_fill)
+                    .changed();
+                ui.end_row();
+                ui.label("Selection border");
+                changed |= ui
+                    .color_edit_button_srgba(&mut visuals.selection.stroke.color)
+                    .changed();
+                ui.end_row();
+                changed
+            })
+            .inner;
+
+        if changed {
+            self.egui_style = Arc::new(egui_style);
+        }
+        changed
+    }
+}
+
+fn color_row(ui: &mut Ui, name: &str, color: &mut Option<Color32>, default: Color32) -> bool {
+    ui.label(name);
+    let mut value = color.unwrap_or(default);
+    let mut changed = false;
+    if ui.color_edit_button_srgba(&mut value).changed() {
+        *color = Some(value);
+        changed = true;
+    }
+    if ui.add_enabled(color.is_some(), Button::new("x")).clicked() {
+        *color = None;
+        changed = true;
+    }
+    ui.end_row();
+    changed
+}
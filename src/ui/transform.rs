@@ -0,0 +1,171 @@
+use std::{ops::Deref, sync::Arc};
+
+use crate::{
+    app::SimulationContext,
+    circuits::{props::CircuitPropertyStore, CircuitPreview},
+    error::{ErrorList, OptionReport},
+    io::{CopyPasteData, WirePartCopyData},
+    vector::Vec2i,
+    Direction2, Direction4,
+};
+
+use super::history::BoardObjects;
+
+/// Transforms applied to a group of board objects around its center
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GroupTransform {
+    RotateClockwise,
+    RotateCounterclockwise,
+    /// Swaps left and right sides
+    MirrorHorizontal,
+    /// Swaps top and bottom sides
+    MirrorVertical,
+}
+
+impl GroupTransform {
+    fn is_rotation(self) -> bool {
+        matches!(
+            self,
+            GroupTransform::RotateClockwise | GroupTransform::RotateCounterclockwise
+        )
+    }
+
+    fn size(self, size: Vec2i) -> Vec2i {
+        match self.is_rotation() {
+            true => [size.y, size.x].into(),
+            false => size,
+        }
+    }
+
+    /// Transforms tile position local to the top-left corner of an area of `size`
+    fn tile(self, pos: Vec2i, size: Vec2i) -> Vec2i {
+        match self {
+            GroupTransform::RotateClockwise => [size.y - 1 - pos.y, pos.x],
+            GroupTransform::RotateCounterclockwise => [pos.y, size.x - 1 - pos.x],
+            GroupTransform::MirrorHorizontal => [size.x - 1 - pos.x, pos.y],
+            GroupTransform::MirrorVertical => [pos.x, size.y - 1 - pos.y],
+        }
+        .into()
+    }
+
+    fn dir(self, dir: Direction4) -> Direction4 {
+        match self {
+            GroupTransform::RotateClockwise => dir.rotate_clockwise(),
+            GroupTransform::RotateCounterclockwise => dir.rotate_counterclockwise(),
+            GroupTransform::MirrorHorizontal => dir.inverted_lr(),
+            GroupTransform::MirrorVertical => dir.inverted_ud(),
+        }
+    }
+
+    fn transform_props(self, props: &CircuitPropertyStore) {
+        for id in ["dir", "label_dir"] {
+            props.write(id, |d: &mut Direction4| *d = self.dir(*d));
+        }
+
+        if self.is_rotation() {
+            let width = props.read_dyn("width", |p| p.imp().save());
+            let height = props.read_dyn("height", |p| p.imp().save());
+            if let (Some(width), Some(height)) = (width, height) {
+                props.write_dyn("width", |p| p.imp_mut().load(&height));
+                props.write_dyn("height", |p| p.imp_mut().load(&width));
+            }
+        } else {
+            // Flip mirrors circuits across their direction axis.
+            // Together with direction change this mirrors them across any axis
+            props.write("flip", |f: &mut bool| *f = !*f);
+        }
+    }
+}
+
+impl BoardObjects {
+    /// Objects transformed around their center.
+    /// Circuits that don't change their size with rotation are kept centered on their rotated area.
+    /// World positions in `points` are transformed along with the objects.
+    /// Returns `None` if any circuit can't be loaded, as it would be lost otherwise
+    pub fn transformed(
+        &self,
+        transform: GroupTransform,
+        points: &mut [Vec2i],
+        ctx: &Arc<SimulationContext>,
+        errors: &mut ErrorList,
+    ) -> Option<BoardObjects> {
+        let mut circuits = vec![];
+        for circuit in self.data.circuits.iter() {
+            let preview = CircuitPreview::from_copy(circuit, ctx, errors);
+            let preview = preview.report_none(errors, || {
+                format!("{} can't be transformed", circuit.ty.deref())
+            });
+            let preview = crate::unwrap_option_or_return!(preview, None);
+            let old_size = preview.describe().size.convert(|v| v as i32);
+            transform.transform_props(&preview.props);
+            preview.redescribe();
+            let new_size = preview.describe().size.convert(|v| v as i32);
+
+            let mut data = circuit.clone();
+            data.props = preview.props.save();
+            circuits.push((data, old_size, new_size));
+        }
+
+        let mut size = Vec2i::default();
+        for wire in self.data.wires.iter() {
+            size = [
+                size.x.max(wire.pos.x as i32 + 1),
+                size.y.max(wire.pos.y as i32 + 1),
+            ]
+            .into();
+        }
+        for (circuit, old_size, _) in circuits.iter() {
+            let br = circuit.pos.convert(|v| v as i32) + *old_size;
+            size = [size.x.max(br.x), size.y.max(br.y)].into();
+        }
+        let new_size = transform.size(size);
+
+        let to_local = |pos: Vec2i| pos.convert(|v| v.max(0) as u32);
+
+        let wires = self
+            .data
+            .wires
+            .iter()
+            .map(|wire| {
+                let start = wire.pos.convert(|v| v as i32);
+                let end = wire.dir.move_vector(start, wire.length as i32, true);
+                let start = transform.tile(start, size);
+                let end = transform.tile(end, size);
+                let (pos, dir) = match start.x == end.x {
+                    true => ([start.x, start.y.max(end.y)], Direction2::Up),
+                    false => ([start.x.max(end.x), start.y], Direction2::Left),
+                };
+                WirePartCopyData {
+                    pos: to_local(pos.into()),
+                    length: wire.length,
+                    colors: wire.colors,
+                    dir,
+                }
+            })
+            .collect();
+
+        let circuits = circuits
+            .into_iter()
+            .map(|(mut circuit, old_size, circuit_size)| {
+                let tl = circuit.pos.convert(|v| v as i32);
+                let a = transform.tile(tl, size);
+                let b = transform.tile(tl + old_size - Vec2i::single_value(1), size);
+                let area_tl: Vec2i = [a.x.min(b.x), a.y.min(b.y)].into();
+                let area_size = transform.size(old_size);
+
+                circuit.pos = to_local(area_tl + (area_size - circuit_size) / 2);
+                circuit
+            })
+            .collect();
+
+        let origin = self.origin + (size - new_size) / 2;
+        for point in points.iter_mut() {
+            *point = origin + transform.tile(*point - self.origin, size);
+        }
+
+        Some(BoardObjects {
+            origin,
+            data: CopyPasteData { wires, circuits },
+        })
+    }
+}